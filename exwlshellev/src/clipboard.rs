@@ -0,0 +1,627 @@
+//! Clipboard and primary selection on the connection of the [`WindowState`]
+//!
+//! Both selections are read and written through [`WindowState`]: [`WindowState::set_selection`]
+//! offers data under any number of MIME types, [`WindowState::receive_selection`] asks for the
+//! current selection as one of the types listed by [`WindowState::selection_mime_types`], its
+//! data arrives as [`DispatchMessage::SelectionData`]. Text selections are read ahead when they
+//! change, so [`WindowState::read_selection_text`] answers right away. Reads and writes happen
+//! on the event loop, a slow selection owner or reader never blocks it.
+//!
+//! [`DispatchMessage::SelectionData`]: crate::DispatchMessage::SelectionData
+use std::io::PipeReader;
+use std::os::fd::{AsFd, BorrowedFd, OwnedFd};
+use std::sync::{Arc, Mutex};
+
+use calloop::{Interest, LoopHandle, Mode, PostAction, generic::Generic};
+use wayland_client::{
+    Connection, Dispatch, Proxy, QueueHandle, WEnum,
+    backend::ObjectId,
+    delegate_noop, event_created_child,
+    protocol::{
+        wl_data_device::{self, WlDataDevice},
+        wl_data_device_manager::{DndAction, WlDataDeviceManager},
+        wl_data_offer::{self, WlDataOffer},
+        wl_data_source::{self, WlDataSource},
+    },
+};
+use wayland_protocols::wp::primary_selection::zv1::client::{
+    zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1,
+    zwp_primary_selection_device_v1::{self, ZwpPrimarySelectionDeviceV1},
+    zwp_primary_selection_offer_v1::{self, ZwpPrimarySelectionOfferV1},
+    zwp_primary_selection_source_v1::{self, ZwpPrimarySelectionSourceV1},
+};
+
+use crate::SeatId;
+use crate::WindowState;
+use crate::events::DispatchMessageInner;
+use crate::id::Id;
+
+/// MIME types text is offered as, and looked for when reading text, most preferred first
+pub const TEXT_MIME_TYPES: [&str; 5] = [
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "text/plain",
+    "STRING",
+    "TEXT",
+];
+
+/// The first of [`TEXT_MIME_TYPES`] in `mime_types`
+fn text_mime_type(mime_types: &[String]) -> Option<&'static str> {
+    TEXT_MIME_TYPES
+        .into_iter()
+        .find(|text| mime_types.iter().any(|offered| offered == text))
+}
+
+/// Which selection to read or write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelectionKind {
+    /// The clipboard, filled by explicit copy
+    Clipboard,
+    /// The primary selection, filled by selecting text and pasted with the middle button.
+    /// Needs primary-selection-unstable-v1.
+    Primary,
+}
+
+/// The MIME types an offer was announced with, and the drag and drop action chosen for it
+#[derive(Debug, Default)]
+pub(crate) struct OfferData {
+    mime_types: Mutex<Vec<String>>,
+    action: Mutex<Option<DndAction>>,
+}
+
+impl OfferData {
+    pub(crate) fn mime_types(&self) -> Vec<String> {
+        self.mime_types.lock().unwrap().clone()
+    }
+
+    fn push(&self, mime_type: String) {
+        self.mime_types.lock().unwrap().push(mime_type);
+    }
+
+    /// The action the compositor chose from the ones both sides support, `None` before it did
+    pub(crate) fn action(&self) -> Option<DndAction> {
+        *self.action.lock().unwrap()
+    }
+}
+
+/// Data offered by one of our sources, the same bytes under every MIME type
+#[derive(Debug)]
+pub(crate) struct SourceData {
+    pub(crate) mime_types: Vec<String>,
+    pub(crate) data: Arc<[u8]>,
+}
+
+impl SourceData {
+    /// Write the data for the client that asked for it, on the event loop
+    pub(crate) fn send(&self, fd: OwnedFd) -> PendingWrite {
+        PendingWrite {
+            writer: fd,
+            data: self.data.clone(),
+        }
+    }
+}
+
+/// Current selections of the seat, and the ones we own
+#[derive(Debug, Default)]
+pub(crate) struct SelectionState {
+    clipboard: Option<WlDataOffer>,
+    primary: Option<ZwpPrimarySelectionOfferV1>,
+    /// the wl_seat whose device announced the selection, or that we set it on
+    clipboard_seat: Option<ObjectId>,
+    primary_seat: Option<ObjectId>,
+    /// our own selections are read back without asking the compositor, a read through the pipe
+    /// would wait for ourselves
+    clipboard_source: Option<WlDataSource>,
+    primary_source: Option<ZwpPrimarySelectionSourceV1>,
+    /// the offered selections as text, read ahead when they change
+    clipboard_text: Option<String>,
+    primary_text: Option<String>,
+}
+
+impl SelectionState {
+    fn set_clipboard_offer(&mut self, offer: Option<WlDataOffer>, seat: &ObjectId) {
+        self.clipboard_text = None;
+        self.clipboard_seat = Some(seat.clone());
+        if let Some(old) = std::mem::replace(&mut self.clipboard, offer) {
+            old.destroy();
+        }
+    }
+
+    fn set_primary_offer(&mut self, offer: Option<ZwpPrimarySelectionOfferV1>, seat: &ObjectId) {
+        self.primary_text = None;
+        self.primary_seat = Some(seat.clone());
+        if let Some(old) = std::mem::replace(&mut self.primary, offer) {
+            old.destroy();
+        }
+    }
+
+    /// The offer currently holding the selection of `kind`
+    fn offer_id(&self, kind: SelectionKind) -> Option<ObjectId> {
+        match kind {
+            SelectionKind::Clipboard => self.clipboard.as_ref().map(Proxy::id),
+            SelectionKind::Primary => self.primary.as_ref().map(Proxy::id),
+        }
+    }
+
+    fn seat(&self, kind: SelectionKind) -> Option<&ObjectId> {
+        match kind {
+            SelectionKind::Clipboard => self.clipboard_seat.as_ref(),
+            SelectionKind::Primary => self.primary_seat.as_ref(),
+        }
+    }
+}
+
+impl<T> WindowState<T> {
+    fn own_selection(&self, kind: SelectionKind) -> Option<&SourceData> {
+        match kind {
+            SelectionKind::Clipboard => self.selection.clipboard_source.as_ref()?.data(),
+            SelectionKind::Primary => self.selection.primary_source.as_ref()?.data(),
+        }
+    }
+
+    /// MIME types the current selection of `kind` can be read as, empty if there is none
+    pub fn selection_mime_types(&self, kind: SelectionKind) -> Vec<String> {
+        if let Some(own) = self.own_selection(kind) {
+            return own.mime_types.clone();
+        }
+        let offer_data = match kind {
+            SelectionKind::Clipboard => self
+                .selection
+                .clipboard
+                .as_ref()
+                .and_then(|offer| offer.data::<OfferData>()),
+            SelectionKind::Primary => self
+                .selection
+                .primary
+                .as_ref()
+                .and_then(|offer| offer.data::<OfferData>()),
+        };
+        offer_data.map(OfferData::mime_types).unwrap_or_default()
+    }
+
+    /// Ask for the current selection of `kind` as `mime_type`. The data arrives as
+    /// [`DispatchMessage::SelectionData`](crate::DispatchMessage::SelectionData) once the
+    /// selection owner sent all of it.
+    ///
+    /// Returns `None` if the selection is not offered as `mime_type`, or its seat is gone.
+    pub fn receive_selection(&mut self, kind: SelectionKind, mime_type: &str) -> Option<()> {
+        if !self
+            .selection_mime_types(kind)
+            .iter()
+            .any(|offered| offered == mime_type)
+        {
+            return None;
+        }
+        let seat = self.seat_id(self.selection.seat(kind)?)?;
+        if let Some(own) = self.own_selection(kind) {
+            let data = own.data.to_vec();
+            self.message.push((
+                None,
+                DispatchMessageInner::SelectionData {
+                    kind,
+                    mime_type: mime_type.to_owned(),
+                    data,
+                    seat,
+                },
+            ));
+            return Some(());
+        }
+        let target = ReadTarget::Selection {
+            kind,
+            mime_type: mime_type.to_owned(),
+            seat,
+        };
+        let read = match kind {
+            SelectionKind::Clipboard => {
+                let offer = self.selection.clipboard.as_ref()?;
+                start_receive(|fd| offer.receive(mime_type.to_owned(), fd), target)?
+            }
+            SelectionKind::Primary => {
+                let offer = self.selection.primary.as_ref()?;
+                start_receive(|fd| offer.receive(mime_type.to_owned(), fd), target)?
+            }
+        };
+        self.pending_reads.push(read);
+        Some(())
+    }
+
+    /// The current selection of `kind` as text, in the first of [`TEXT_MIME_TYPES`] it is
+    /// offered as. Does not wait: `None` until the selection of another client has been read.
+    pub fn read_selection_text(&self, kind: SelectionKind) -> Option<String> {
+        if let Some(own) = self.own_selection(kind) {
+            text_mime_type(&own.mime_types)?;
+            return String::from_utf8(own.data.to_vec()).ok();
+        }
+        match kind {
+            SelectionKind::Clipboard => self.selection.clipboard_text.clone(),
+            SelectionKind::Primary => self.selection.primary_text.clone(),
+        }
+    }
+
+    /// Read the new selection of `kind` ahead as text, if it is offered as text
+    fn read_ahead_text(&mut self, kind: SelectionKind) {
+        let Some(offer) = self.selection.offer_id(kind) else {
+            return;
+        };
+        let Some(mime_type) = text_mime_type(&self.selection_mime_types(kind)) else {
+            return;
+        };
+        let target = ReadTarget::SelectionText { kind, offer };
+        let read = match kind {
+            SelectionKind::Clipboard => self.selection.clipboard.as_ref().and_then(|offer| {
+                start_receive(|fd| offer.receive(mime_type.to_owned(), fd), target)
+            }),
+            SelectionKind::Primary => self.selection.primary.as_ref().and_then(|offer| {
+                start_receive(|fd| offer.receive(mime_type.to_owned(), fd), target)
+            }),
+        };
+        self.pending_reads.extend(read);
+    }
+}
+
+impl<T: 'static> WindowState<T> {
+    /// Offer `data` as the selection of `kind`, under each of `mime_types`. The serial of the
+    /// last key press, button press or touch is used, on the seat it came from, so call it in
+    /// response to input.
+    ///
+    /// Returns `None` if the compositor does not support the selection, or there was no input
+    /// yet.
+    pub fn set_selection(
+        &mut self,
+        kind: SelectionKind,
+        mime_types: Vec<String>,
+        data: impl Into<Arc<[u8]>>,
+    ) -> Option<()> {
+        let serial = self.last_input_serial?;
+        let seat = self
+            .seats
+            .values()
+            .find(|seat| seat.input_serial == Some(serial))?;
+        let (seat, data_device, primary_selection_device) = (
+            seat.seat.id(),
+            seat.data_device.clone(),
+            seat.primary_selection_device.clone(),
+        );
+        let qh = self.queue_handle.clone()?;
+        let source_data = SourceData {
+            mime_types: mime_types.clone(),
+            data: data.into(),
+        };
+        match kind {
+            SelectionKind::Clipboard => {
+                let manager = self.data_device_manager.as_ref()?;
+                let device = data_device?;
+                let source = manager.create_data_source(&qh, source_data);
+                for mime_type in mime_types {
+                    source.offer(mime_type);
+                }
+                device.set_selection(Some(&source), serial);
+                if let Some(old) = self.selection.clipboard_source.replace(source) {
+                    old.destroy();
+                }
+                self.selection.clipboard_seat = Some(seat);
+            }
+            SelectionKind::Primary => {
+                let manager = self.primary_selection_manager.as_ref()?;
+                let device = primary_selection_device?;
+                let source = manager.create_source(&qh, source_data);
+                for mime_type in mime_types {
+                    source.offer(mime_type);
+                }
+                device.set_selection(Some(&source), serial);
+                if let Some(old) = self.selection.primary_source.replace(source) {
+                    old.destroy();
+                }
+                self.selection.primary_seat = Some(seat);
+            }
+        }
+        Some(())
+    }
+
+    /// Offer `text` as the selection of `kind`, under all of [`TEXT_MIME_TYPES`]
+    pub fn set_selection_text(&mut self, kind: SelectionKind, text: String) -> Option<()> {
+        let mime_types = TEXT_MIME_TYPES.map(str::to_owned).to_vec();
+        self.set_selection(kind, mime_types, text.into_bytes())
+    }
+}
+
+/// What the data of a read on the event loop is for
+#[derive(Debug, Clone)]
+pub(crate) enum ReadTarget {
+    /// asked for with [`WindowState::receive_dnd`], for the surface the drag is over or was
+    /// dropped on
+    Dnd {
+        surface: Id,
+        mime_type: String,
+        seat: SeatId,
+        dropped: bool,
+    },
+    /// asked for with [`WindowState::receive_selection`]
+    Selection {
+        kind: SelectionKind,
+        mime_type: String,
+        seat: SeatId,
+    },
+    /// read ahead when `offer` became the selection of `kind`
+    SelectionText {
+        kind: SelectionKind,
+        offer: ObjectId,
+    },
+}
+
+/// A pipe the event loop reads to the end, the other end may be slow to send
+#[derive(Debug)]
+pub(crate) struct PendingRead {
+    reader: PipeReader,
+    target: ReadTarget,
+}
+
+/// Ask the owner of an offer to write into a pipe, which is read once the request is flushed
+pub(crate) fn start_receive(
+    request: impl FnOnce(BorrowedFd),
+    target: ReadTarget,
+) -> Option<PendingRead> {
+    let (reader, writer) = std::io::pipe().ok()?;
+    request(writer.as_fd());
+    // our end of the write side must be closed, or the read never sees the end
+    drop(writer);
+    Some(PendingRead { reader, target })
+}
+
+/// Read `read` on the event loop without blocking it, its data goes to
+/// [`WindowState::read_done`] once the writer closed the pipe
+pub(crate) fn insert_read<S: 'static, T: 'static>(
+    handle: &LoopHandle<'static, S>,
+    read: PendingRead,
+    window_state: fn(&mut S) -> &mut WindowState<T>,
+) {
+    let PendingRead { reader, target } = read;
+    let mut data = Vec::new();
+    let source = Generic::new(reader, Interest::READ, Mode::Level);
+    let inserted = handle.insert_source(source, move |_, reader, state| {
+        let mut chunk = [0; 4096];
+        let data = match rustix::io::read(&**reader, &mut chunk) {
+            Ok(0) => Some(std::mem::take(&mut data)),
+            Ok(read) => {
+                data.extend_from_slice(&chunk[..read]);
+                return Ok(PostAction::Continue);
+            }
+            Err(rustix::io::Errno::INTR | rustix::io::Errno::AGAIN) => {
+                return Ok(PostAction::Continue);
+            }
+            Err(error) => {
+                log::warn!("error receiving data: {error}");
+                None
+            }
+        };
+        window_state(state).read_done(target.clone(), data);
+        Ok(PostAction::Remove)
+    });
+    if let Err(error) = inserted {
+        log::error!("cannot read on the event loop: {}", error.error);
+    }
+}
+
+impl<T> WindowState<T> {
+    /// A read on the event loop ended, with its data if the writer sent all of it
+    pub(crate) fn read_done(&mut self, target: ReadTarget, data: Option<Vec<u8>>) {
+        match target {
+            ReadTarget::Dnd {
+                surface,
+                mime_type,
+                seat,
+                dropped,
+            } => self.dnd_read_done(surface, mime_type, seat, dropped, data),
+            ReadTarget::Selection {
+                kind,
+                mime_type,
+                seat,
+            } => {
+                if let Some(data) = data {
+                    self.message.push((
+                        None,
+                        DispatchMessageInner::SelectionData {
+                            kind,
+                            mime_type,
+                            data,
+                            seat,
+                        },
+                    ));
+                }
+            }
+            // the selection may have changed again while it was read
+            ReadTarget::SelectionText { kind, offer } => {
+                if self.selection.offer_id(kind) != Some(offer) {
+                    return;
+                }
+                let text = data.and_then(|data| String::from_utf8(data).ok());
+                match kind {
+                    SelectionKind::Clipboard => self.selection.clipboard_text = text,
+                    SelectionKind::Primary => self.selection.primary_text = text,
+                }
+            }
+        }
+    }
+}
+
+/// Data to write into a pipe on the event loop, the other end may be slow to read
+#[derive(Debug)]
+pub(crate) struct PendingWrite {
+    writer: OwnedFd,
+    data: Arc<[u8]>,
+}
+
+/// Write `write` on the event loop without blocking it, the pipe is closed once all of the data
+/// is written or the reader went away
+pub(crate) fn insert_write<S: 'static>(handle: &LoopHandle<'static, S>, write: PendingWrite) {
+    let PendingWrite { writer, data } = write;
+    if let Err(error) = rustix::io::ioctl_fionbio(&writer, true) {
+        log::warn!("cannot send data without blocking: {error}");
+        return;
+    }
+    let mut written = 0;
+    let source = Generic::new(writer, Interest::WRITE, Mode::Level);
+    let inserted = handle.insert_source(source, move |_, writer, _| {
+        if written == data.len() {
+            return Ok(PostAction::Remove);
+        }
+        match rustix::io::write(&**writer, &data[written..]) {
+            Ok(count) => {
+                written += count;
+                if written < data.len() {
+                    return Ok(PostAction::Continue);
+                }
+            }
+            Err(rustix::io::Errno::INTR | rustix::io::Errno::AGAIN) => {
+                return Ok(PostAction::Continue);
+            }
+            Err(error) => log::warn!("error sending data: {error}"),
+        }
+        Ok(PostAction::Remove)
+    });
+    if let Err(error) = inserted {
+        log::error!("cannot write on the event loop: {}", error.error);
+    }
+}
+
+impl<T: 'static> Dispatch<WlDataDevice, ObjectId> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &WlDataDevice,
+        event: <WlDataDevice as Proxy>::Event,
+        data: &ObjectId,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_data_device::Event::Selection { id } => {
+                state.selection.set_clipboard_offer(id, data);
+                state.read_ahead_text(SelectionKind::Clipboard);
+            }
+            event => state.dnd_event(data, event),
+        }
+    }
+
+    event_created_child!(WindowState<T>, WlDataDevice, [
+        wl_data_device::EVT_DATA_OFFER_OPCODE => (WlDataOffer, OfferData::default()),
+    ]);
+}
+
+impl<T> Dispatch<WlDataOffer, OfferData> for WindowState<T> {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WlDataOffer,
+        event: <WlDataOffer as Proxy>::Event,
+        data: &OfferData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_data_offer::Event::Offer { mime_type } => data.push(mime_type),
+            wl_data_offer::Event::Action {
+                dnd_action: WEnum::Value(action),
+            } => *data.action.lock().unwrap() = Some(action),
+            _ => {}
+        }
+    }
+}
+
+impl<T> Dispatch<WlDataSource, SourceData> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        proxy: &WlDataSource,
+        event: <WlDataSource as Proxy>::Event,
+        data: &SourceData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if state.dnd_source_event(proxy, &event) {
+            if let wl_data_source::Event::Send { fd, .. } = event {
+                state.pending_writes.push(data.send(fd));
+            }
+            return;
+        }
+        match event {
+            wl_data_source::Event::Send { fd, .. } => state.pending_writes.push(data.send(fd)),
+            wl_data_source::Event::Cancelled => {
+                if state
+                    .selection
+                    .clipboard_source
+                    .as_ref()
+                    .is_some_and(|source| source == proxy)
+                {
+                    state.selection.clipboard_source = None;
+                }
+                proxy.destroy();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T: 'static> Dispatch<ZwpPrimarySelectionDeviceV1, ObjectId> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPrimarySelectionDeviceV1,
+        event: <ZwpPrimarySelectionDeviceV1 as Proxy>::Event,
+        data: &ObjectId,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let zwp_primary_selection_device_v1::Event::Selection { id } = event {
+            state.selection.set_primary_offer(id, data);
+            state.read_ahead_text(SelectionKind::Primary);
+        }
+    }
+
+    event_created_child!(WindowState<T>, ZwpPrimarySelectionDeviceV1, [
+        zwp_primary_selection_device_v1::EVT_DATA_OFFER_OPCODE => (ZwpPrimarySelectionOfferV1, OfferData::default()),
+    ]);
+}
+
+impl<T> Dispatch<ZwpPrimarySelectionOfferV1, OfferData> for WindowState<T> {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ZwpPrimarySelectionOfferV1,
+        event: <ZwpPrimarySelectionOfferV1 as Proxy>::Event,
+        data: &OfferData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let zwp_primary_selection_offer_v1::Event::Offer { mime_type } = event {
+            data.push(mime_type);
+        }
+    }
+}
+
+impl<T> Dispatch<ZwpPrimarySelectionSourceV1, SourceData> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwpPrimarySelectionSourceV1,
+        event: <ZwpPrimarySelectionSourceV1 as Proxy>::Event,
+        data: &SourceData,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_primary_selection_source_v1::Event::Send { fd, .. } => {
+                state.pending_writes.push(data.send(fd));
+            }
+            zwp_primary_selection_source_v1::Event::Cancelled => {
+                if state
+                    .selection
+                    .primary_source
+                    .as_ref()
+                    .is_some_and(|source| source == proxy)
+                {
+                    state.selection.primary_source = None;
+                }
+                proxy.destroy();
+            }
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(@<T> WindowState<T>: ignore WlDataDeviceManager);
+delegate_noop!(@<T> WindowState<T>: ignore ZwpPrimarySelectionDeviceManagerV1);
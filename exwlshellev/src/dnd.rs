@@ -0,0 +1,394 @@
+//! Drag and drop through `wl_data_device`
+//!
+//! Drags over our surfaces are reported as [`DispatchMessage::DndEnter`],
+//! [`DispatchMessage::DndMotion`], [`DispatchMessage::DndDrop`] and [`DispatchMessage::DndLeave`],
+//! with the MIME types they offer. A drag can only be dropped once one of its types was picked
+//! with [`WindowState::accept_dnd`]. The data is asked for with [`WindowState::receive_dnd`],
+//! while the drag hovers or while the drop is handled, and arrives as
+//! [`DispatchMessage::DndData`] without blocking the event loop. Drags out of our surfaces are
+//! started with [`WindowState::start_drag`].
+//!
+//! [`DispatchMessage::DndEnter`]: crate::DispatchMessage::DndEnter
+//! [`DispatchMessage::DndMotion`]: crate::DispatchMessage::DndMotion
+//! [`DispatchMessage::DndDrop`]: crate::DispatchMessage::DndDrop
+//! [`DispatchMessage::DndLeave`]: crate::DispatchMessage::DndLeave
+//! [`DispatchMessage::DndData`]: crate::DispatchMessage::DndData
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use wayland_client::{
+    Proxy,
+    backend::ObjectId,
+    protocol::{
+        wl_data_device,
+        wl_data_device_manager::DndAction,
+        wl_data_offer::WlDataOffer,
+        wl_data_source::{self, WlDataSource},
+        wl_surface::WlSurface,
+    },
+};
+
+use crate::clipboard::{OfferData, ReadTarget, SourceData, start_receive};
+use crate::id::Id;
+use crate::{DispatchMessageInner, SeatId, WindowState};
+
+/// The MIME type file managers drag files as
+pub const URI_LIST_MIME_TYPE: &str = "text/uri-list";
+
+/// Drags over and out of our surfaces
+#[derive(Debug, Default)]
+pub(crate) struct DndState {
+    /// offer of the drag over one of our surfaces
+    offer: Option<WlDataOffer>,
+    /// surface the drag is over, and the last position on it
+    target: Option<(Id, f64, f64)>,
+    /// seat of the drag over one of our surfaces, and the serial of its enter
+    enter: Option<(SeatId, u32)>,
+    /// we accepted one of the types of the offer
+    accepted: bool,
+    /// offer dropped on us, the surface it was dropped on and the seat that dropped it,
+    /// finished once the drop message is handled and its data was read
+    dropped: Option<(WlDataOffer, Id, SeatId)>,
+    /// reads of the dropped offer still running
+    reads: usize,
+    /// our own drag
+    source: Option<WlDataSource>,
+    /// surface our own drag started from, and the seat dragging
+    origin: Option<(Id, SeatId)>,
+}
+
+fn offered_mime_types(offer: &WlDataOffer) -> Vec<String> {
+    offer
+        .data::<OfferData>()
+        .map(OfferData::mime_types)
+        .unwrap_or_default()
+}
+
+/// Paths of the `file://` uris in a `text/uri-list`
+pub fn parse_uri_list(data: &[u8]) -> Vec<PathBuf> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.strip_prefix("file://"))
+        // skip the host, local files have none or `localhost`
+        .filter_map(|uri| uri.find('/').map(|start| &uri[start..]))
+        .map(|path| PathBuf::from(percent_decode(path)))
+        .collect()
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+impl<T> WindowState<T> {
+    /// Accept the drag over one of our surfaces as `mime_type`, one of the types of its
+    /// [`DispatchMessage::DndEnter`](crate::DispatchMessage::DndEnter), or refuse it with
+    /// `None`. Only an accepted drag can be dropped.
+    ///
+    /// Returns `None` if no drag is over our surfaces, or it is not offered as `mime_type`.
+    pub fn accept_dnd(&mut self, mime_type: Option<&str>) -> Option<()> {
+        let offer = self.dnd.offer.as_ref()?;
+        let (_, serial) = self.dnd.enter?;
+        if let Some(mime_type) = mime_type
+            && !offered_mime_types(offer)
+                .iter()
+                .any(|offered| offered == mime_type)
+        {
+            return None;
+        }
+        offer.accept(serial, mime_type.map(str::to_owned));
+        self.dnd.accepted = mime_type.is_some();
+        Some(())
+    }
+
+    /// Ask for the data of the drag over one of our surfaces as `mime_type`, or of the drag
+    /// dropped on one while its [`DispatchMessage::DndDrop`](crate::DispatchMessage::DndDrop) is
+    /// handled. The data arrives as [`DispatchMessage::DndData`](crate::DispatchMessage::DndData)
+    /// on the same surface once the source sent all of it.
+    ///
+    /// Returns `None` if no drag is over our surfaces and nothing was dropped, or the drag is
+    /// not offered as `mime_type`.
+    pub fn receive_dnd(&mut self, mime_type: &str) -> Option<()> {
+        let (offer, surface, seat, dropped) = match &self.dnd.dropped {
+            Some((offer, surface, seat)) => (offer, *surface, *seat, true),
+            None => {
+                let (surface, ..) = self.dnd.target?;
+                let (seat, _) = self.dnd.enter?;
+                (self.dnd.offer.as_ref()?, surface, seat, false)
+            }
+        };
+        if !offered_mime_types(offer)
+            .iter()
+            .any(|offered| offered == mime_type)
+        {
+            return None;
+        }
+        let target = ReadTarget::Dnd {
+            surface,
+            mime_type: mime_type.to_owned(),
+            seat,
+            dropped,
+        };
+        let read = start_receive(|fd| offer.receive(mime_type.to_owned(), fd), target)?;
+        self.pending_reads.push(read);
+        // only reads of the drop hold back its finish
+        if dropped {
+            self.dnd.reads += 1;
+        }
+        Some(())
+    }
+
+    pub(crate) fn dnd_read_done(
+        &mut self,
+        surface: Id,
+        mime_type: String,
+        seat: SeatId,
+        dropped: bool,
+        data: Option<Vec<u8>>,
+    ) {
+        if let Some(data) = data {
+            self.message.push((
+                Some(surface),
+                DispatchMessageInner::DndData {
+                    mime_type,
+                    data,
+                    dropped,
+                    seat,
+                },
+            ));
+        }
+        if dropped {
+            self.dnd.reads -= 1;
+            self.finish_dnd_drop();
+        }
+    }
+
+    /// Finish the drop once its message was handled and the data it asked for was read
+    pub(crate) fn finish_dnd_drop(&mut self) {
+        if self.dnd.reads > 0 {
+            return;
+        }
+        let Some((offer, ..)) = self.dnd.dropped.take() else {
+            return;
+        };
+        // finishing without an accepted type or a negotiated action is a protocol error
+        let negotiated = offer
+            .data::<OfferData>()
+            .and_then(OfferData::action)
+            .is_some_and(|action| !action.is_empty());
+        if offer.version() >= 3 && self.dnd.accepted && negotiated {
+            offer.finish();
+        }
+        offer.destroy();
+    }
+
+    /// Handle a drag and drop event of the data device of `seat`
+    pub(crate) fn dnd_event(&mut self, seat: &ObjectId, event: wl_data_device::Event) {
+        let Some(seat_id) = self.seat_id(seat) else {
+            return;
+        };
+        match event {
+            wl_data_device::Event::Enter {
+                serial,
+                surface,
+                x,
+                y,
+                id,
+            } => {
+                if let Some(old) = std::mem::replace(&mut self.dnd.offer, id) {
+                    old.destroy();
+                }
+                let target = self.get_id_from_surface(&surface);
+                self.dnd.target = target.map(|target| (target, x, y));
+                self.dnd.enter = Some((seat_id, serial));
+                // nothing is accepted until the handler of the enter picks a type
+                self.dnd.accepted = false;
+                let (Some(offer), Some(target)) = (&self.dnd.offer, target) else {
+                    return;
+                };
+                let mime_types = offered_mime_types(offer);
+                if offer.version() >= 3 {
+                    // the compositor picks the action from these, answered by an `action` event
+                    offer.set_actions(DndAction::Copy | DndAction::Move, DndAction::Copy);
+                }
+                self.push_seat_message(
+                    seat,
+                    (
+                        Some(target),
+                        DispatchMessageInner::DndEnter {
+                            mime_types,
+                            x,
+                            y,
+                            seat: seat_id,
+                        },
+                    ),
+                );
+            }
+            wl_data_device::Event::Motion { x, y, .. } => {
+                let Some((target, ..)) = self.dnd.target else {
+                    return;
+                };
+                self.dnd.target = Some((target, x, y));
+                self.push_seat_message(
+                    seat,
+                    (
+                        Some(target),
+                        DispatchMessageInner::DndMotion {
+                            x,
+                            y,
+                            seat: seat_id,
+                        },
+                    ),
+                );
+            }
+            wl_data_device::Event::Leave => {
+                self.dnd.enter = None;
+                if let Some(offer) = self.dnd.offer.take() {
+                    offer.destroy();
+                }
+                if let Some((target, ..)) = self.dnd.target.take() {
+                    self.push_seat_message(
+                        seat,
+                        (
+                            Some(target),
+                            DispatchMessageInner::DndLeave { seat: seat_id },
+                        ),
+                    );
+                }
+            }
+            wl_data_device::Event::Drop => {
+                self.dnd.enter = None;
+                let (Some(offer), Some((target, x, y))) =
+                    (self.dnd.offer.take(), self.dnd.target.take())
+                else {
+                    return;
+                };
+                let mime_types = offered_mime_types(&offer);
+                if let Some((old, ..)) = self.dnd.dropped.replace((offer, target, seat_id)) {
+                    old.destroy();
+                }
+                self.push_seat_message(
+                    seat,
+                    (
+                        Some(target),
+                        DispatchMessageInner::DndDrop {
+                            mime_types,
+                            x,
+                            y,
+                            seat: seat_id,
+                        },
+                    ),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle an event of a data source, returns whether it belonged to our drag
+    pub(crate) fn dnd_source_event(
+        &mut self,
+        source: &WlDataSource,
+        event: &wl_data_source::Event,
+    ) -> bool {
+        if self.dnd.source.as_ref() != Some(source) {
+            return false;
+        }
+        let cancelled = match event {
+            wl_data_source::Event::Cancelled => true,
+            wl_data_source::Event::DndFinished => false,
+            _ => return true,
+        };
+        self.dnd.source = None;
+        source.destroy();
+        if let Some((origin, seat)) = self.dnd.origin.take() {
+            self.message.push((
+                Some(origin),
+                DispatchMessageInner::DragFinished { cancelled, seat },
+            ));
+        }
+        true
+    }
+}
+
+impl<T: 'static> WindowState<T> {
+    /// Start dragging `data` out of the surface of `id`, offered under each of `mime_types`.
+    /// `icon` follows the pointer during the drag. Must be called while the pointer button
+    /// pressed on the surface is still down, the drag belongs to the seat of that pointer.
+    ///
+    /// Returns `None` if there is no data device, no such surface or no pointer of any seat
+    /// pressed a button on it.
+    pub fn start_drag(
+        &mut self,
+        id: Id,
+        mime_types: Vec<String>,
+        data: impl Into<Arc<[u8]>>,
+        icon: Option<&WlSurface>,
+    ) -> Option<()> {
+        let seat = self.seats.values().find(|seat| {
+            seat.button_serial.is_some()
+                && seat
+                    .active_surfaces
+                    .get(&None)
+                    .is_some_and(|(_, surface)| *surface == Some(id))
+        })?;
+        let seat_id = seat.id;
+        let serial = seat.button_serial?;
+        let device = seat.data_device.as_ref()?;
+        let qh = self.queue_handle.clone()?;
+        let manager = self.data_device_manager.as_ref()?;
+        let origin = &self.get_unit_with_id(id)?.window.wl_surface;
+        let source = manager.create_data_source(
+            &qh,
+            SourceData {
+                mime_types: mime_types.clone(),
+                data: data.into(),
+            },
+        );
+        for mime_type in mime_types {
+            source.offer(mime_type);
+        }
+        if source.version() >= 3 {
+            source.set_actions(DndAction::Copy | DndAction::Move);
+        }
+        device.start_drag(Some(&source), origin, icon, serial);
+        if let Some(old) = self.dnd.source.replace(source) {
+            old.destroy();
+        }
+        self.dnd.origin = Some((id, seat_id));
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uri_list_yields_decoded_local_paths() {
+        let list = b"# comment\r\nfile:///home/me/a%20b.txt\r\nfile://localhost/tmp/c\r\nhttps://example.org/d\r\n";
+        assert_eq!(
+            parse_uri_list(list),
+            [PathBuf::from("/home/me/a b.txt"), PathBuf::from("/tmp/c")]
+        );
+    }
+}
@@ -26,10 +26,15 @@ use crate::size::{LayerSize, PixelSize};
 use crate::{blur::BlurOption, id, xkb_keyboard::KeyEvent};
 
 use crate::keyboard::ModifiersState;
+use crate::reexport::zwp_tablet_tool_v2::Type as TabletToolType;
 
 use super::WindowState;
 
+use crate::clipboard::SelectionKind;
 use crate::id::Id;
+use crate::input_method::InputMethodContext;
+use crate::seat::SeatId;
+use waycrate_xkbkeycode::compose::ComposeState;
 
 use std::{fmt::Debug, fs::File};
 
@@ -170,6 +175,38 @@ pub struct NewXdgWindowSettings {
     pub size: Option<PixelSize>,
     /// Request client-side decorations instead of the default server-side mode.
     pub client_side_decorations: bool,
+    /// The application id, desktop entries are matched against it.
+    pub app_id: Option<String>,
+    /// The minimum size the window can be resized to.
+    pub min_size: Option<PixelSize>,
+    /// The maximum size the window can be resized to.
+    pub max_size: Option<PixelSize>,
+}
+
+/// States of an xdg toplevel from its last configure
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ToplevelState {
+    pub maximized: bool,
+    pub fullscreen: bool,
+    /// being resized interactively
+    pub resizing: bool,
+    /// has the keyboard focus or is otherwise drawn as the active window
+    pub activated: bool,
+    pub tiled_left: bool,
+    pub tiled_right: bool,
+    pub tiled_top: bool,
+    pub tiled_bottom: bool,
+    /// not visible to the user, drawing can be paused
+    pub suspended: bool,
+}
+
+/// Window management the compositor offers for xdg toplevels
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct WmCapabilities {
+    pub window_menu: bool,
+    pub maximize: bool,
+    pub fullscreen: bool,
+    pub minimize: bool,
 }
 
 /// input panel settings to create a new input panel surface
@@ -184,6 +221,12 @@ pub struct NewInputPanelSettings {
     pub output_option: OutputOption,
 }
 
+/// settings to create a candidate window of the input method, shown next to the text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewInputMethodPopupSettings {
+    pub size: PixelSize,
+}
+
 impl Default for NewLayerShellSettings {
     fn default() -> Self {
         NewLayerShellSettings {
@@ -230,6 +273,7 @@ pub enum ReturnData<INFO> {
     PopUpReposition((PopUpRepositionSettings, id::Id)),
     NewXdgBase((NewXdgWindowSettings, id::Id, Option<INFO>)),
     NewInputPanel((NewInputPanelSettings, id::Id, Option<INFO>)),
+    NewInputMethodPopup((NewInputMethodPopupSettings, id::Id, Option<INFO>)),
     None,
 }
 
@@ -245,6 +289,15 @@ pub struct AxisScroll {
     /// source.
     pub discrete: i32,
 
+    /// The scroll measured in fractions of 120 per step, from high-resolution wheels.
+    ///
+    /// Zero if the compositor only reports whole steps.
+    pub value120: i32,
+
+    /// The physical direction of the scroll is inverted from the content, like with natural
+    /// scrolling.
+    pub inverted: bool,
+
     /// The scroll was stopped.
     ///
     /// Generally this is encountered when hardware indicates the end of some continuous scrolling.
@@ -273,6 +326,14 @@ pub enum Ime {
     /// Right before this event winit will send empty [`Self::Preedit`] event.
     Commit(String),
 
+    /// Notifies when text around the cursor should be deleted, `before` bytes before it and
+    /// `after` bytes after it, not counting the selection.
+    ///
+    /// Sent between the empty [`Preedit`][Self::Preedit] and the [`Commit`][Self::Commit] of the
+    /// same change. The offsets refer to the text set with
+    /// [`WindowState::set_ime_surrounding_text`].
+    DeleteSurrounding { before: usize, after: usize },
+
     /// Notifies when the IME was disabled.
     ///
     /// After receiving this event you won't get any more [`Preedit`][Self::Preedit] or
@@ -294,18 +355,23 @@ pub(crate) enum DispatchMessageInner {
         serial: u32,
         button: u32,
         time: u32,
+        seat: SeatId,
+    },
+    MouseLeave {
+        seat: SeatId,
     },
-    MouseLeave,
     MouseEnter {
         pointer: WlPointer,
         serial: u32,
         surface_x: f64,
         surface_y: f64,
+        seat: SeatId,
     },
     MouseMotion {
         time: u32,
         surface_x: f64,
         surface_y: f64,
+        seat: SeatId,
     },
     Axis {
         time: u32,
@@ -313,6 +379,7 @@ pub(crate) enum DispatchMessageInner {
         horizontal: AxisScroll,
         vertical: AxisScroll,
         source: Option<wl_pointer::AxisSource>,
+        seat: SeatId,
     },
     TouchDown {
         serial: u32,
@@ -320,6 +387,7 @@ pub(crate) enum DispatchMessageInner {
         id: i32,
         x: f64,
         y: f64,
+        seat: SeatId,
     },
     TouchUp {
         serial: u32,
@@ -327,35 +395,46 @@ pub(crate) enum DispatchMessageInner {
         id: i32,
         x: f64,
         y: f64,
+        seat: SeatId,
     },
     TouchMotion {
         time: u32,
         id: i32,
         x: f64,
         y: f64,
+        seat: SeatId,
     },
     TouchCancel {
         id: i32,
         x: f64,
         y: f64,
+        seat: SeatId,
     },
 
-    ModifiersChanged(ModifiersState),
-    Focused(Id),
-    Unfocus,
+    ModifiersChanged {
+        modifiers: ModifiersState,
+        seat: SeatId,
+    },
+    Focused {
+        id: Id,
+        seat: SeatId,
+    },
+    Unfocus {
+        seat: SeatId,
+    },
     KeyboardInput {
         event: KeyEvent,
 
-        /// If `true`, the event was generated synthetically by winit
-        /// in one of the following circumstances:
+        /// If `true`, the event was generated synthetically in one of the following
+        /// circumstances:
         ///
         /// * Synthetic key press events are generated for all keys pressed when a window gains
         ///   focus. Likewise, synthetic key release events are generated for all keys pressed when
-        ///   a window goes out of focus. ***Currently, this is only functional on X11 and
-        ///   Windows***
+        ///   a window goes out of focus.
         ///
         /// Otherwise, this value is always `false`.
         is_synthetic: bool,
+        seat: SeatId,
     },
     PreferredScale {
         scale_u32: u32,
@@ -364,10 +443,188 @@ pub(crate) enum DispatchMessageInner {
     OutputChanged(Option<WlOutput>),
     Locked,
     LockFinished,
-    Ime(Ime),
+    Ime {
+        ime: Ime,
+        seat: SeatId,
+    },
+    ActivationToken(String),
+    SwipeBegin {
+        id: Id,
+        time: u32,
+        fingers: u32,
+        seat: SeatId,
+    },
+    SwipeUpdate {
+        id: Id,
+        time: u32,
+        dx: f64,
+        dy: f64,
+        seat: SeatId,
+    },
+    SwipeEnd {
+        id: Id,
+        time: u32,
+        cancelled: bool,
+        seat: SeatId,
+    },
+    PinchBegin {
+        id: Id,
+        time: u32,
+        fingers: u32,
+        seat: SeatId,
+    },
+    PinchUpdate {
+        id: Id,
+        time: u32,
+        dx: f64,
+        dy: f64,
+        scale: f64,
+        rotation: f64,
+        seat: SeatId,
+    },
+    PinchEnd {
+        id: Id,
+        time: u32,
+        cancelled: bool,
+        seat: SeatId,
+    },
+    HoldBegin {
+        id: Id,
+        time: u32,
+        fingers: u32,
+        seat: SeatId,
+    },
+    HoldEnd {
+        id: Id,
+        time: u32,
+        cancelled: bool,
+        seat: SeatId,
+    },
+    RelativeMotion {
+        dx: f64,
+        dy: f64,
+        dx_unaccel: f64,
+        dy_unaccel: f64,
+        seat: SeatId,
+    },
+    PointerLocked,
+    PointerUnlocked,
+    PointerConfined,
+    PointerUnconfined,
+    TabletToolProximityIn {
+        tool_type: Option<TabletToolType>,
+        seat: SeatId,
+    },
+    TabletToolProximityOut {
+        seat: SeatId,
+    },
+    TabletToolDown {
+        seat: SeatId,
+    },
+    TabletToolUp {
+        seat: SeatId,
+    },
+    TabletToolMotion {
+        surface_x: f64,
+        surface_y: f64,
+        seat: SeatId,
+    },
+    TabletToolPressure {
+        pressure: f64,
+        seat: SeatId,
+    },
+    TabletToolTilt {
+        x: f64,
+        y: f64,
+        seat: SeatId,
+    },
+    TabletToolRotation {
+        degrees: f64,
+        seat: SeatId,
+    },
+    TabletToolButton {
+        button: u32,
+        pressed: bool,
+        seat: SeatId,
+    },
+    TabletPadButton {
+        button: u32,
+        pressed: bool,
+        seat: SeatId,
+    },
+    TabletPadRing {
+        angle: Option<f64>,
+        seat: SeatId,
+    },
+    TabletPadStrip {
+        position: Option<f64>,
+        seat: SeatId,
+    },
+    DndEnter {
+        mime_types: Vec<String>,
+        x: f64,
+        y: f64,
+        seat: SeatId,
+    },
+    DndMotion {
+        x: f64,
+        y: f64,
+        seat: SeatId,
+    },
+    DndLeave {
+        seat: SeatId,
+    },
+    DndDrop {
+        mime_types: Vec<String>,
+        x: f64,
+        y: f64,
+        seat: SeatId,
+    },
+    DndData {
+        mime_type: String,
+        data: Vec<u8>,
+        dropped: bool,
+        seat: SeatId,
+    },
+    SelectionData {
+        kind: SelectionKind,
+        mime_type: String,
+        data: Vec<u8>,
+        seat: SeatId,
+    },
+    DragFinished {
+        cancelled: bool,
+        seat: SeatId,
+    },
+    ToplevelStateChanged(ToplevelState),
+    WmCapabilities(WmCapabilities),
+    DecorationModeChanged {
+        client_side: bool,
+    },
+    InputMethodDone(InputMethodContext),
+    InputMethodUnavailable,
+    InputPopupTextRectangle {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    Compose {
+        state: ComposeState,
+        seat: SeatId,
+    },
+    KeyboardLayoutChanged {
+        index: u32,
+        name: String,
+        seat: SeatId,
+    },
+    SeatAdded(SeatId),
+    SeatRemoved(SeatId),
 }
 
 /// This tell the DispatchMessage by dispatch
+///
+/// Input messages carry the `seat` they came from, see [`DispatchMessage::SeatAdded`]
 #[derive(Debug)]
 pub enum DispatchMessage {
     /// forward the event of wayland-mouse
@@ -376,29 +633,35 @@ pub enum DispatchMessage {
         serial: u32,
         button: u32,
         time: u32,
+        seat: SeatId,
     },
     /// Mouse leave the surface
-    MouseLeave,
+    MouseLeave {
+        seat: SeatId,
+    },
     /// forward the event of wayland-mouse
     MouseEnter {
         pointer: WlPointer,
         serial: u32,
         surface_x: f64,
         surface_y: f64,
+        seat: SeatId,
     },
     /// forward the event of wayland-mouse
     MouseMotion {
         time: u32,
         surface_x: f64,
         surface_y: f64,
+        seat: SeatId,
     },
-    /// About the scroll
+    /// About the scroll, all the axis events of one pointer frame
     Axis {
         time: u32,
         scale: f64,
         horizontal: AxisScroll,
         vertical: AxisScroll,
         source: Option<wl_pointer::AxisSource>,
+        seat: SeatId,
     },
     /// forward the event of wayland-touch
     TouchDown {
@@ -407,6 +670,7 @@ pub enum DispatchMessage {
         id: i32,
         x: f64,
         y: f64,
+        seat: SeatId,
     },
     /// forward the event of wayland-touch
     TouchUp {
@@ -415,6 +679,7 @@ pub enum DispatchMessage {
         id: i32,
         x: f64,
         y: f64,
+        seat: SeatId,
     },
     /// forward the event of wayland-touch
     TouchMotion {
@@ -422,31 +687,41 @@ pub enum DispatchMessage {
         id: i32,
         x: f64,
         y: f64,
+        seat: SeatId,
     },
     /// TouchEvent is cancelled
     TouchCancel {
         id: i32,
         x: f64,
         y: f64,
+        seat: SeatId,
+    },
+    Focused {
+        id: Id,
+        seat: SeatId,
+    },
+    Unfocus {
+        seat: SeatId,
     },
-    Focused(Id),
-    Unfocus,
     /// Keyboard ModifiersChanged.
-    ModifiersChanged(ModifiersState),
+    ModifiersChanged {
+        modifiers: ModifiersState,
+        seat: SeatId,
+    },
     /// Keyboard Event about input.
     KeyboardInput {
         event: KeyEvent,
 
-        /// If `true`, the event was generated synthetically by winit
-        /// in one of the following circumstances:
+        /// If `true`, the event was generated synthetically in one of the following
+        /// circumstances:
         ///
         /// * Synthetic key press events are generated for all keys pressed when a window gains
         ///   focus. Likewise, synthetic key release events are generated for all keys pressed when
-        ///   a window goes out of focus. ***Currently, this is only functional on X11 and
-        ///   Windows***
+        ///   a window goes out of focus.
         ///
         /// Otherwise, this value is always `false`.
         is_synthetic: bool,
+        seat: SeatId,
     },
     /// this will request to do refresh the whole screen, because the layershell tell that a new
     /// configure happened
@@ -460,7 +735,10 @@ pub enum DispatchMessage {
         scale_u32: u32,
         scale_float: f64,
     },
-    Ime(Ime),
+    Ime {
+        ime: Ime,
+        seat: SeatId,
+    },
     /// surface entered output, or left the one it was on
     OutputChanged(Option<WlOutput>),
     /// monitor was connected
@@ -472,6 +750,238 @@ pub enum DispatchMessage {
     Locked,
     LockDenied,
     LockFinished,
+    /// xdg-activation token requested with [`WindowState::request_activation_token`], export it
+    /// as [`ACTIVATION_TOKEN_ENV`] to the process you spawn
+    ///
+    /// [`WindowState::request_activation_token`]: crate::WindowState::request_activation_token
+    /// [`ACTIVATION_TOKEN_ENV`]: crate::ACTIVATION_TOKEN_ENV
+    ActivationToken(String),
+    /// touchpad swipe started on the surface `id`
+    SwipeBegin {
+        id: Id,
+        time: u32,
+        fingers: u32,
+        seat: SeatId,
+    },
+    /// the swipe moved, by `dx`/`dy` in surface coordinates since the last update
+    SwipeUpdate {
+        id: Id,
+        time: u32,
+        dx: f64,
+        dy: f64,
+        seat: SeatId,
+    },
+    /// the swipe is over, `cancelled` if the fingers left without finishing it
+    SwipeEnd {
+        id: Id,
+        time: u32,
+        cancelled: bool,
+        seat: SeatId,
+    },
+    /// touchpad pinch started on the surface `id`
+    PinchBegin {
+        id: Id,
+        time: u32,
+        fingers: u32,
+        seat: SeatId,
+    },
+    /// the pinch moved, `scale` is relative to the begin, `rotation` is in degrees since the last
+    /// update
+    PinchUpdate {
+        id: Id,
+        time: u32,
+        dx: f64,
+        dy: f64,
+        scale: f64,
+        rotation: f64,
+        seat: SeatId,
+    },
+    PinchEnd {
+        id: Id,
+        time: u32,
+        cancelled: bool,
+        seat: SeatId,
+    },
+    /// fingers are resting on the touchpad without moving
+    HoldBegin {
+        id: Id,
+        time: u32,
+        fingers: u32,
+        seat: SeatId,
+    },
+    HoldEnd {
+        id: Id,
+        time: u32,
+        cancelled: bool,
+        seat: SeatId,
+    },
+    /// raw pointer motion in surface coordinates, also sent while the pointer is locked.
+    /// `dx_unaccel`/`dy_unaccel` are the deltas before pointer acceleration
+    RelativeMotion {
+        dx: f64,
+        dy: f64,
+        dx_unaccel: f64,
+        dy_unaccel: f64,
+        seat: SeatId,
+    },
+    /// the lock requested with [`WindowStateUnit::lock_pointer`] is active
+    ///
+    /// [`WindowStateUnit::lock_pointer`]: crate::WindowStateUnit::lock_pointer
+    PointerLocked,
+    /// the pointer lock is no longer active, a oneshot lock is gone for good
+    PointerUnlocked,
+    /// the confinement requested with [`WindowStateUnit::confine_pointer`] is active
+    ///
+    /// [`WindowStateUnit::confine_pointer`]: crate::WindowStateUnit::confine_pointer
+    PointerConfined,
+    /// the confinement is no longer active, a oneshot confinement is gone for good
+    PointerUnconfined,
+    /// a tablet tool came into proximity of the surface, `tool_type` is `None` if the compositor
+    /// did not tell
+    TabletToolProximityIn {
+        tool_type: Option<TabletToolType>,
+        seat: SeatId,
+    },
+    /// the tablet tool left the proximity of the surface
+    TabletToolProximityOut {
+        seat: SeatId,
+    },
+    /// the tablet tool touches the tablet
+    TabletToolDown {
+        seat: SeatId,
+    },
+    /// the tablet tool no longer touches the tablet
+    TabletToolUp {
+        seat: SeatId,
+    },
+    /// the tablet tool moved, in surface coordinates
+    TabletToolMotion {
+        surface_x: f64,
+        surface_y: f64,
+        seat: SeatId,
+    },
+    /// pressure of the tablet tool, from 0 to 1
+    TabletToolPressure {
+        pressure: f64,
+        seat: SeatId,
+    },
+    /// tilt of the tablet tool, in degrees from the z axis
+    TabletToolTilt {
+        x: f64,
+        y: f64,
+        seat: SeatId,
+    },
+    /// rotation of the tablet tool around its z axis, in degrees
+    TabletToolRotation {
+        degrees: f64,
+        seat: SeatId,
+    },
+    /// a tablet tool button, `button` is a linux input event code such as `BTN_STYLUS`
+    TabletToolButton {
+        button: u32,
+        pressed: bool,
+        seat: SeatId,
+    },
+    /// a tablet pad button, `button` is the index of the button on the pad
+    TabletPadButton {
+        button: u32,
+        pressed: bool,
+        seat: SeatId,
+    },
+    /// angle of a tablet pad ring in degrees, `None` when the finger is lifted
+    TabletPadRing {
+        angle: Option<f64>,
+        seat: SeatId,
+    },
+    /// position of a tablet pad strip from 0 to 1, `None` when the finger is lifted
+    TabletPadStrip {
+        position: Option<f64>,
+        seat: SeatId,
+    },
+    /// a drag entered the surface, offering its data as `mime_types`, pick one with
+    /// [`WindowState::accept_dnd`](crate::WindowState::accept_dnd) to let it be dropped
+    DndEnter {
+        mime_types: Vec<String>,
+        x: f64,
+        y: f64,
+        seat: SeatId,
+    },
+    /// the drag over the surface moved
+    DndMotion {
+        x: f64,
+        y: f64,
+        seat: SeatId,
+    },
+    /// the drag left the surface without a drop
+    DndLeave {
+        seat: SeatId,
+    },
+    /// the drag was dropped on the surface, ask for its data with
+    /// [`WindowState::receive_dnd`](crate::WindowState::receive_dnd) while this is handled
+    DndDrop {
+        mime_types: Vec<String>,
+        x: f64,
+        y: f64,
+        seat: SeatId,
+    },
+    /// the data of the drag asked for with
+    /// [`WindowState::receive_dnd`](crate::WindowState::receive_dnd), `dropped` if it was read
+    /// from the drop rather than while the drag hovered
+    DndData {
+        mime_type: String,
+        data: Vec<u8>,
+        dropped: bool,
+        seat: SeatId,
+    },
+    /// the selection asked for with
+    /// [`WindowState::receive_selection`](crate::WindowState::receive_selection)
+    SelectionData {
+        kind: SelectionKind,
+        mime_type: String,
+        data: Vec<u8>,
+        seat: SeatId,
+    },
+    /// a drag started from the surface ended, `cancelled` if nothing accepted the drop
+    DragFinished {
+        cancelled: bool,
+        seat: SeatId,
+    },
+    /// the states of an xdg toplevel changed with a configure
+    ToplevelStateChanged(ToplevelState),
+    /// the window management the compositor offers for an xdg toplevel
+    WmCapabilities(WmCapabilities),
+    /// the compositor chose who draws the frame of an xdg toplevel, `client_side` if we have
+    /// to. Also sent when the compositor has no decoration protocol at all
+    DecorationModeChanged {
+        client_side: bool,
+    },
+    /// the focused text input changed, see [WindowState::input_method_context]
+    InputMethodDone(InputMethodContext),
+    /// another input method took over, the input method requests do nothing from now on
+    InputMethodUnavailable,
+    /// where the text being edited is, relative to the input method popup
+    InputPopupTextRectangle {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    /// a key press took part in a compose or dead key sequence, sent before its
+    /// [`DispatchMessage::KeyboardInput`]
+    Compose {
+        state: ComposeState,
+        seat: SeatId,
+    },
+    /// the effective xkb layout changed, `name` is empty if the keymap does not name it
+    KeyboardLayoutChanged {
+        index: u32,
+        name: String,
+        seat: SeatId,
+    },
+    /// a seat appeared, the seats present at start are announced too
+    SeatAdded(SeatId),
+    /// a seat went away
+    SeatRemoved(SeatId),
     Closed,
 }
 
@@ -485,44 +995,52 @@ impl From<DispatchMessageInner> for DispatchMessage {
             DispatchMessageInner::OutputUpdated(info) => DispatchMessage::OutputUpdated(info),
             DispatchMessageInner::OutputRemoved(info) => DispatchMessage::OutputRemoved(info),
             DispatchMessageInner::MouseButton {
+                seat,
                 state,
                 serial,
                 button,
                 time,
             } => DispatchMessage::MouseButton {
+                seat,
                 state,
                 serial,
                 button,
                 time,
             },
-            DispatchMessageInner::MouseLeave => DispatchMessage::MouseLeave,
+            DispatchMessageInner::MouseLeave { seat } => DispatchMessage::MouseLeave { seat },
             DispatchMessageInner::MouseEnter {
+                seat,
                 pointer,
                 serial,
                 surface_x,
                 surface_y,
             } => DispatchMessage::MouseEnter {
+                seat,
                 pointer,
                 serial,
                 surface_x,
                 surface_y,
             },
             DispatchMessageInner::MouseMotion {
+                seat,
                 time,
                 surface_x,
                 surface_y,
             } => DispatchMessage::MouseMotion {
+                seat,
                 time,
                 surface_x,
                 surface_y,
             },
             DispatchMessageInner::TouchDown {
+                seat,
                 serial,
                 time,
                 id,
                 x,
                 y,
             } => DispatchMessage::TouchDown {
+                seat,
                 serial,
                 time,
                 id,
@@ -530,46 +1048,62 @@ impl From<DispatchMessageInner> for DispatchMessage {
                 y,
             },
             DispatchMessageInner::TouchUp {
+                seat,
                 serial,
                 time,
                 id,
                 x,
                 y,
             } => DispatchMessage::TouchUp {
+                seat,
                 serial,
                 time,
                 id,
                 x,
                 y,
             },
-            DispatchMessageInner::TouchMotion { time, id, x, y } => {
-                DispatchMessage::TouchMotion { time, id, x, y }
-            }
-            DispatchMessageInner::TouchCancel { id, x, y } => {
-                DispatchMessage::TouchCancel { id, x, y }
+            DispatchMessageInner::TouchMotion {
+                seat,
+                time,
+                id,
+                x,
+                y,
+            } => DispatchMessage::TouchMotion {
+                seat,
+                time,
+                id,
+                x,
+                y,
+            },
+            DispatchMessageInner::TouchCancel { seat, id, x, y } => {
+                DispatchMessage::TouchCancel { seat, id, x, y }
             }
             DispatchMessageInner::Axis {
+                seat,
                 time,
                 scale,
                 horizontal,
                 vertical,
                 source,
             } => DispatchMessage::Axis {
+                seat,
                 time,
                 scale,
                 horizontal,
                 vertical,
                 source,
             },
-            DispatchMessageInner::Focused(id) => DispatchMessage::Focused(id),
-            DispatchMessageInner::Unfocus => DispatchMessage::Unfocus,
-            DispatchMessageInner::ModifiersChanged(modifier) => {
-                DispatchMessage::ModifiersChanged(modifier)
+            DispatchMessageInner::Focused { id, seat } => DispatchMessage::Focused { id, seat },
+            DispatchMessageInner::Unfocus { seat } => DispatchMessage::Unfocus { seat },
+            DispatchMessageInner::ModifiersChanged { modifiers, seat } => {
+                DispatchMessage::ModifiersChanged { modifiers, seat }
             }
             DispatchMessageInner::KeyboardInput {
+                seat,
                 event,
                 is_synthetic,
             } => DispatchMessage::KeyboardInput {
+                seat,
                 event,
                 is_synthetic,
             },
@@ -580,10 +1114,259 @@ impl From<DispatchMessageInner> for DispatchMessage {
                 scale_u32,
                 scale_float,
             },
-            DispatchMessageInner::Ime(ime) => DispatchMessage::Ime(ime),
+            DispatchMessageInner::Ime { ime, seat } => DispatchMessage::Ime { ime, seat },
             DispatchMessageInner::OutputChanged(output) => DispatchMessage::OutputChanged(output),
             DispatchMessageInner::Locked => DispatchMessage::Locked,
             DispatchMessageInner::LockFinished => DispatchMessage::LockFinished,
+            DispatchMessageInner::ActivationToken(token) => DispatchMessage::ActivationToken(token),
+            DispatchMessageInner::SwipeBegin {
+                seat,
+                id,
+                time,
+                fingers,
+            } => DispatchMessage::SwipeBegin {
+                seat,
+                id,
+                time,
+                fingers,
+            },
+            DispatchMessageInner::SwipeUpdate {
+                seat,
+                id,
+                time,
+                dx,
+                dy,
+            } => DispatchMessage::SwipeUpdate {
+                seat,
+                id,
+                time,
+                dx,
+                dy,
+            },
+            DispatchMessageInner::SwipeEnd {
+                seat,
+                id,
+                time,
+                cancelled,
+            } => DispatchMessage::SwipeEnd {
+                seat,
+                id,
+                time,
+                cancelled,
+            },
+            DispatchMessageInner::PinchBegin {
+                seat,
+                id,
+                time,
+                fingers,
+            } => DispatchMessage::PinchBegin {
+                seat,
+                id,
+                time,
+                fingers,
+            },
+            DispatchMessageInner::PinchUpdate {
+                seat,
+                id,
+                time,
+                dx,
+                dy,
+                scale,
+                rotation,
+            } => DispatchMessage::PinchUpdate {
+                seat,
+                id,
+                time,
+                dx,
+                dy,
+                scale,
+                rotation,
+            },
+            DispatchMessageInner::PinchEnd {
+                seat,
+                id,
+                time,
+                cancelled,
+            } => DispatchMessage::PinchEnd {
+                seat,
+                id,
+                time,
+                cancelled,
+            },
+            DispatchMessageInner::HoldBegin {
+                seat,
+                id,
+                time,
+                fingers,
+            } => DispatchMessage::HoldBegin {
+                seat,
+                id,
+                time,
+                fingers,
+            },
+            DispatchMessageInner::HoldEnd {
+                seat,
+                id,
+                time,
+                cancelled,
+            } => DispatchMessage::HoldEnd {
+                seat,
+                id,
+                time,
+                cancelled,
+            },
+            DispatchMessageInner::RelativeMotion {
+                seat,
+                dx,
+                dy,
+                dx_unaccel,
+                dy_unaccel,
+            } => DispatchMessage::RelativeMotion {
+                seat,
+                dx,
+                dy,
+                dx_unaccel,
+                dy_unaccel,
+            },
+            DispatchMessageInner::PointerLocked => DispatchMessage::PointerLocked,
+            DispatchMessageInner::PointerUnlocked => DispatchMessage::PointerUnlocked,
+            DispatchMessageInner::PointerConfined => DispatchMessage::PointerConfined,
+            DispatchMessageInner::PointerUnconfined => DispatchMessage::PointerUnconfined,
+            DispatchMessageInner::TabletToolProximityIn { seat, tool_type } => {
+                DispatchMessage::TabletToolProximityIn { seat, tool_type }
+            }
+            DispatchMessageInner::TabletToolProximityOut { seat } => {
+                DispatchMessage::TabletToolProximityOut { seat }
+            }
+            DispatchMessageInner::TabletToolDown { seat } => {
+                DispatchMessage::TabletToolDown { seat }
+            }
+            DispatchMessageInner::TabletToolUp { seat } => DispatchMessage::TabletToolUp { seat },
+            DispatchMessageInner::TabletToolMotion {
+                seat,
+                surface_x,
+                surface_y,
+            } => DispatchMessage::TabletToolMotion {
+                seat,
+                surface_x,
+                surface_y,
+            },
+            DispatchMessageInner::TabletToolPressure { pressure, seat } => {
+                DispatchMessage::TabletToolPressure { pressure, seat }
+            }
+            DispatchMessageInner::TabletToolTilt { seat, x, y } => {
+                DispatchMessage::TabletToolTilt { seat, x, y }
+            }
+            DispatchMessageInner::TabletToolRotation { degrees, seat } => {
+                DispatchMessage::TabletToolRotation { degrees, seat }
+            }
+            DispatchMessageInner::TabletToolButton {
+                seat,
+                button,
+                pressed,
+            } => DispatchMessage::TabletToolButton {
+                seat,
+                button,
+                pressed,
+            },
+            DispatchMessageInner::TabletPadButton {
+                seat,
+                button,
+                pressed,
+            } => DispatchMessage::TabletPadButton {
+                seat,
+                button,
+                pressed,
+            },
+            DispatchMessageInner::TabletPadRing { angle, seat } => {
+                DispatchMessage::TabletPadRing { angle, seat }
+            }
+            DispatchMessageInner::TabletPadStrip { position, seat } => {
+                DispatchMessage::TabletPadStrip { position, seat }
+            }
+            DispatchMessageInner::DndEnter {
+                seat,
+                mime_types,
+                x,
+                y,
+            } => DispatchMessage::DndEnter {
+                seat,
+                mime_types,
+                x,
+                y,
+            },
+            DispatchMessageInner::DndMotion { seat, x, y } => {
+                DispatchMessage::DndMotion { seat, x, y }
+            }
+            DispatchMessageInner::DndLeave { seat } => DispatchMessage::DndLeave { seat },
+            DispatchMessageInner::DndDrop {
+                seat,
+                mime_types,
+                x,
+                y,
+            } => DispatchMessage::DndDrop {
+                seat,
+                mime_types,
+                x,
+                y,
+            },
+            DispatchMessageInner::DndData {
+                seat,
+                mime_type,
+                data,
+                dropped,
+            } => DispatchMessage::DndData {
+                seat,
+                mime_type,
+                data,
+                dropped,
+            },
+            DispatchMessageInner::SelectionData {
+                seat,
+                kind,
+                mime_type,
+                data,
+            } => DispatchMessage::SelectionData {
+                seat,
+                kind,
+                mime_type,
+                data,
+            },
+            DispatchMessageInner::DragFinished { seat, cancelled } => {
+                DispatchMessage::DragFinished { seat, cancelled }
+            }
+            DispatchMessageInner::ToplevelStateChanged(toplevel_state) => {
+                DispatchMessage::ToplevelStateChanged(toplevel_state)
+            }
+            DispatchMessageInner::WmCapabilities(capabilities) => {
+                DispatchMessage::WmCapabilities(capabilities)
+            }
+            DispatchMessageInner::DecorationModeChanged { client_side } => {
+                DispatchMessage::DecorationModeChanged { client_side }
+            }
+            DispatchMessageInner::InputMethodDone(context) => {
+                DispatchMessage::InputMethodDone(context)
+            }
+            DispatchMessageInner::InputMethodUnavailable => DispatchMessage::InputMethodUnavailable,
+            DispatchMessageInner::Compose { state, seat } => {
+                DispatchMessage::Compose { state, seat }
+            }
+            DispatchMessageInner::KeyboardLayoutChanged { seat, index, name } => {
+                DispatchMessage::KeyboardLayoutChanged { seat, index, name }
+            }
+            DispatchMessageInner::SeatAdded(seat) => DispatchMessage::SeatAdded(seat),
+            DispatchMessageInner::SeatRemoved(seat) => DispatchMessage::SeatRemoved(seat),
+            DispatchMessageInner::InputPopupTextRectangle {
+                x,
+                y,
+                width,
+                height,
+            } => DispatchMessage::InputPopupTextRectangle {
+                x,
+                y,
+                width,
+                height,
+            },
         }
     }
 }
@@ -0,0 +1,243 @@
+//! Acting as the input method of the compositor through input-method-unstable-v2
+//!
+//! Opt in with [`WindowState::with_input_method`], the compositor allows one input method per
+//! seat and a regular application would take it away from the real one. It is bound when the
+//! compositor announces a single seat, with several pick one with
+//! [`WindowState::set_input_method_seat`].
+//!
+//! The state of the focused text input arrives double-buffered, and is reported as
+//! [`DispatchMessage::InputMethodDone`] once complete. Text is sent back with
+//! [`WindowState::commit_input_method_string`], [`WindowState::set_input_method_preedit`] and
+//! [`WindowState::delete_input_method_surrounding`], which take effect together on
+//! [`WindowState::commit_input_method`]. Candidate windows are created with
+//! [`ReturnData::NewInputMethodPopup`], placed next to the text by the compositor.
+//!
+//! [`DispatchMessage::InputMethodDone`]: crate::DispatchMessage::InputMethodDone
+//! [`ReturnData::NewInputMethodPopup`]: crate::ReturnData::NewInputMethodPopup
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, WEnum, delegate_noop};
+use wayland_protocols::wp::text_input::zv3::client::zwp_text_input_v3::{
+    ChangeCause, ContentHint, ContentPurpose,
+};
+use wayland_protocols_misc::zwp_input_method_v2::client::{
+    zwp_input_method_manager_v2::ZwpInputMethodManagerV2,
+    zwp_input_method_v2::{self, ZwpInputMethodV2},
+    zwp_input_popup_surface_v2::{self, ZwpInputPopupSurfaceV2},
+};
+
+use crate::id::Id;
+use crate::{DispatchMessageInner, SeatId, WindowState};
+
+/// Text around the cursor of the focused text input, positions are byte offsets into `text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SurroundingText {
+    pub text: String,
+    pub cursor: u32,
+    /// the other end of the selection, the same as `cursor` without one
+    pub anchor: u32,
+}
+
+/// What the focused text input told the input method
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputMethodContext {
+    /// a text input is focused and wants input
+    pub active: bool,
+    pub surrounding_text: Option<SurroundingText>,
+    /// whether the last change came from us or from the application
+    pub change_cause: ChangeCause,
+    pub content_hint: ContentHint,
+    pub content_purpose: ContentPurpose,
+}
+
+impl Default for InputMethodContext {
+    fn default() -> Self {
+        Self {
+            active: false,
+            surrounding_text: None,
+            change_cause: ChangeCause::InputMethod,
+            content_hint: ContentHint::None,
+            content_purpose: ContentPurpose::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct InputMethodState {
+    /// set with [`WindowState::with_input_method`]
+    pub(crate) requested: bool,
+    pub(crate) input_method: Option<ZwpInputMethodV2>,
+    /// applied by the next `done`
+    pending: InputMethodContext,
+    current: InputMethodContext,
+    /// number of `done` events, the serial a commit must carry
+    serial: u32,
+}
+
+impl<T> WindowState<T> {
+    fn input_method(&self) -> Option<&ZwpInputMethodV2> {
+        self.input_method.input_method.as_ref()
+    }
+
+    /// What the focused text input told us with the last `done`
+    pub fn input_method_context(&self) -> &InputMethodContext {
+        &self.input_method.current
+    }
+
+    /// Insert `text` at the cursor, replacing the selection, on the next commit.
+    ///
+    /// Returns `None` if the input method was not requested with
+    /// [`WindowState::with_input_method`], the compositor does not support
+    /// input-method-unstable-v2 or another input method is running.
+    pub fn commit_input_method_string(&self, text: String) -> Option<()> {
+        self.input_method()?.commit_string(text);
+        Some(())
+    }
+
+    /// Show `text` as preedit at the cursor on the next commit, with the cursor drawn from
+    /// `cursor_begin` to `cursor_end` in bytes, or hidden if both are -1.
+    ///
+    /// Returns `None` if there is no input method.
+    pub fn set_input_method_preedit(
+        &self,
+        text: String,
+        cursor_begin: i32,
+        cursor_end: i32,
+    ) -> Option<()> {
+        self.input_method()?
+            .set_preedit_string(text, cursor_begin, cursor_end);
+        Some(())
+    }
+
+    /// Delete `before` bytes before and `after` bytes after the cursor on the next commit.
+    ///
+    /// Returns `None` if there is no input method.
+    pub fn delete_input_method_surrounding(&self, before: u32, after: u32) -> Option<()> {
+        self.input_method()?.delete_surrounding_text(before, after);
+        Some(())
+    }
+
+    /// Apply the strings, preedit and deletion sent since the last commit.
+    ///
+    /// Returns `None` if there is no input method.
+    pub fn commit_input_method(&self) -> Option<()> {
+        self.input_method()?.commit(self.input_method.serial);
+        Some(())
+    }
+}
+
+impl<T: 'static> WindowState<T> {
+    /// Act as the input method of `seat` instead, when the compositor announced several seats.
+    ///
+    /// Returns `None` if the input method was not requested with
+    /// [`WindowState::with_input_method`], the compositor does not support
+    /// input-method-unstable-v2 or there is no such seat.
+    pub fn set_input_method_seat(&mut self, seat: SeatId) -> Option<()> {
+        if !self.input_method.requested {
+            return None;
+        }
+        let manager = self.input_method_manager.as_ref()?;
+        let seat = &self.seat_storage(seat)?.seat;
+        let qh = self.queue_handle.as_ref()?;
+        let input_method = manager.get_input_method(seat, qh, ());
+        if let Some(old) = self.input_method.input_method.replace(input_method) {
+            old.destroy();
+        }
+        self.input_method.pending = InputMethodContext::default();
+        self.input_method.current = InputMethodContext::default();
+        self.input_method.serial = 0;
+        Some(())
+    }
+}
+
+impl<T> Dispatch<ZwpInputMethodV2, ()> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwpInputMethodV2,
+        event: <ZwpInputMethodV2 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let pending = &mut state.input_method.pending;
+        match event {
+            zwp_input_method_v2::Event::Activate => {
+                // activation starts from a clean state
+                *pending = InputMethodContext {
+                    active: true,
+                    ..Default::default()
+                };
+            }
+            zwp_input_method_v2::Event::Deactivate => pending.active = false,
+            zwp_input_method_v2::Event::SurroundingText {
+                text,
+                cursor,
+                anchor,
+            } => {
+                pending.surrounding_text = Some(SurroundingText {
+                    text,
+                    cursor,
+                    anchor,
+                });
+            }
+            zwp_input_method_v2::Event::TextChangeCause {
+                cause: WEnum::Value(cause),
+            } => pending.change_cause = cause,
+            zwp_input_method_v2::Event::ContentType { hint, purpose } => {
+                if let WEnum::Value(hint) = hint {
+                    pending.content_hint = hint;
+                }
+                if let WEnum::Value(purpose) = purpose {
+                    pending.content_purpose = purpose;
+                }
+            }
+            zwp_input_method_v2::Event::Done => {
+                let input_method = &mut state.input_method;
+                input_method.serial = input_method.serial.wrapping_add(1);
+                input_method.current = input_method.pending.clone();
+                state.message.push((
+                    None,
+                    DispatchMessageInner::InputMethodDone(input_method.current.clone()),
+                ));
+            }
+            zwp_input_method_v2::Event::Unavailable => {
+                log::warn!("another input method is running, input-method-unstable-v2 is off");
+                state.input_method.input_method = None;
+                proxy.destroy();
+                state
+                    .message
+                    .push((None, DispatchMessageInner::InputMethodUnavailable));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T> Dispatch<ZwpInputPopupSurfaceV2, Id> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpInputPopupSurfaceV2,
+        event: <ZwpInputPopupSurfaceV2 as Proxy>::Event,
+        id: &Id,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let zwp_input_popup_surface_v2::Event::TextInputRectangle {
+            x,
+            y,
+            width,
+            height,
+        } = event
+        {
+            state.message.push((
+                Some(*id),
+                DispatchMessageInner::InputPopupTextRectangle {
+                    x,
+                    y,
+                    width,
+                    height,
+                },
+            ));
+        }
+    }
+}
+
+delegate_noop!(@<T> WindowState<T>: ignore ZwpInputMethodManagerV2);
@@ -66,6 +66,7 @@
 //!                 time,
 //!                 surface_x,
 //!                 surface_y,
+//!                 ..
 //!             }) => {
 //!                 println!("{time}, {surface_x}, {surface_y}");
 //!                 ReturnData::None
@@ -101,20 +102,31 @@
 //! }
 //! ```
 //!
+pub use events::NewInputMethodPopupSettings;
 pub use events::NewInputPanelSettings;
 pub use events::NewLayerShellSettings;
 pub use events::NewXdgWindowSettings;
 pub use events::OutputOption;
 pub use events::{NewPopUpSettings, PopUpRepositionSettings, PopupPlacement};
+pub use events::{ToplevelState, WmCapabilities};
 pub use sctk::output::OutputInfo;
+pub use waycrate_xkbkeycode::compose;
 pub use waycrate_xkbkeycode::keyboard;
 pub use waycrate_xkbkeycode::xkb_keyboard;
 pub mod blur;
+pub mod clipboard;
+pub mod dnd;
 pub mod dpi;
 mod events;
+pub mod input_method;
 mod seat;
 mod size;
 mod strtoshape;
+mod tablet;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod virtual_keyboard;
+pub mod virtual_pointer;
 
 use events::DispatchMessageInner;
 use size::warn_if_exclusive_zone_ignored;
@@ -137,13 +149,14 @@ use sctk::{
 use wayland_backend::client::ObjectId;
 use wayland_client::protocol::wl_surface;
 use wayland_client::{
-    ConnectError, Connection, Dispatch, DispatchError, EventQueue, Proxy, QueueHandle,
+    ConnectError, Connection, Dispatch, DispatchError, EventQueue, Proxy, QueueHandle, WEnum,
     delegate_noop,
     globals::{BindError, GlobalError, GlobalList, registry_queue_init},
     protocol::{
         wl_buffer::WlBuffer,
         wl_callback::{Event as WlCallbackEvent, WlCallback},
         wl_compositor::WlCompositor,
+        wl_data_device_manager::WlDataDeviceManager,
         wl_display::WlDisplay,
         wl_keyboard::KeyState,
         wl_output::{self, WlOutput},
@@ -194,20 +207,41 @@ use wayland_protocols::wp::cursor_shape::v1::client::{
     wp_cursor_shape_manager_v1::WpCursorShapeManagerV1,
 };
 
+use wayland_protocols_misc::zwp_input_method_v2::client::{
+    zwp_input_method_manager_v2::ZwpInputMethodManagerV2,
+    zwp_input_popup_surface_v2::ZwpInputPopupSurfaceV2,
+};
 use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::{
     zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1,
     zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
 };
+use wayland_protocols_wlr::virtual_pointer::v1::client::zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1;
 
 use wayland_protocols::ext::background_effect::v1::client::{
     ext_background_effect_manager_v1::ExtBackgroundEffectManagerV1,
     ext_background_effect_surface_v1::ExtBackgroundEffectSurfaceV1,
 };
 
+use wayland_protocols::wp::idle_inhibit::zv1::client::{
+    zwp_idle_inhibit_manager_v1::ZwpIdleInhibitManagerV1, zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1,
+};
+use wayland_protocols::wp::pointer_constraints::zv1::client::{
+    zwp_confined_pointer_v1::{self, ZwpConfinedPointerV1},
+    zwp_locked_pointer_v1::{self, ZwpLockedPointerV1},
+    zwp_pointer_constraints_v1::{Lifetime, ZwpPointerConstraintsV1},
+};
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gestures_v1::ZwpPointerGesturesV1;
+use wayland_protocols::wp::primary_selection::zv1::client::zwp_primary_selection_device_manager_v1::ZwpPrimarySelectionDeviceManagerV1;
+use wayland_protocols::wp::relative_pointer::zv1::client::zwp_relative_pointer_manager_v1::ZwpRelativePointerManagerV1;
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_manager_v2::ZwpTabletManagerV2;
 use wayland_protocols::wp::text_input::zv3::client::{
     zwp_text_input_manager_v3::ZwpTextInputManagerV3,
     zwp_text_input_v3::{self, ContentHint, ContentPurpose, ZwpTextInputV3},
 };
+use wayland_protocols::xdg::activation::v1::client::{
+    xdg_activation_token_v1::{self, XdgActivationTokenV1},
+    xdg_activation_v1::XdgActivationV1,
+};
 use wayland_protocols::xdg::decoration::zv1::client::{
     zxdg_decoration_manager_v1::ZxdgDecorationManagerV1,
     zxdg_toplevel_decoration_v1::{self, ZxdgToplevelDecorationV1},
@@ -230,6 +264,7 @@ use std::time::Instant;
 
 use crate::blur::{BlurOption, BlurRegion};
 use crate::seat::SeatStorage;
+pub use seat::SeatId;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ExShellEventError {
@@ -291,11 +326,17 @@ pub mod reexport {
         pub use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::Shape;
     }
     pub mod xdg_toplevel {
-        pub use wayland_protocols::xdg::shell::client::xdg_toplevel::XdgToplevel;
+        pub use wayland_protocols::xdg::shell::client::xdg_toplevel::{ResizeEdge, XdgToplevel};
     }
     pub mod wp_viewport {
         pub use wayland_protocols::wp::viewporter::client::wp_viewport::WpViewport;
     }
+    pub mod zwp_tablet_tool_v2 {
+        pub use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_tool_v2::Type;
+    }
+    pub mod zwp_pointer_constraints_v1 {
+        pub use wayland_protocols::wp::pointer_constraints::zv1::client::zwp_pointer_constraints_v1::Lifetime;
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -304,6 +345,7 @@ pub enum WlShellType {
     PopUp,
     XdgTopLevel,
     InputPanel,
+    InputMethodPopup,
     SessionLock,
 }
 
@@ -321,6 +363,7 @@ enum Shell {
     PopUp((XdgPopup, XdgSurface)),
     XdgTopLevel((XdgToplevel, XdgSurface, Option<ZxdgToplevelDecorationV1>)),
     InputPanel(#[allow(unused)] ZwpInputPanelSurfaceV1),
+    InputMethodPopup(ZwpInputPopupSurfaceV2),
     SessionLock(ExtSessionLockSurfaceV1),
 }
 
@@ -382,6 +425,7 @@ impl Shell {
             }
             Self::LayerShell(shell) => shell.destroy(),
             Self::InputPanel(_) => {}
+            Self::InputMethodPopup(popup) => popup.destroy(),
             Self::SessionLock(lock) => lock.destroy(),
         }
     }
@@ -423,7 +467,7 @@ impl<T> WindowStateUnitBuilder<T> {
         wmcompositor: WlCompositor,
         shell: Shell,
     ) -> Self {
-        let configured = matches!(shell, Shell::InputPanel(_));
+        let configured = matches!(shell, Shell::InputPanel(_) | Shell::InputMethodPopup(_));
         Self {
             inner: WindowStateUnit {
                 id,
@@ -436,6 +480,10 @@ impl<T> WindowStateUnitBuilder<T> {
                     toplevel: shell.top_level(),
                 }),
                 wmcompositor,
+                idle_inhibit_manager: None,
+                idle_inhibitor: None,
+                pointer_constraints: None,
+                pointer_constraint: None,
                 shell,
                 parent: None,
                 size: (0, 0),
@@ -449,6 +497,9 @@ impl<T> WindowStateUnitBuilder<T> {
                 configured,
                 blur_option: BlurOption::None,
                 pending_reposition: None,
+                toplevel_state: ToplevelState::default(),
+                wm_capabilities: None,
+                client_side_decorated: false,
                 effect: None,
                 // Unknown why it is 120
                 scale: 120,
@@ -500,6 +551,11 @@ impl<T> WindowStateUnitBuilder<T> {
         self
     }
 
+    fn client_side_decorated(mut self, client_side_decorated: bool) -> Self {
+        self.inner.client_side_decorated = client_side_decorated;
+        self
+    }
+
     fn binding(mut self, binding: Option<T>) -> Self {
         self.inner.binding = binding;
         self
@@ -540,6 +596,10 @@ pub struct WindowStateUnit<T> {
     /// not refcounted, so destroying it here would leave the renderer a dead one.
     window: Arc<WindowWrapper>,
     wmcompositor: WlCompositor,
+    idle_inhibit_manager: Option<ZwpIdleInhibitManagerV1>,
+    idle_inhibitor: Option<ZwpIdleInhibitorV1>,
+    pointer_constraints: Option<ZwpPointerConstraintsV1>,
+    pointer_constraint: Option<PointerConstraint>,
     size: (u32, u32),
     /// Only meaningful for LayerShell
     anchor: Anchor,
@@ -562,6 +622,12 @@ pub struct WindowStateUnit<T> {
 
     /// Only meaningful for PopUp
     pending_reposition: Option<u32>,
+    /// Only meaningful for XdgTopLevel
+    toplevel_state: ToplevelState,
+    /// Only meaningful for XdgTopLevel
+    wm_capabilities: Option<WmCapabilities>,
+    /// Only meaningful for XdgTopLevel
+    client_side_decorated: bool,
 
     scale: u32,
     request_flag: WindowStateUnitRequestFlag,
@@ -582,6 +648,41 @@ impl<T> Drop for WindowStateUnit<T> {
         if let Some(effect) = &self.effect {
             effect.destroy();
         }
+        if let Some(inhibitor) = &self.idle_inhibitor {
+            inhibitor.destroy();
+        }
+        if let Some(constraint) = self.pointer_constraint.take() {
+            constraint.destroy();
+        }
+    }
+}
+
+/// A lock or confinement of the pointer to a surface
+#[derive(Debug)]
+enum PointerConstraint {
+    Locked(ZwpLockedPointerV1, Lifetime),
+    Confined(ZwpConfinedPointerV1, Lifetime),
+}
+
+impl PointerConstraint {
+    fn destroy(self) {
+        match self {
+            Self::Locked(locked, _) => locked.destroy(),
+            Self::Confined(confined, _) => confined.destroy(),
+        }
+    }
+
+    /// Whether this constraint is `proxy` and cannot come back once it ended. A persistent one
+    /// is active again the next time the compositor allows it.
+    fn ends_with(&self, proxy: &ObjectId) -> bool {
+        match self {
+            Self::Locked(locked, lifetime) => {
+                *lifetime == Lifetime::Oneshot && locked.id() == *proxy
+            }
+            Self::Confined(confined, lifetime) => {
+                *lifetime == Lifetime::Oneshot && confined.id() == *proxy
+            }
+        }
     }
 }
 
@@ -604,6 +705,7 @@ impl<T> WindowStateUnit<T> {
             Shell::XdgTopLevel(_) => WlShellType::XdgTopLevel,
             Shell::SessionLock(_) => WlShellType::SessionLock,
             Shell::InputPanel(_) => WlShellType::InputPanel,
+            Shell::InputMethodPopup(_) => WlShellType::InputMethodPopup,
         }
     }
 
@@ -634,6 +736,31 @@ impl<T> WindowStateUnit<T> {
     pub fn gen_wrapper(&self) -> Arc<WindowWrapper> {
         self.window.clone()
     }
+
+    /// Whether this surface currently keeps the screen from idling
+    pub fn is_idle_inhibited(&self) -> bool {
+        self.idle_inhibitor.is_some()
+    }
+
+    /// Whether a pointer lock or confinement was requested on this surface
+    pub fn is_pointer_constrained(&self) -> bool {
+        self.pointer_constraint.is_some()
+    }
+
+    /// States of the xdg toplevel from its last configure, all unset for other surfaces
+    pub fn toplevel_state(&self) -> ToplevelState {
+        self.toplevel_state
+    }
+
+    /// Window management the compositor offers for the xdg toplevel, `None` until it tells
+    pub fn wm_capabilities(&self) -> Option<WmCapabilities> {
+        self.wm_capabilities
+    }
+
+    /// Whether the xdg toplevel has to draw its own frame
+    pub fn is_client_side_decorated(&self) -> bool {
+        self.client_side_decorated
+    }
 }
 impl<T> WindowStateUnit<T> {
     #[inline]
@@ -700,6 +827,88 @@ impl<T> rwh_06::HasDisplayHandle for WindowState<T> {
 }
 
 impl<T: 'static> WindowStateUnit<T> {
+    /// Keep the screen from blanking, locking or dimming while this surface is visible.
+    ///
+    /// Returns `None` if the compositor does not support idle-inhibit-unstable-v1.
+    pub fn set_idle_inhibit(&mut self, enabled: bool) -> Option<()> {
+        let manager = self.idle_inhibit_manager.as_ref()?;
+        match (enabled, self.idle_inhibitor.take()) {
+            (true, None) => {
+                self.idle_inhibitor =
+                    Some(manager.create_inhibitor(&self.window.wl_surface, &self.qh, ()));
+            }
+            (true, inhibitor) => self.idle_inhibitor = inhibitor,
+            (false, Some(inhibitor)) => inhibitor.destroy(),
+            (false, None) => {}
+        }
+        Some(())
+    }
+
+    /// Lock `pointer` in place while it is over this surface, motion is then only reported by
+    /// [`DispatchMessage::RelativeMotion`]. The lock only applies within `region`, or the whole
+    /// surface if `None`. A previous lock or confinement of this surface is released.
+    ///
+    /// Returns `None` if the compositor does not support pointer-constraints-unstable-v1.
+    pub fn lock_pointer(
+        &mut self,
+        pointer: &WlPointer,
+        region: Option<&WlRegion>,
+        lifetime: Lifetime,
+    ) -> Option<()> {
+        let constraints = self.pointer_constraints.as_ref()?;
+        let locked = constraints.lock_pointer(
+            &self.window.wl_surface,
+            pointer,
+            region,
+            lifetime,
+            &self.qh,
+            self.id,
+        );
+        if let Some(constraint) = self
+            .pointer_constraint
+            .replace(PointerConstraint::Locked(locked, lifetime))
+        {
+            constraint.destroy();
+        }
+        Some(())
+    }
+
+    /// Keep `pointer` within `region` of this surface, or the whole surface if `None`. A
+    /// previous lock or confinement of this surface is released.
+    ///
+    /// Returns `None` if the compositor does not support pointer-constraints-unstable-v1.
+    pub fn confine_pointer(
+        &mut self,
+        pointer: &WlPointer,
+        region: Option<&WlRegion>,
+        lifetime: Lifetime,
+    ) -> Option<()> {
+        let constraints = self.pointer_constraints.as_ref()?;
+        let confined = constraints.confine_pointer(
+            &self.window.wl_surface,
+            pointer,
+            region,
+            lifetime,
+            &self.qh,
+            self.id,
+        );
+        if let Some(constraint) = self
+            .pointer_constraint
+            .replace(PointerConstraint::Confined(confined, lifetime))
+        {
+            constraint.destroy();
+        }
+        Some(())
+    }
+
+    /// Release the lock or confinement of this surface, if any. A oneshot one is released when
+    /// it ends.
+    pub fn release_pointer(&mut self) {
+        if let Some(constraint) = self.pointer_constraint.take() {
+            constraint.destroy();
+        }
+    }
+
     pub fn set_blur_option(&mut self, blur_option: BlurOption) {
         self.blur_option = blur_option;
         if let Some(effect) = &self.effect {
@@ -832,6 +1041,75 @@ impl<T> WindowStateUnit<T> {
         }
     }
 
+    fn xdg_toplevel(&self) -> Option<&XdgToplevel> {
+        match &self.shell {
+            Shell::XdgTopLevel((toplevel, _, _)) => Some(toplevel),
+            _ => None,
+        }
+    }
+
+    /// set the app id of the xdg toplevel, `None` for other surfaces
+    pub fn set_app_id(&self, app_id: String) -> Option<()> {
+        self.xdg_toplevel()?.set_app_id(app_id);
+        Some(())
+    }
+
+    /// set the minimum size of the xdg toplevel, or remove it with `None`
+    pub fn set_min_size(&mut self, size: Option<PixelSize>) -> Option<()> {
+        let (width, height) = size.map_or((0, 0), PixelSize::to_set_i32);
+        self.xdg_toplevel()?.set_min_size(width, height);
+        // double-buffered, applied by the commit of the next frame
+        self.request_refresh(RefreshRequest::NextFrame);
+        Some(())
+    }
+
+    /// set the maximum size of the xdg toplevel, or remove it with `None`
+    pub fn set_max_size(&mut self, size: Option<PixelSize>) -> Option<()> {
+        let (width, height) = size.map_or((0, 0), PixelSize::to_set_i32);
+        self.xdg_toplevel()?.set_max_size(width, height);
+        // double-buffered, applied by the commit of the next frame
+        self.request_refresh(RefreshRequest::NextFrame);
+        Some(())
+    }
+
+    /// ask the compositor to maximize or unmaximize the xdg toplevel, the outcome arrives as
+    /// [`DispatchMessage::ToplevelStateChanged`]
+    pub fn set_maximized(&self, maximized: bool) -> Option<()> {
+        let toplevel = self.xdg_toplevel()?;
+        if maximized {
+            toplevel.set_maximized();
+        } else {
+            toplevel.unset_maximized();
+        }
+        Some(())
+    }
+
+    /// ask the compositor to make the xdg toplevel fullscreen on `output`, or any output if
+    /// `None`, or to leave fullscreen
+    pub fn set_fullscreen(&self, fullscreen: bool, output: Option<&WlOutput>) -> Option<()> {
+        let toplevel = self.xdg_toplevel()?;
+        if fullscreen {
+            toplevel.set_fullscreen(output);
+        } else {
+            toplevel.unset_fullscreen();
+        }
+        Some(())
+    }
+
+    /// ask the compositor to minimize the xdg toplevel, there is no way to unminimize it
+    pub fn set_minimized(&self) -> Option<()> {
+        self.xdg_toplevel()?.set_minimized();
+        Some(())
+    }
+
+    /// resize the xdg toplevel, only lasts until the compositor configures a size itself
+    pub fn resize_toplevel(&mut self, size: PixelSize) -> Option<()> {
+        self.xdg_toplevel()?;
+        self.size = size.to_set();
+        self.request_refresh(RefreshRequest::NextFrame);
+        Some(())
+    }
+
     /// you can use this function to set a binding data. the message passed back contain
     /// a index, you can use that to get the unit. It will be very useful, because you can
     /// use the binding data to operate the file binding to the buffer. you can take
@@ -986,6 +1264,8 @@ struct KeyboardTokenState {
     surface_id: Option<id::Id>,
     pressed_state: ElementState,
     object_id: ObjectId,
+    /// the seat of the keyboard
+    seat: ObjectId,
 }
 
 #[derive(Debug)]
@@ -1035,8 +1315,6 @@ impl From<Connection> for WithConnection {
 #[derive(Debug)]
 pub struct WindowState<T> {
     outputs: Vec<wl_output::WlOutput>,
-    keyboard_focus: Option<WlSurface>,
-    active_surfaces: HashMap<Option<i32>, (WlSurface, Option<id::Id>)>,
     units: Vec<WindowStateUnit<T>>,
     message: Vec<(Option<id::Id>, DispatchMessageInner)>,
 
@@ -1062,9 +1340,16 @@ pub struct WindowState<T> {
     // base managers
     seat_state: Option<SeatState>,
     seats: HashMap<ObjectId, SeatStorage>,
+    /// the seat of the latest input
+    last_seat: Option<ObjectId>,
+    /// the serial of the latest key press, button press or touch, on any seat
+    last_input_serial: Option<u32>,
     seat_back: Option<WlSeat>,
 
     virtual_keyboard: Option<ZwpVirtualKeyboardV1>,
+    virtual_keyboard_manager: Option<ZwpVirtualKeyboardManagerV1>,
+    virtual_keyboard_state: virtual_keyboard::VirtualKeyboardState,
+    virtual_pointer: virtual_pointer::VirtualPointerState,
 
     // states
     default_namespace: String,
@@ -1083,24 +1368,37 @@ pub struct WindowState<T> {
     closed_ids: Vec<id::Id>,
 
     to_be_released_key: Option<VirtualKeyRelease>,
+    /// pipes to read on the event loop
+    pending_reads: Vec<clipboard::PendingRead>,
+    /// pipes to write on the event loop
+    pending_writes: Vec<clipboard::PendingWrite>,
 
     last_unit_index: usize,
     last_wloutput: Option<WlOutput>,
 
     return_data: Vec<ReturnData<T>>,
-    finger_locations: HashMap<i32, (f64, f64)>,
-    enter_serial: Option<u32>,
-    button_serial: Option<u32>,
 
     start_mode: StartMode,
     init_finished: bool,
     events_transparent: bool,
 
     text_input_manager: Option<ZwpTextInputManagerV3>,
-    text_input: Option<ZwpTextInputV3>,
-    text_inputs: Vec<ZwpTextInputV3>,
+    pointer_gestures: Option<ZwpPointerGesturesV1>,
+    relative_pointer_manager: Option<ZwpRelativePointerManagerV1>,
+    pointer_constraints: Option<ZwpPointerConstraintsV1>,
+    tablet_manager: Option<ZwpTabletManagerV2>,
+    tablet: tablet::TabletState,
+    data_device_manager: Option<WlDataDeviceManager>,
+    primary_selection_manager: Option<ZwpPrimarySelectionDeviceManagerV1>,
+    selection: clipboard::SelectionState,
+    dnd: dnd::DndState,
+    input_method_manager: Option<ZwpInputMethodManagerV2>,
+    input_method: input_method::InputMethodState,
+    queue_handle: Option<QueueHandle<WindowState<T>>>,
 
     xdg_decoration_manager: Option<ZxdgDecorationManagerV1>,
+    xdg_activation: Option<XdgActivationV1>,
+    idle_inhibit_manager: Option<ZwpIdleInhibitManagerV1>,
 
     ime_purpose: ImePurpose,
     ime_allowed: bool,
@@ -1113,7 +1411,8 @@ impl<T: 'static> WindowState<T> {
 
     /// Take the serial to use for the next popup grab, consuming it.
     pub fn take_popup_grab_serial(&mut self) -> Option<u32> {
-        self.button_serial.take().or(self.enter_serial)
+        let seat = self.current_seat_mut()?;
+        seat.button_serial.take().or(seat.enter_serial)
     }
 
     /// Compute the minimum dispatch timeout across all window units.
@@ -1121,6 +1420,10 @@ impl<T: 'static> WindowState<T> {
     /// meaning the event loop can sleep indefinitely until an external
     /// event (Wayland, channel, etc.) arrives.
     fn min_dispatch_timeout(&self) -> Option<Duration> {
+        // data returned while refreshing is handled on the next iteration
+        if !self.return_data.is_empty() {
+            return Some(Duration::ZERO);
+        }
         let mut min: Option<Duration> = None;
         for unit in &self.units {
             match unit.refresh_timeout() {
@@ -1148,10 +1451,8 @@ impl<T: 'static> WindowState<T> {
     fn remove_shell(&mut self, id: id::Id) -> Option<()> {
         let index = self.units.iter().position(|unit| unit.id == id)?;
 
-        if self.keyboard_focus.as_ref() == Some(&self.units[index].window.wl_surface) {
-            self.keyboard_focus = None;
-        }
-        self.units.remove(index);
+        let unit = self.units.remove(index);
+        self.forget_keyboard_focus(&unit.window.wl_surface);
         Some(())
     }
 
@@ -1177,6 +1478,86 @@ impl<T: 'static> WindowState<T> {
         self.last_wloutput.take();
     }
 
+    /// Request an xdg-activation token for the surface of `id`, using the serial of the last
+    /// pointer button or enter. The token is sent back as [`DispatchMessage::ActivationToken`] on
+    /// that surface.
+    ///
+    /// Returns `None` if the compositor does not support xdg-activation-v1 or there is no such
+    /// surface.
+    pub fn request_activation_token(&mut self, id: id::Id) -> Option<()> {
+        let activation = self.xdg_activation.clone()?;
+        let unit = self.units.iter().find(|unit| unit.id == id)?;
+        let (qh, surface) = (unit.qh.clone(), unit.window.wl_surface.clone());
+        // the serial is only consumed once the token can be requested
+        let serial = self.take_popup_grab_serial();
+        let seat = self.current_seat().map(|seat| seat.seat.clone());
+        let token = activation.get_activation_token(&qh, id);
+        if let (Some(serial), Some(seat)) = (serial, seat) {
+            token.set_serial(serial, &seat);
+        }
+        token.set_surface(&surface);
+        token.commit();
+        Some(())
+    }
+
+    /// Activate the surface of `id` with a token from another client, like the
+    /// [`ACTIVATION_TOKEN_ENV`] we were started with.
+    ///
+    /// Returns `None` if the compositor does not support xdg-activation-v1 or there is no such
+    /// surface.
+    pub fn activate_with_token(&self, id: id::Id, token: &str) -> Option<()> {
+        let activation = self.xdg_activation.as_ref()?;
+        let unit = self.get_unit_with_id(id)?;
+        activation.activate(token.to_owned(), &unit.window.wl_surface);
+        Some(())
+    }
+
+    /// The xdg toplevel of `id` with the seat and serial of the button press that started an
+    /// interactive request. The serial is consumed, it can only start one.
+    fn toplevel_grab(&mut self, id: id::Id) -> Option<(XdgToplevel, WlSeat, u32)> {
+        let toplevel = self.get_unit_with_id(id)?.xdg_toplevel()?.clone();
+        // the seat whose pointer pressed the button on the window
+        let seat = self.seats.values_mut().find(|seat| {
+            seat.button_serial.is_some()
+                && seat
+                    .active_surfaces
+                    .get(&None)
+                    .is_some_and(|(_, surface_id)| *surface_id == Some(id))
+        })?;
+        let serial = seat.button_serial.take()?;
+        Some((toplevel, seat.seat.clone(), serial))
+    }
+
+    /// Start moving the xdg toplevel of `id` with the pointer, while the button pressed on it
+    /// is still down.
+    ///
+    /// Returns `None` if there is no such toplevel or no pressed button.
+    pub fn start_move(&mut self, id: id::Id) -> Option<()> {
+        let (toplevel, seat, serial) = self.toplevel_grab(id)?;
+        toplevel._move(&seat, serial);
+        Some(())
+    }
+
+    /// Start resizing the xdg toplevel of `id` from `edge` with the pointer, while the button
+    /// pressed on it is still down.
+    ///
+    /// Returns `None` if there is no such toplevel or no pressed button.
+    pub fn start_resize(&mut self, id: id::Id, edge: xdg_toplevel::ResizeEdge) -> Option<()> {
+        let (toplevel, seat, serial) = self.toplevel_grab(id)?;
+        toplevel.resize(&seat, serial, edge);
+        Some(())
+    }
+
+    /// Show the window menu of the compositor for the xdg toplevel of `id` at `x`, `y` on the
+    /// surface, after a button press on it.
+    ///
+    /// Returns `None` if there is no such toplevel or no pressed button.
+    pub fn show_window_menu(&mut self, id: id::Id, x: i32, y: i32) -> Option<()> {
+        let (toplevel, seat, serial) = self.toplevel_grab(id)?;
+        toplevel.show_window_menu(&seat, serial, x, y);
+        Some(())
+    }
+
     fn last_output(&mut self) -> Option<WlOutput> {
         if self.last_wloutput.is_none() {
             self.last_wloutput = self.outputs.get(self.last_unit_index).cloned();
@@ -1192,6 +1573,9 @@ impl<T: 'static> WindowState<T> {
 /// Simple WindowState, without any data binding or info
 pub type WindowStateSimple = WindowState<()>;
 
+/// The environment variable an activation token is passed to a spawned process with
+pub const ACTIVATION_TOKEN_ENV: &str = "XDG_ACTIVATION_TOKEN";
+
 impl<T> WindowState<T> {
     pub fn display_wrapper(&self) -> DisplayWrapper {
         DisplayWrapper {
@@ -1213,7 +1597,9 @@ impl<T> WindowState<T> {
         &self.units
     }
 
-    fn push_window(&mut self, window_state_unit: WindowStateUnit<T>) {
+    fn push_window(&mut self, mut window_state_unit: WindowStateUnit<T>) {
+        window_state_unit.idle_inhibit_manager = self.idle_inhibit_manager.clone();
+        window_state_unit.pointer_constraints = self.pointer_constraints.clone();
         let surface = window_state_unit.window.wl_surface.clone();
         self.units.push(window_state_unit);
         // update newest window output for `OutputOption::LastOutput`
@@ -1312,10 +1698,18 @@ impl<T> WindowState<T> {
 
     pub fn set_ime_allowed(&mut self, ime_allowed: bool) {
         self.ime_allowed = ime_allowed;
-        for text_input in &self.text_inputs {
+        for seat in self.seats.values_mut() {
+            if !ime_allowed {
+                seat.ime_surrounding_text = None;
+            }
+            let Some(text_input) = seat.text_input.as_ref().filter(|_| seat.text_input_entered)
+            else {
+                continue;
+            };
             if ime_allowed {
                 text_input.enable();
                 text_input.set_content_type_by_purpose(self.ime_purpose);
+                send_ime_surrounding_text(seat);
             } else {
                 text_input.disable();
             }
@@ -1342,7 +1736,7 @@ impl<T> WindowState<T> {
         let size: dpi::LogicalSize<u32> = size.to_logical(scale_factor);
         let (x, y) = (position.x as i32, position.y as i32);
         let (width, height) = (size.width as i32, size.height as i32);
-        for text_input in self.text_inputs.iter() {
+        for text_input in self.entered_text_inputs() {
             text_input.set_cursor_rectangle(x, y, width, height);
             text_input.commit();
         }
@@ -1350,31 +1744,133 @@ impl<T> WindowState<T> {
 
     pub fn set_ime_purpose(&mut self, purpose: ImePurpose) {
         self.ime_purpose = purpose;
-        self.text_input.iter().for_each(|text_input| {
+        for text_input in self.entered_text_inputs() {
             text_input.set_content_type_by_purpose(purpose);
             text_input.commit();
-        });
+        }
+    }
+
+    /// Tell the input method the text around the cursor of the focused text field on the surface
+    /// of `id`, with the byte offsets of the cursor and of the other end of the selection in it.
+    /// Only the seats whose text input is on that surface get it. IMEs need it for autocorrect
+    /// and word prediction. text-input-v3 limits the text to 4000 bytes, longer text is cut
+    /// around the cursor.
+    pub fn set_ime_surrounding_text(
+        &mut self,
+        text: &str,
+        cursor: usize,
+        anchor: usize,
+        id: id::Id,
+    ) {
+        let Some(surface) = self
+            .get_unit_with_id(id)
+            .map(|unit| unit.window.wl_surface.clone())
+        else {
+            return;
+        };
+        let surrounding = clamp_surrounding_text(text, cursor, anchor);
+        let ime_allowed = self.ime_allowed();
+        for seat in self.seats.values_mut() {
+            if text_input_surface(seat).as_ref() != Some(&surface) {
+                continue;
+            }
+            seat.ime_surrounding_text = Some(surrounding.clone());
+            let Some(text_input) = seat.text_input.as_ref() else {
+                continue;
+            };
+            if ime_allowed {
+                send_ime_surrounding_text(seat);
+                text_input.commit();
+                seat.ime_text_changed = false;
+            }
+        }
+    }
+
+    /// The text inputs of the seats, which entered one of our surfaces
+    fn entered_text_inputs(&self) -> impl Iterator<Item = &ZwpTextInputV3> {
+        self.seats
+            .values()
+            .filter(|seat| seat.text_input_entered)
+            .filter_map(|seat| seat.text_input.as_ref())
     }
 
     #[inline]
     pub fn text_input_entered(&mut self, text_input: &ZwpTextInputV3) {
-        if !self.text_inputs.iter().any(|t| t == text_input) {
-            self.text_inputs.push(text_input.clone());
+        if let Some(seat) = self.text_input_seat_mut(text_input) {
+            seat.text_input_entered = true;
         }
     }
 
     #[inline]
     pub fn text_input_left(&mut self, text_input: &ZwpTextInputV3) {
-        if let Some(position) = self.text_inputs.iter().position(|t| t == text_input) {
-            self.text_inputs.remove(position);
+        if let Some(seat) = self.text_input_seat_mut(text_input) {
+            seat.text_input_entered = false;
         }
     }
 
+    fn text_input_seat_mut(&mut self, text_input: &ZwpTextInputV3) -> Option<&mut SeatStorage> {
+        self.seats
+            .values_mut()
+            .find(|seat| seat.text_input.as_ref() == Some(text_input))
+    }
+
     fn ime_purpose(&self) -> ImePurpose {
         self.ime_purpose
     }
 }
 
+/// The most text-input-v3 takes as surrounding text, in bytes.
+const MAX_SURROUNDING_TEXT: usize = 4000;
+
+/// Cut `text` to [`MAX_SURROUNDING_TEXT`] bytes around the cursor, on char boundaries, and move
+/// the offsets along. The selection is kept as far as it fits.
+fn clamp_surrounding_text(text: &str, cursor: usize, anchor: usize) -> (String, usize, usize) {
+    let cursor = cursor.min(text.len());
+    let anchor = anchor.min(text.len());
+    if text.len() <= MAX_SURROUNDING_TEXT {
+        return (text.to_owned(), cursor, anchor);
+    }
+    let (low, high) = (cursor.min(anchor), cursor.max(anchor));
+    let start = if high - low <= MAX_SURROUNDING_TEXT {
+        low.saturating_sub((MAX_SURROUNDING_TEXT - (high - low)) / 2)
+    } else {
+        cursor.saturating_sub(MAX_SURROUNDING_TEXT / 2)
+    }
+    .min(text.len() - MAX_SURROUNDING_TEXT);
+    let start = (start..=cursor)
+        .find(|index| text.is_char_boundary(*index))
+        .unwrap_or(cursor);
+    let end = (cursor..=start + MAX_SURROUNDING_TEXT)
+        .rev()
+        .find(|index| text.is_char_boundary(*index))
+        .unwrap_or(cursor);
+    (
+        text[start..end].to_owned(),
+        cursor - start,
+        anchor.clamp(start, end) - start,
+    )
+}
+
+/// The surface the text input of `seat` entered
+fn text_input_surface(seat: &SeatStorage) -> Option<WlSurface> {
+    let data = seat.text_input.as_ref()?.data::<TextInputData>()?;
+    data.inner.lock().unwrap().surface.clone()
+}
+
+fn send_ime_surrounding_text(seat: &SeatStorage) {
+    let (Some(text_input), Some((text, cursor, anchor))) =
+        (&seat.text_input, &seat.ime_surrounding_text)
+    else {
+        return;
+    };
+    text_input.set_surrounding_text(text.clone(), *cursor as i32, *anchor as i32);
+    text_input.set_text_change_cause(if seat.ime_text_changed {
+        zwp_text_input_v3::ChangeCause::InputMethod
+    } else {
+        zwp_text_input_v3::ChangeCause::Other
+    });
+}
+
 pub trait ZwpTextInputV3Ext {
     fn set_content_type_by_purpose(&self, purpose: ImePurpose);
 }
@@ -1593,14 +2089,19 @@ impl<T> WindowState<T> {
         self.with_connection = connection_or;
         self
     }
+
+    /// act as the input method of the seat, see [`input_method`](crate::input_method) for
+    /// several seats. Off by default, as there can only be one input method per seat
+    pub fn with_input_method(mut self, input_method: bool) -> Self {
+        self.input_method.requested = input_method;
+        self
+    }
 }
 
 impl<T> Default for WindowState<T> {
     fn default() -> Self {
         Self {
             outputs: Vec::new(),
-            keyboard_focus: None,
-            active_surfaces: HashMap::new(),
             units: Vec::new(),
             message: Vec::new(),
 
@@ -1620,12 +2121,17 @@ impl<T> Default for WindowState<T> {
             globals: None,
             fractional_scale_manager: None,
             virtual_keyboard: None,
+            virtual_keyboard_manager: None,
+            virtual_keyboard_state: Default::default(),
+            virtual_pointer: Default::default(),
 
             output_state: None,
             registry_state: None,
 
             seat_state: None,
             seats: HashMap::new(),
+            last_seat: None,
+            last_input_serial: None,
             seat_back: None,
 
             default_namespace: "osd".to_owned(),
@@ -1641,27 +2147,38 @@ impl<T> Default for WindowState<T> {
             repeat_delay: None,
             to_remove_tokens: Vec::new(),
             to_be_released_key: None,
+            pending_reads: Vec::new(),
+            pending_writes: Vec::new(),
             closed_ids: Vec::new(),
 
             last_wloutput: None,
             last_unit_index: 0,
 
             return_data: Vec::new(),
-            finger_locations: HashMap::new(),
-            enter_serial: None,
-            button_serial: None,
 
             start_mode: StartMode::Active,
             init_finished: false,
             events_transparent: false,
 
             text_input_manager: None,
-            text_input: None,
-            text_inputs: Vec::new(),
             ime_purpose: ImePurpose::Normal,
             ime_allowed: false,
 
             xdg_decoration_manager: None,
+            xdg_activation: None,
+            idle_inhibit_manager: None,
+            pointer_gestures: None,
+            relative_pointer_manager: None,
+            pointer_constraints: None,
+            tablet_manager: None,
+            tablet: Default::default(),
+            data_device_manager: None,
+            primary_selection_manager: None,
+            selection: Default::default(),
+            dnd: Default::default(),
+            input_method_manager: None,
+            input_method: Default::default(),
+            queue_handle: None,
         }
     }
 }
@@ -1754,12 +2271,18 @@ impl<T> WindowState<T> {
         self.get_output_info_of(&output)
     }
 
-    /// get the current keyboard focus window id
+    /// get the current keyboard focus window id, of the seat of the latest input when several
+    /// seats focus windows
     pub fn keyboard_focus_id(&self) -> Option<id::Id> {
-        self.units
-            .iter()
-            .find(|unit| Some(&unit.window.wl_surface) == self.keyboard_focus.as_ref())
-            .map(|unit| unit.id())
+        let focus = self
+            .current_seat()
+            .and_then(|seat| seat.keyboard_focus.as_ref())
+            .or_else(|| {
+                self.seats
+                    .values()
+                    .find_map(|seat| seat.keyboard_focus.as_ref())
+            })?;
+        self.get_id_from_surface(focus)
     }
 
     /// parent popup/menu when no parent from caller
@@ -1767,20 +2290,27 @@ impl<T> WindowState<T> {
         self.keyboard_focus_id()
             .or_else(|| self.pointer_surface_id())
             .or_else(|| {
-                self.active_surfaces
+                self.seats
                     .values()
+                    .flat_map(|seat| seat.active_surfaces.values())
                     .filter_map(|(_, id)| *id)
                     .find(|id| self.get_unit_with_id(*id).is_some())
             })
             .or_else(|| self.units.last().map(|unit| unit.id()))
     }
 
-    /// window id under the pointer
+    /// window id under the pointer, of the seat of the latest input when several pointers are
+    /// over windows
     pub fn pointer_surface_id(&self) -> Option<id::Id> {
-        self.active_surfaces
-            .get(&None)
-            .and_then(|(_, id)| *id)
-            .filter(|id| self.get_unit_with_id(*id).is_some())
+        let pointer_surface = |seat: &SeatStorage| {
+            seat.active_surfaces
+                .get(&None)
+                .and_then(|(_, id)| *id)
+                .filter(|id| self.get_unit_with_id(*id).is_some())
+        };
+        self.current_seat()
+            .and_then(pointer_surface)
+            .or_else(|| self.seats.values().find_map(pointer_surface))
     }
 
     fn get_id_from_surface(&self, surface: &WlSurface) -> Option<id::Id> {
@@ -1791,10 +2321,11 @@ impl<T> WindowState<T> {
     }
 
     pub fn is_mouse_surface(&self, surface_id: id::Id) -> bool {
-        self.active_surfaces
-            .get(&None)
-            .filter(|(_, id)| *id == Some(surface_id))
-            .is_some()
+        self.seats.values().any(|seat| {
+            seat.active_surfaces
+                .get(&None)
+                .is_some_and(|(_, id)| *id == Some(surface_id))
+        })
     }
 
     /// update output window is on, for `OutputOption::LastOutput`
@@ -1911,11 +2442,8 @@ impl<T: 'static> OutputHandler for WindowState<T> {
                 !unit.window.wl_surface.is_alive() || unit.wl_outputs.as_slice() == [output.clone()]
             })
             .collect();
-        if removed_states
-            .iter()
-            .any(|unit| Some(&unit.window.wl_surface) == self.keyboard_focus.as_ref())
-        {
-            self.keyboard_focus = None;
+        for unit in &removed_states {
+            self.forget_keyboard_focus(&unit.window.wl_surface);
         }
         for unit in &mut self.units {
             let previous = unit.wl_outputs.first().cloned();
@@ -1992,6 +2520,51 @@ impl<T> Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ()> for WindowState<
     }
 }
 
+/// Decode the array of `xdg_toplevel::State`s a configure carries
+fn toplevel_state_from(states: &[u8]) -> ToplevelState {
+    let mut toplevel_state = ToplevelState::default();
+    for raw in states.chunks_exact(4) {
+        let raw = u32::from_ne_bytes(raw.try_into().expect("chunks are 4 bytes"));
+        let Ok(value) = xdg_toplevel::State::try_from(raw) else {
+            continue;
+        };
+        let flag = match value {
+            xdg_toplevel::State::Maximized => &mut toplevel_state.maximized,
+            xdg_toplevel::State::Fullscreen => &mut toplevel_state.fullscreen,
+            xdg_toplevel::State::Resizing => &mut toplevel_state.resizing,
+            xdg_toplevel::State::Activated => &mut toplevel_state.activated,
+            xdg_toplevel::State::TiledLeft => &mut toplevel_state.tiled_left,
+            xdg_toplevel::State::TiledRight => &mut toplevel_state.tiled_right,
+            xdg_toplevel::State::TiledTop => &mut toplevel_state.tiled_top,
+            xdg_toplevel::State::TiledBottom => &mut toplevel_state.tiled_bottom,
+            xdg_toplevel::State::Suspended => &mut toplevel_state.suspended,
+            _ => continue,
+        };
+        *flag = true;
+    }
+    toplevel_state
+}
+
+/// Decode the array of `xdg_toplevel::WmCapabilities` the compositor sends
+fn wm_capabilities_from(capabilities: &[u8]) -> WmCapabilities {
+    let mut wm_capabilities = WmCapabilities::default();
+    for raw in capabilities.chunks_exact(4) {
+        let raw = u32::from_ne_bytes(raw.try_into().expect("chunks are 4 bytes"));
+        let Ok(value) = xdg_toplevel::WmCapabilities::try_from(raw) else {
+            continue;
+        };
+        let flag = match value {
+            xdg_toplevel::WmCapabilities::WindowMenu => &mut wm_capabilities.window_menu,
+            xdg_toplevel::WmCapabilities::Maximize => &mut wm_capabilities.maximize,
+            xdg_toplevel::WmCapabilities::Fullscreen => &mut wm_capabilities.fullscreen,
+            xdg_toplevel::WmCapabilities::Minimize => &mut wm_capabilities.minimize,
+            _ => continue,
+        };
+        *flag = true;
+    }
+    wm_capabilities
+}
+
 impl<T> Dispatch<xdg_toplevel::XdgToplevel, ()> for WindowState<T> {
     fn event(
         state: &mut Self,
@@ -2003,16 +2576,40 @@ impl<T> Dispatch<xdg_toplevel::XdgToplevel, ()> for WindowState<T> {
     ) {
         let unit_index = state.units.iter().position(|unit| unit.shell == *surface);
         match event {
-            xdg_toplevel::Event::Configure { width, height, .. } => {
+            xdg_toplevel::Event::Configure {
+                width,
+                height,
+                states,
+            } => {
                 let Some(unit_index) = unit_index else {
                     return;
                 };
+                let unit = &mut state.units[unit_index];
                 if width != 0 && height != 0 {
-                    state.units[unit_index].size = (width as u32, height as u32);
+                    unit.size = (width as u32, height as u32);
+                }
+                let toplevel_state = toplevel_state_from(&states);
+                if unit.toplevel_state != toplevel_state {
+                    unit.toplevel_state = toplevel_state;
+                    state.message.push((
+                        Some(unit.id),
+                        DispatchMessageInner::ToplevelStateChanged(toplevel_state),
+                    ));
                 }
 
                 state.units[unit_index].request_refresh(RefreshRequest::NextFrame);
             }
+            xdg_toplevel::Event::WmCapabilities { capabilities } => {
+                let Some(unit_index) = unit_index else {
+                    return;
+                };
+                let capabilities = wm_capabilities_from(&capabilities);
+                state.units[unit_index].wm_capabilities = Some(capabilities);
+                state.message.push((
+                    Some(state.units[unit_index].id),
+                    DispatchMessageInner::WmCapabilities(capabilities),
+                ));
+            }
             xdg_toplevel::Event::Close => {
                 let Some(unit_index) = unit_index else {
                     return;
@@ -2194,11 +2791,21 @@ fn leave_output<O: Clone + PartialEq>(
     }
 }
 
-#[derive(Default)]
 pub struct TextInputData {
+    /// the seat of the text input
+    seat: ObjectId,
     inner: std::sync::Mutex<TextInputDataInner>,
 }
 
+impl TextInputData {
+    pub(crate) fn new(seat: ObjectId) -> Self {
+        Self {
+            seat,
+            inner: Default::default(),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct TextInputDataInner {
     /// The `WlSurface` we're performing input to.
@@ -2209,6 +2816,9 @@ pub struct TextInputDataInner {
 
     /// The preedit to submit on `done`.
     pending_preedit: Option<Preedit>,
+
+    /// The bytes before and after the cursor to delete on `done`.
+    pending_delete: Option<(usize, usize)>,
 }
 /// The state of the preedit.
 struct Preedit {
@@ -2227,6 +2837,9 @@ impl<T> Dispatch<zwp_text_input_v3::ZwpTextInputV3, TextInputData> for WindowSta
         _qhandle: &QueueHandle<Self>,
     ) {
         use zwp_text_input_v3::Event;
+        let Some(seat_id) = state.seat_id(&data.seat) else {
+            return;
+        };
         let mut text_input_data = data.inner.lock().unwrap();
 
         match event {
@@ -2240,9 +2853,16 @@ impl<T> Dispatch<zwp_text_input_v3::ZwpTextInputV3, TextInputData> for WindowSta
                     text_input.enable();
                     text_input.set_content_type_by_purpose(state.ime_purpose());
                     text_input.commit();
-                    state
-                        .message
-                        .push((Some(id), DispatchMessageInner::Ime(events::Ime::Enabled)));
+                    state.push_seat_message(
+                        &data.seat,
+                        (
+                            Some(id),
+                            DispatchMessageInner::Ime {
+                                ime: events::Ime::Enabled,
+                                seat: seat_id,
+                            },
+                        ),
+                    );
                 }
                 state.text_input_entered(text_input);
             }
@@ -2255,15 +2875,28 @@ impl<T> Dispatch<zwp_text_input_v3::ZwpTextInputV3, TextInputData> for WindowSta
                     return;
                 };
                 state.text_input_left(text_input);
-                state
-                    .message
-                    .push((Some(id), DispatchMessageInner::Ime(events::Ime::Disabled)));
+                state.push_seat_message(
+                    &data.seat,
+                    (
+                        Some(id),
+                        DispatchMessageInner::Ime {
+                            ime: events::Ime::Disabled,
+                            seat: seat_id,
+                        },
+                    ),
+                );
             }
             Event::CommitString { text } => {
                 text_input_data.pending_preedit = None;
                 text_input_data.pending_commit = text;
             }
-            Event::DeleteSurroundingText { .. } => {}
+            Event::DeleteSurroundingText {
+                before_length,
+                after_length,
+            } => {
+                text_input_data.pending_delete =
+                    Some((before_length as usize, after_length as usize));
+            }
             Event::Done { .. } => {
                 let Some(id) = text_input_data
                     .surface
@@ -2276,17 +2909,50 @@ impl<T> Dispatch<zwp_text_input_v3::ZwpTextInputV3, TextInputData> for WindowSta
                 if text_input_data.pending_commit.is_some()
                     || text_input_data.pending_preedit.is_none()
                 {
-                    state.message.push((
-                        Some(id),
-                        DispatchMessageInner::Ime(Ime::Preedit(String::new(), None)),
-                    ));
+                    state.push_seat_message(
+                        &data.seat,
+                        (
+                            Some(id),
+                            DispatchMessageInner::Ime {
+                                ime: Ime::Preedit(String::new(), None),
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                }
+
+                // Delete before committing, the offsets are relative to the old text.
+                if let Some((before, after)) = text_input_data.pending_delete.take() {
+                    if let Some(seat) = state.seats.get_mut(&data.seat) {
+                        seat.ime_text_changed = true;
+                    }
+                    state.push_seat_message(
+                        &data.seat,
+                        (
+                            Some(id),
+                            DispatchMessageInner::Ime {
+                                ime: Ime::DeleteSurrounding { before, after },
+                                seat: seat_id,
+                            },
+                        ),
+                    );
                 }
 
                 // Send `Commit`.
                 if let Some(text) = text_input_data.pending_commit.take() {
-                    state
-                        .message
-                        .push((Some(id), DispatchMessageInner::Ime(Ime::Commit(text))));
+                    if let Some(seat) = state.seats.get_mut(&data.seat) {
+                        seat.ime_text_changed = true;
+                    }
+                    state.push_seat_message(
+                        &data.seat,
+                        (
+                            Some(id),
+                            DispatchMessageInner::Ime {
+                                ime: Ime::Commit(text),
+                                seat: seat_id,
+                            },
+                        ),
+                    );
                 }
 
                 // Send preedit.
@@ -2295,10 +2961,16 @@ impl<T> Dispatch<zwp_text_input_v3::ZwpTextInputV3, TextInputData> for WindowSta
                         .cursor_begin
                         .map(|b| (b, preedit.cursor_end.unwrap_or(b)));
 
-                    state.message.push((
-                        Some(id),
-                        DispatchMessageInner::Ime(Ime::Preedit(preedit.text, cursor_range)),
-                    ));
+                    state.push_seat_message(
+                        &data.seat,
+                        (
+                            Some(id),
+                            DispatchMessageInner::Ime {
+                                ime: Ime::Preedit(preedit.text, cursor_range),
+                                seat: seat_id,
+                            },
+                        ),
+                    );
                 }
             }
             Event::PreeditString {
@@ -2343,6 +3015,91 @@ impl<T> Dispatch<WlCallback, (id::Id, PresentAvailableState)> for WindowState<T>
     }
 }
 
+impl<T> WindowState<T> {
+    /// Forget the constraint of the surface `id` if `proxy` ended it for good
+    fn pointer_constraint_ended(&mut self, id: id::Id, proxy: &ObjectId) {
+        let Some(unit) = self.units.iter_mut().find(|unit| unit.id == id) else {
+            return;
+        };
+        if let Some(constraint) = unit
+            .pointer_constraint
+            .take_if(|constraint| constraint.ends_with(proxy))
+        {
+            constraint.destroy();
+        }
+    }
+}
+
+impl<T> Dispatch<ZwpLockedPointerV1, id::Id> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwpLockedPointerV1,
+        event: <ZwpLockedPointerV1 as Proxy>::Event,
+        data: &id::Id,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_locked_pointer_v1::Event::Locked => {
+                state
+                    .message
+                    .push((Some(*data), DispatchMessageInner::PointerLocked));
+            }
+            zwp_locked_pointer_v1::Event::Unlocked => {
+                state.pointer_constraint_ended(*data, &proxy.id());
+                state
+                    .message
+                    .push((Some(*data), DispatchMessageInner::PointerUnlocked));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T> Dispatch<ZwpConfinedPointerV1, id::Id> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwpConfinedPointerV1,
+        event: <ZwpConfinedPointerV1 as Proxy>::Event,
+        data: &id::Id,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_confined_pointer_v1::Event::Confined => {
+                state
+                    .message
+                    .push((Some(*data), DispatchMessageInner::PointerConfined));
+            }
+            zwp_confined_pointer_v1::Event::Unconfined => {
+                state.pointer_constraint_ended(*data, &proxy.id());
+                state
+                    .message
+                    .push((Some(*data), DispatchMessageInner::PointerUnconfined));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T> Dispatch<XdgActivationTokenV1, id::Id> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        proxy: &XdgActivationTokenV1,
+        event: <XdgActivationTokenV1 as Proxy>::Event,
+        data: &id::Id,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let xdg_activation_token_v1::Event::Done { token } = event {
+            state
+                .message
+                .push((Some(*data), DispatchMessageInner::ActivationToken(token)));
+            proxy.destroy();
+        }
+    }
+}
+
 impl<T> Dispatch<ExtSessionLockV1, ()> for WindowState<T> {
     fn event(
         state: &mut Self,
@@ -2415,7 +3172,40 @@ delegate_noop!(@<T> WindowState<T>: ignore ZwpInputPanelSurfaceV1);
 delegate_noop!(@<T> WindowState<T>: ignore ZwpInputPanelV1);
 
 delegate_noop!(@<T> WindowState<T>: ignore ZxdgDecorationManagerV1);
-delegate_noop!(@<T> WindowState<T>: ignore ZxdgToplevelDecorationV1);
+impl<T> Dispatch<ZxdgToplevelDecorationV1, id::Id> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZxdgToplevelDecorationV1,
+        event: <ZxdgToplevelDecorationV1 as Proxy>::Event,
+        id: &id::Id,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let zxdg_toplevel_decoration_v1::Event::Configure { mode } = event else {
+            return;
+        };
+        let client_side = mode == WEnum::Value(zxdg_toplevel_decoration_v1::Mode::ClientSide);
+        let Some(unit) = state.get_mut_unit_with_id(*id) else {
+            return;
+        };
+        if unit.client_side_decorated == client_side {
+            return;
+        }
+        unit.client_side_decorated = client_side;
+        state.message.push((
+            Some(*id),
+            DispatchMessageInner::DecorationModeChanged { client_side },
+        ));
+    }
+}
+
+delegate_noop!(@<T> WindowState<T>: ignore XdgActivationV1);
+
+delegate_noop!(@<T> WindowState<T>: ignore ZwpIdleInhibitManagerV1);
+delegate_noop!(@<T> WindowState<T>: ignore ZwpIdleInhibitorV1);
+delegate_noop!(@<T> WindowState<T>: ignore ZwpPointerGesturesV1);
+delegate_noop!(@<T> WindowState<T>: ignore ZwpRelativePointerManagerV1);
+delegate_noop!(@<T> WindowState<T>: ignore ZwpPointerConstraintsV1);
 
 impl<T: 'static> WindowState<T> {
     /// build a new WindowState
@@ -2429,12 +3219,13 @@ impl<T: 'static> WindowState<T> {
         self.display = Some(connection.display());
 
         let qh = event_queue.handle();
+        self.queue_handle = Some(qh.clone());
 
         self.registry_state = Some(RegistryState::new(&globals));
         self.output_state = Some(OutputState::new(&globals, &qh));
         let seat_state = SeatState::new(&globals, &qh);
         for seat in seat_state.seats() {
-            self.seats.insert(seat.id(), SeatStorage::new());
+            self.add_seat(seat);
         }
         self.seat_state = Some(seat_state);
         let wmcompositer = globals.bind::<WlCompositor, _, _>(&qh, 1..=5, ())?;
@@ -2460,6 +3251,10 @@ impl<T: 'static> WindowState<T> {
             .ok();
 
         self.xdg_decoration_manager = decoration_manager;
+        self.xdg_activation = globals.bind::<XdgActivationV1, _, _>(&qh, 1..=1, ()).ok();
+        self.idle_inhibit_manager = globals
+            .bind::<ZwpIdleInhibitManagerV1, _, _>(&qh, 1..=1, ())
+            .ok();
 
         let fractional_scale_manager = globals
             .bind::<WpFractionalScaleManagerV1, _, _>(&qh, 1..=1, ())
@@ -2471,6 +3266,46 @@ impl<T: 'static> WindowState<T> {
             .bind::<ExtSessionLockManagerV1, _, _>(&qh, 1..=1, ())
             .ok();
         self.text_input_manager = text_input_manager;
+        // before the dispatch below, it announces the pointers the gestures are bound to
+        self.pointer_gestures = globals
+            .bind::<ZwpPointerGesturesV1, _, _>(&qh, 1..=3, ())
+            .ok();
+        self.relative_pointer_manager = globals
+            .bind::<ZwpRelativePointerManagerV1, _, _>(&qh, 1..=1, ())
+            .ok();
+        self.pointer_constraints = globals
+            .bind::<ZwpPointerConstraintsV1, _, _>(&qh, 1..=1, ())
+            .ok();
+        self.data_device_manager = globals
+            .bind::<WlDataDeviceManager, _, _>(&qh, 1..=3, ())
+            .ok();
+        self.primary_selection_manager = globals
+            .bind::<ZwpPrimarySelectionDeviceManagerV1, _, _>(&qh, 1..=1, ())
+            .ok();
+        self.virtual_keyboard_manager = globals
+            .bind::<ZwpVirtualKeyboardManagerV1, _, _>(&qh, 1..=1, ())
+            .ok();
+        self.virtual_pointer.manager = globals
+            .bind::<ZwlrVirtualPointerManagerV1, _, _>(&qh, 1..=2, ())
+            .ok();
+        self.input_method_manager = globals
+            .bind::<ZwpInputMethodManagerV2, _, _>(&qh, 1..=1, ())
+            .ok();
+        // with several seats it is up to the application which one to serve
+        if self.input_method.requested
+            && let (Some(manager), [seat]) = (
+                &self.input_method_manager,
+                &self.seats.values().collect::<Vec<_>>()[..],
+            )
+        {
+            self.input_method.input_method = Some(manager.get_input_method(&seat.seat, &qh, ()));
+        }
+        // version 2 adds dials, which are not handled
+        self.tablet_manager = globals
+            .bind::<ZwpTabletManagerV2, _, _>(&qh, 1..=1, ())
+            .ok();
+        // the seats known by now were announced before the manager was bound
+        self.bind_tablet_seats(&qh);
         event_queue.blocking_dispatch(&mut self)?; // then make a dispatch
 
         // OutputState bound its own xdg_outputs before the dispatch above, so output info is
@@ -2769,10 +3604,12 @@ impl<T: 'static> WindowState<T> {
         }
 
         fn remove_lock_units<T>(window_state: &mut WindowState<T>) {
-            for removed in window_state.units.extract_if(.., |unit| unit.is_lock()) {
-                if window_state.keyboard_focus.as_ref() == Some(&removed.window.wl_surface) {
-                    window_state.keyboard_focus = None;
-                }
+            let removed: Vec<_> = window_state
+                .units
+                .extract_if(.., |unit| unit.is_lock())
+                .collect();
+            for removed in removed {
+                window_state.forget_keyboard_focus(&removed.window.wl_surface);
                 window_state.closed_ids.push(removed.id);
             }
         }
@@ -2797,8 +3634,7 @@ impl<T: 'static> WindowState<T> {
                                     event_handler: &mut F,
                                     lock_manager: Option<&ExtSessionLockManagerV1>,
                                     lock: &mut LockLifecycle| {
-            let mut messages = Vec::new();
-            std::mem::swap(&mut messages, &mut window_state.message);
+            let messages = std::mem::take(&mut window_state.message);
             for msg in messages.iter() {
                 match msg {
                     (_, DispatchMessageInner::NewDisplay(output_display)) => {
@@ -2996,6 +3832,7 @@ impl<T: 'static> WindowState<T> {
                     }
                 }
             }
+            window_state.finish_dnd_drop();
 
             window_state.handle_event(&mut *event_handler, ExWlShellEvent::NormalDispatch, None);
             loop {
@@ -3107,7 +3944,12 @@ impl<T: 'static> WindowState<T> {
                             LockLifecycle::Unlocked => {}
                         },
                         ReturnData::RequestSetCursorShape((shape_name, pointer)) => {
-                            let Some(serial) = window_state.enter_serial else {
+                            let Some(serial) = window_state
+                                .seats
+                                .values()
+                                .find(|seat| seat.pointer.as_ref() == Some(&pointer))
+                                .and_then(|seat| seat.enter_serial)
+                            else {
                                 continue;
                             };
                             set_cursor_shape(&cursor_update_context, shape_name, pointer, serial);
@@ -3259,7 +4101,9 @@ impl<T: 'static> WindowState<T> {
                             };
                             positioner.destroy();
 
-                            match (window_state.seat_back.as_ref(), grab_serial) {
+                            let grab_seat = grab_serial
+                                .and_then(|serial| window_state.seat_with_serial(serial));
+                            match (grab_seat, grab_serial) {
                                 (Some(seat), Some(serial)) => popup.grab(seat, serial),
                                 (None, Some(_)) => log::warn!(
                                     target: "exwlshellev",
@@ -3348,6 +4192,9 @@ impl<T: 'static> WindowState<T> {
                                 title,
                                 size,
                                 client_side_decorations,
+                                app_id,
+                                min_size,
+                                max_size,
                             },
                             id,
                             info,
@@ -3357,22 +4204,40 @@ impl<T: 'static> WindowState<T> {
                             let toplevel = wl_xdg_surface.get_toplevel(&qh, ());
 
                             toplevel.set_title(title.unwrap_or("".to_owned()));
+                            if let Some(app_id) = app_id {
+                                toplevel.set_app_id(app_id);
+                            }
+                            if let Some((width, height)) = min_size.map(PixelSize::to_set_i32) {
+                                toplevel.set_min_size(width, height);
+                            }
+                            if let Some((width, height)) = max_size.map(PixelSize::to_set_i32) {
+                                toplevel.set_max_size(width, height);
+                            }
 
-                            let decoration = if let Some(decoration_manager) =
-                                &zxdg_decoration_manager
-                            {
-                                let decoration =
-                                    decoration_manager.get_toplevel_decoration(&toplevel, &qh, ());
-                                use zxdg_toplevel_decoration_v1::Mode;
-                                decoration.set_mode(if client_side_decorations {
-                                    Mode::ClientSide
+                            let decoration =
+                                if let Some(decoration_manager) = &zxdg_decoration_manager {
+                                    let decoration = decoration_manager
+                                        .get_toplevel_decoration(&toplevel, &qh, id);
+                                    use zxdg_toplevel_decoration_v1::Mode;
+                                    decoration.set_mode(if client_side_decorations {
+                                        Mode::ClientSide
+                                    } else {
+                                        Mode::ServerSide
+                                    });
+                                    Some(decoration)
                                 } else {
-                                    Mode::ServerSide
-                                });
-                                Some(decoration)
-                            } else {
-                                None
-                            };
+                                    None
+                                };
+                            // without the protocol nothing but us draws a frame
+                            let client_side_decorated = decoration.is_none();
+                            if client_side_decorated {
+                                window_state.message.push((
+                                    Some(id),
+                                    DispatchMessageInner::DecorationModeChanged {
+                                        client_side: true,
+                                    },
+                                ));
+                            }
                             let mut fractional_scale = None;
                             if let Some(ref fractional_scale_manager) = fractional_scale_manager {
                                 fractional_scale =
@@ -3399,6 +4264,7 @@ impl<T: 'static> WindowState<T> {
                                 .size(size.unwrap_or(PixelSize::px(300, 300)).to_set())
                                 .viewport(viewport)
                                 .fractional_scale(fractional_scale)
+                                .client_side_decorated(client_side_decorated)
                                 .binding(info)
                                 .build(),
                             );
@@ -3465,6 +4331,44 @@ impl<T: 'static> WindowState<T> {
                                 .build(),
                             );
                         }
+                        ReturnData::NewInputMethodPopup((
+                            NewInputMethodPopupSettings { size },
+                            id,
+                            info,
+                        )) => {
+                            let Some(input_method) = window_state.input_method.input_method.clone()
+                            else {
+                                log::warn!("no input method, skip creating input method popup");
+                                continue;
+                            };
+
+                            let wl_surface = wmcompositer.create_surface(&qh, ());
+                            let popup_surface =
+                                input_method.get_input_popup_surface(&wl_surface, &qh, id);
+                            wl_surface.commit();
+
+                            let fractional_scale = fractional_scale_manager
+                                .as_ref()
+                                .map(|manager| manager.get_fractional_scale(&wl_surface, &qh, ()));
+                            let viewport = viewporter
+                                .as_ref()
+                                .map(|viewport| viewport.get_viewport(&wl_surface, &qh, ()));
+                            window_state.push_window(
+                                WindowStateUnitBuilder::new(
+                                    id,
+                                    qh.clone(),
+                                    connection.display(),
+                                    wl_surface,
+                                    wmcompositer.clone(),
+                                    Shell::InputMethodPopup(popup_surface),
+                                )
+                                .size(size.to_set())
+                                .viewport(viewport)
+                                .fractional_scale(fractional_scale)
+                                .binding(info)
+                                .build(),
+                            );
+                        }
                         _ => {}
                     }
                 }
@@ -3622,6 +4526,14 @@ impl<T: 'static> WindowState<T> {
                 looph.remove(*token);
             }
             window_state.to_remove_tokens.clear();
+            for read in window_state.pending_reads.drain(..) {
+                clipboard::insert_read(looph, read, |wrapper: &mut EventWrapper<Self, F>| {
+                    &mut wrapper.raw
+                });
+            }
+            for write in window_state.pending_writes.drain(..) {
+                clipboard::insert_write(looph, write);
+            }
             if let Some(VirtualKeyRelease { delay, time, key }) =
                 window_state.to_be_released_key.take()
             {
@@ -3642,6 +4554,7 @@ impl<T: 'static> WindowState<T> {
                 surface_id,
                 pressed_state,
                 object_id,
+                seat,
             }) = window_state.repeat_delay.take()
             {
                 let timer = Timer::from_duration(delay);
@@ -3652,15 +4565,14 @@ impl<T: 'static> WindowState<T> {
                         .insert_source(timer, move |_, _, r_window_state| {
                             let state = &mut r_window_state.raw;
                             let event_handler = &mut r_window_state.fun;
-                            let keyboard_state = match state
-                                .seats
-                                .values_mut()
-                                .find(|seat| {
-                                    seat.keyboard_state
-                                        .as_ref()
-                                        .is_some_and(|state| state.keyboard.id() == object_id)
-                                })
-                                .map(|storage| storage.keyboard_state.as_mut().unwrap())
+                            let Some(seat_storage) = state.seats.get_mut(&seat) else {
+                                return TimeoutAction::Drop;
+                            };
+                            let seat_id = seat_storage.id;
+                            let keyboard_state = match seat_storage
+                                .keyboard_state
+                                .as_mut()
+                                .filter(|state| state.keyboard.id() == object_id)
                             {
                                 Some(keyboard_state) => keyboard_state,
                                 None => return TimeoutAction::Drop,
@@ -3673,16 +4585,15 @@ impl<T: 'static> WindowState<T> {
                             if repeat_keycode != key {
                                 return TimeoutAction::Drop;
                             }
-                            if let Some(mut key_context) = keyboard_state.xkb_context.key_context()
-                            {
-                                let event = key_context.process_key_event(
-                                    repeat_keycode,
-                                    pressed_state,
-                                    false,
-                                );
+                            if let Some((event, _)) = keyboard_state.process_key_event(
+                                repeat_keycode,
+                                pressed_state,
+                                true,
+                            ) {
                                 let event = DispatchMessageInner::KeyboardInput {
                                     event,
                                     is_synthetic: false,
+                                    seat: seat_id,
                                 };
                                 state.message.push((surface_id, event));
                             }
@@ -3840,3 +4751,32 @@ fn set_cursor_shape<T: 'static>(
         cursor_surface.commit();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surrounding_text_is_cut_around_the_cursor() {
+        let short = "hello";
+        assert_eq!(
+            clamp_surrounding_text(short, 2, 4),
+            (short.to_owned(), 2, 4)
+        );
+
+        // 3 bytes per char, so a cut in the middle of one has to move to its edge
+        let long = "€".repeat(2000);
+        let cursor = 3000;
+        let (text, new_cursor, anchor) = clamp_surrounding_text(&long, cursor, cursor);
+        assert!(text.len() <= MAX_SURROUNDING_TEXT);
+        assert!(text.chars().all(|c| c == '€'));
+        assert_eq!(new_cursor, anchor);
+        assert_eq!(&text[..new_cursor], &long[cursor - new_cursor..cursor]);
+
+        // a selection wider than the limit keeps the cursor, the anchor is pulled in
+        let (text, new_cursor, anchor) = clamp_surrounding_text(&long, 6000, 0);
+        assert!(text.len() <= MAX_SURROUNDING_TEXT);
+        assert_eq!(anchor, 0);
+        assert!(new_cursor > 0);
+    }
+}
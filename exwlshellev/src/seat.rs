@@ -1,6 +1,6 @@
 use super::WindowState;
 use sctk::seat::{Capability as SeatCapability, SeatHandler};
-use waycrate_xkbkeycode::xkb_keyboard;
+use waycrate_xkbkeycode::{compose::ComposeState, xkb_keyboard};
 use wayland_backend::client::ObjectId;
 use wayland_client::{
     Connection, Dispatch, Proxy, QueueHandle, WEnum, delegate_noop,
@@ -8,14 +8,49 @@ use wayland_client::{
         wl_keyboard::{self, KeyState, KeymapFormat, WlKeyboard},
         wl_pointer::{self, WlPointer},
         wl_seat::{self, WlSeat},
+        wl_surface::WlSurface,
         wl_touch::{self, WlTouch},
     },
 };
 
+use crate::id::Id;
 use crate::{AxisScroll, DispatchMessageInner, KeyboardTokenState, RepeatInfo, TextInputData};
+use wayland_client::protocol::wl_data_device::WlDataDevice;
+use wayland_protocols::wp::pointer_gestures::zv1::client::{
+    zwp_pointer_gesture_hold_v1::{self, ZwpPointerGestureHoldV1},
+    zwp_pointer_gesture_pinch_v1::{self, ZwpPointerGesturePinchV1},
+    zwp_pointer_gesture_swipe_v1::{self, ZwpPointerGestureSwipeV1},
+    zwp_pointer_gestures_v1::ZwpPointerGesturesV1,
+};
+use wayland_protocols::wp::primary_selection::zv1::client::zwp_primary_selection_device_v1::ZwpPrimarySelectionDeviceV1;
+use wayland_protocols::wp::relative_pointer::zv1::client::zwp_relative_pointer_v1::{
+    self, ZwpRelativePointerV1,
+};
+use wayland_protocols::wp::tablet::zv2::client::zwp_tablet_seat_v2::ZwpTabletSeatV2;
 use wayland_protocols::wp::text_input::zv3::client::zwp_text_input_v3::ZwpTextInputV3;
 
+use std::collections::HashMap;
+use std::sync::atomic::{self, AtomicU64};
 use std::time::Duration;
+
+/// The id of a seat, input messages carry the one they came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SeatId(u64);
+
+static SEAT_COUNT: AtomicU64 = AtomicU64::new(0);
+
+impl SeatId {
+    fn unique() -> Self {
+        Self(SEAT_COUNT.fetch_add(1, atomic::Ordering::Relaxed))
+    }
+}
+
+impl From<SeatId> for u64 {
+    fn from(seat: SeatId) -> Self {
+        seat.0
+    }
+}
+
 impl<T> WindowState<T> {
     /// get a seat from state
     pub fn get_seat(&self) -> &WlSeat {
@@ -58,6 +93,14 @@ impl<T> WindowState<T> {
             })
             .map(|storage| storage.keyboard_state.as_mut().unwrap())
     }
+    /// names of the layouts in the keymap of the keyboard of `seat`, by index as in
+    /// [`crate::DispatchMessage::KeyboardLayoutChanged`]. Empty if no keymap was received.
+    pub fn keyboard_layouts(&self, seat: SeatId) -> Vec<String> {
+        self.seat_storage(seat)
+            .and_then(|storage| storage.keyboard_state.as_ref())
+            .map(|keyboard_state| keyboard_state.layout_names.clone())
+            .unwrap_or_default()
+    }
 
     pub fn get_pointers(&self) -> Vec<WlPointer> {
         self.seats
@@ -81,18 +124,124 @@ impl<T> WindowState<T> {
     pub fn get_touches_iter(&self) -> impl Iterator<Item = &WlTouch> {
         self.seats.values().flat_map(|seat| &seat.touch)
     }
+
+    /// The seats, in no particular order
+    pub fn seat_ids(&self) -> Vec<SeatId> {
+        self.seats.values().map(|seat| seat.id).collect()
+    }
+
+    pub(crate) fn add_seat(&mut self, seat: WlSeat) {
+        let storage = SeatStorage::new(seat.clone());
+        self.message
+            .push((None, DispatchMessageInner::SeatAdded(storage.id)));
+        self.seats.insert(seat.id(), storage);
+    }
+
+    /// The id input messages of `seat` carry, `None` once it is gone
+    pub(crate) fn seat_id(&self, seat: &ObjectId) -> Option<SeatId> {
+        self.seats.get(seat).map(|storage| storage.id)
+    }
+
+    /// Queue an input message of `seat`, which becomes the current seat
+    pub(crate) fn push_seat_message(
+        &mut self,
+        seat: &ObjectId,
+        message: (Option<Id>, DispatchMessageInner),
+    ) {
+        self.message.push(message);
+        self.last_seat = Some(seat.clone());
+    }
+
+    /// The seat of the latest input, `None` before there was input
+    pub(crate) fn current_seat(&self) -> Option<&SeatStorage> {
+        self.seats.get(self.last_seat.as_ref()?)
+    }
+
+    pub(crate) fn current_seat_mut(&mut self) -> Option<&mut SeatStorage> {
+        self.seats.get_mut(self.last_seat.as_ref()?)
+    }
+
+    pub(crate) fn seat_storage(&self, seat: SeatId) -> Option<&SeatStorage> {
+        self.seats.values().find(|storage| storage.id == seat)
+    }
+
+    /// The seat an enter or press `serial` came from
+    pub(crate) fn seat_with_serial(&self, serial: u32) -> Option<&WlSeat> {
+        self.seats
+            .values()
+            .find(|seat| seat.enter_serial == Some(serial) || seat.input_serial == Some(serial))
+            .map(|seat| &seat.seat)
+    }
+
+    fn seat_keyboard_focus_id(&self, seat: &ObjectId) -> Option<Id> {
+        let focus = self.seats.get(seat)?.keyboard_focus.as_ref()?;
+        self.get_id_from_surface(focus)
+    }
+
+    /// Forget `surface` as the keyboard focus of every seat
+    pub(crate) fn forget_keyboard_focus(&mut self, surface: &WlSurface) {
+        for seat in self.seats.values_mut() {
+            if seat.keyboard_focus.as_ref() == Some(surface) {
+                seat.keyboard_focus = None;
+            }
+        }
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub(crate) struct SeatStorage {
+    pub id: SeatId,
+    pub seat: WlSeat,
+    pub keyboard_focus: Option<WlSurface>,
+    /// surfaces under the pointer (`None`) and the touch points
+    pub active_surfaces: HashMap<Option<i32>, (WlSurface, Option<Id>)>,
+    pub finger_locations: HashMap<i32, (f64, f64)>,
+    pub enter_serial: Option<u32>,
+    pub button_serial: Option<u32>,
+    /// serial of the last key, button or touch press, for setting the selection
+    pub input_serial: Option<u32>,
+    /// the text input entered one of our surfaces
+    pub text_input_entered: bool,
+    /// the last change of the text came from the input method
+    pub ime_text_changed: bool,
+    /// text around the cursor of the focused text field, with the cursor and anchor offsets,
+    /// already cut to what text-input-v3 accepts
+    pub ime_surrounding_text: Option<(String, usize, usize)>,
     pub touch: Option<WlTouch>,
     pub pointer: Option<WlPointer>,
     pub keyboard_state: Option<xkb_keyboard::KeyboardState>,
     pub text_input: Option<ZwpTextInputV3>,
+    pub swipe: Option<ZwpPointerGestureSwipeV1>,
+    pub pinch: Option<ZwpPointerGesturePinchV1>,
+    pub hold: Option<ZwpPointerGestureHoldV1>,
+    pub relative_pointer: Option<ZwpRelativePointerV1>,
+    pub tablet_seat: Option<ZwpTabletSeatV2>,
+    pub data_device: Option<WlDataDevice>,
+    pub primary_selection_device: Option<ZwpPrimarySelectionDeviceV1>,
+    /// the surface the running gesture began on
+    pub gesture_target: Option<Id>,
+    /// axis events waiting for the end of their pointer frame
+    pub axis_frame: Option<AxisFrame>,
 }
 
 impl Drop for SeatStorage {
     fn drop(&mut self) {
+        // gestures have to go before the pointer they belong to
+        self.release_gestures();
+        if let Some(relative_pointer) = self.relative_pointer.take() {
+            relative_pointer.destroy();
+        }
+        if let Some(tablet_seat) = self.tablet_seat.take() {
+            tablet_seat.destroy();
+        }
+        if let Some(data_device) = self.data_device.take()
+            && data_device.version() >= 2
+        {
+            data_device.release();
+        }
+        if let Some(device) = self.primary_selection_device.take() {
+            device.destroy();
+        }
         if let Some(touch) = self.touch.take()
             && touch.version() >= 3
         {
@@ -111,19 +260,80 @@ impl Drop for SeatStorage {
 }
 
 impl SeatStorage {
-    pub(crate) fn new() -> Self {
-        Self::default()
+    pub(crate) fn new(seat: WlSeat) -> Self {
+        Self {
+            id: SeatId::unique(),
+            seat,
+            keyboard_focus: None,
+            active_surfaces: HashMap::new(),
+            finger_locations: HashMap::new(),
+            enter_serial: None,
+            button_serial: None,
+            input_serial: None,
+            text_input_entered: false,
+            ime_text_changed: false,
+            ime_surrounding_text: None,
+            touch: None,
+            pointer: None,
+            keyboard_state: None,
+            text_input: None,
+            swipe: None,
+            pinch: None,
+            hold: None,
+            relative_pointer: None,
+            tablet_seat: None,
+            data_device: None,
+            primary_selection_device: None,
+            gesture_target: None,
+            axis_frame: None,
+        }
+    }
+
+    fn bind_gestures<T: 'static>(
+        &mut self,
+        gestures: &ZwpPointerGesturesV1,
+        pointer: &WlPointer,
+        seat: &WlSeat,
+        qh: &QueueHandle<WindowState<T>>,
+    ) {
+        self.swipe = Some(gestures.get_swipe_gesture(pointer, qh, seat.id()));
+        self.pinch = Some(gestures.get_pinch_gesture(pointer, qh, seat.id()));
+        // hold gestures are new in version 3
+        if gestures.version() >= 3 {
+            self.hold = Some(gestures.get_hold_gesture(pointer, qh, seat.id()));
+        }
+    }
+
+    fn release_gestures(&mut self) {
+        if let Some(swipe) = self.swipe.take() {
+            swipe.destroy();
+        }
+        if let Some(pinch) = self.pinch.take() {
+            pinch.destroy();
+        }
+        if let Some(hold) = self.hold.take() {
+            hold.destroy();
+        }
+        self.gesture_target = None;
     }
 }
 impl<T: 'static> SeatHandler for WindowState<T> {
     fn seat_state(&mut self) -> &mut sctk::seat::SeatState {
         self.seat_state.as_mut().unwrap()
     }
-    fn new_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, seat: wl_seat::WlSeat) {
-        self.seats.insert(seat.id(), SeatStorage::new());
+    fn new_seat(&mut self, _conn: &Connection, qh: &QueueHandle<Self>, seat: wl_seat::WlSeat) {
+        self.add_seat(seat);
+        self.bind_tablet_seats(qh);
     }
     fn remove_seat(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>, seat: wl_seat::WlSeat) {
-        let _ = self.seats.remove(&seat.id());
+        let Some(storage) = self.seats.remove(&seat.id()) else {
+            return;
+        };
+        if self.last_seat.as_ref() == Some(&seat.id()) {
+            self.last_seat = None;
+        }
+        self.message
+            .push((None, DispatchMessageInner::SeatRemoved(storage.id)));
     }
     fn new_capability(
         &mut self,
@@ -143,22 +353,44 @@ impl<T: 'static> SeatHandler for WindowState<T> {
         // maybe we can have virtual-keyboard
         if seat_state.text_input.is_none() {
             let text_input = self.text_input_manager.as_ref().map(|manager| {
-                manager.get_text_input(&seat, queue_handle, TextInputData::default())
+                manager.get_text_input(&seat, queue_handle, TextInputData::new(seat.id()))
             });
             seat_state.text_input = text_input;
         }
+        // selections belong to the seat, not to one of its devices
+        if seat_state.data_device.is_none() {
+            seat_state.data_device = self
+                .data_device_manager
+                .as_ref()
+                .map(|manager| manager.get_data_device(&seat, queue_handle, seat.id()));
+        }
+        if seat_state.primary_selection_device.is_none() {
+            seat_state.primary_selection_device = self
+                .primary_selection_manager
+                .as_ref()
+                .map(|manager| manager.get_device(&seat, queue_handle, seat.id()));
+        }
 
         use xkb_keyboard::KeyboardState;
         match capability {
             SeatCapability::Touch if seat_state.touch.is_none() => {
-                seat_state.touch = Some(seat.get_touch(queue_handle, ()));
+                seat_state.touch = Some(seat.get_touch(queue_handle, seat.id()));
             }
             SeatCapability::Keyboard if seat_state.keyboard_state.is_none() => {
-                seat_state.keyboard_state =
-                    Some(KeyboardState::new(seat.get_keyboard(queue_handle, ())));
+                seat_state.keyboard_state = Some(KeyboardState::new(
+                    seat.get_keyboard(queue_handle, seat.id()),
+                ));
             }
             SeatCapability::Pointer if seat_state.pointer.is_none() => {
-                seat_state.pointer = Some(seat.get_pointer(queue_handle, ()));
+                let pointer = seat.get_pointer(queue_handle, seat.id());
+                if let Some(gestures) = &self.pointer_gestures {
+                    seat_state.bind_gestures(gestures, &pointer, &seat, queue_handle);
+                }
+                if let Some(manager) = &self.relative_pointer_manager {
+                    seat_state.relative_pointer =
+                        Some(manager.get_relative_pointer(&pointer, queue_handle, seat.id()));
+                }
+                seat_state.pointer = Some(pointer);
             }
             _ => (),
         }
@@ -187,6 +419,10 @@ impl<T: 'static> SeatHandler for WindowState<T> {
                 }
             }
             SeatCapability::Pointer => {
+                seat_state.release_gestures();
+                if let Some(relative_pointer) = seat_state.relative_pointer.take() {
+                    relative_pointer.destroy();
+                }
                 if let Some(pointer) = seat_state.pointer.take()
                     && pointer.version() >= 3
                 {
@@ -201,15 +437,18 @@ impl<T: 'static> SeatHandler for WindowState<T> {
     }
 }
 
-impl<T> Dispatch<wl_keyboard::WlKeyboard, ()> for WindowState<T> {
+impl<T> Dispatch<wl_keyboard::WlKeyboard, ObjectId> for WindowState<T> {
     fn event(
         state: &mut Self,
         wl_keyboard: &wl_keyboard::WlKeyboard,
         event: <wl_keyboard::WlKeyboard as Proxy>::Event,
-        _data: &(),
+        data: &ObjectId,
         _conn: &Connection,
         _qhandle: &QueueHandle<Self>,
     ) {
+        let Some(seat_id) = state.seat_id(data) else {
+            return;
+        };
         use crate::keyboard::*;
         use xkb_keyboard::ElementState;
 
@@ -229,61 +468,137 @@ impl<T> Dispatch<wl_keyboard::WlKeyboard, ()> for WindowState<T> {
                     else {
                         return;
                     };
-                    let context = &mut keyboard_state.xkb_context;
-                    context.set_keymap_from_fd(fd, size as usize)
+                    keyboard_state.set_keymap_from_fd(fd, size as usize)
                 }
                 WEnum::Value(KeymapFormat::NoKeymap) => {
                     log::warn!("non-xkb compatible keymap")
                 }
                 _ => unreachable!(),
             },
-            wl_keyboard::Event::Enter { surface, .. } => {
+            wl_keyboard::Event::Enter {
+                serial,
+                surface,
+                keys,
+            } => {
                 state.update_active_output(&surface);
-                if state.keyboard_focus.as_ref() == Some(&surface) {
+                let surface_id = state.get_id_from_surface(&surface);
+                let Some(seat) = state.seats.get_mut(data) else {
+                    return;
+                };
+                seat.input_serial = Some(serial);
+                state.last_input_serial = Some(serial);
+                if seat.keyboard_focus.as_ref() == Some(&surface) {
                     log::warn!("wl_keyboard::enter ignoring duplicate call");
                 } else {
-                    let surface_id = state.get_id_from_surface(&surface);
-                    state.keyboard_focus = Some(surface);
+                    seat.keyboard_focus = Some(surface);
                     if let Some(id) = surface_id {
-                        state
-                            .message
-                            .push((Some(id), DispatchMessageInner::Focused(id)));
+                        state.push_seat_message(
+                            data,
+                            (
+                                Some(id),
+                                DispatchMessageInner::Focused { id, seat: seat_id },
+                            ),
+                        );
                     }
                 }
                 let Some(keyboard_state) = state.get_keyboard_state_mut(wl_keyboard) else {
                     return;
                 };
                 keyboard_state.current_repeat = None;
-                if let Some(token) = keyboard_state.repeat_token.take() {
+                let token = keyboard_state.repeat_token.take();
+                // keys held while the focus came in, like the modifier of a shortcut
+                let pressed = keyboard_state.enter_keys(&keys);
+                if let Some(token) = token {
                     state.to_remove_tokens.push(token);
                 }
+                if surface_id.is_some() {
+                    for event in pressed {
+                        let event = DispatchMessageInner::KeyboardInput {
+                            event,
+                            is_synthetic: true,
+                            seat: seat_id,
+                        };
+                        state.push_seat_message(data, (surface_id, event));
+                    }
+                }
             }
             wl_keyboard::Event::Leave { surface, .. } => {
-                state.keyboard_focus = None;
+                if let Some(seat) = state.seats.get_mut(data) {
+                    seat.keyboard_focus = None;
+                }
                 let surface_id = state.get_id_from_surface(&surface);
+                let released = state
+                    .get_keyboard_state_mut(wl_keyboard)
+                    .map(|keyboard_state| keyboard_state.release_all_keys())
+                    .unwrap_or_default();
                 if surface_id.is_some() {
-                    state.message.push((
-                        surface_id,
-                        DispatchMessageInner::ModifiersChanged(ModifiersState::empty()),
-                    ));
-                    state
-                        .message
-                        .push((surface_id, DispatchMessageInner::Unfocus));
+                    // nothing stays held for the surface once it lost the focus
+                    for event in released {
+                        let event = DispatchMessageInner::KeyboardInput {
+                            event,
+                            is_synthetic: true,
+                            seat: seat_id,
+                        };
+                        state.push_seat_message(data, (surface_id, event));
+                    }
+                    state.push_seat_message(
+                        data,
+                        (
+                            surface_id,
+                            DispatchMessageInner::ModifiersChanged {
+                                modifiers: ModifiersState::empty(),
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                    state.push_seat_message(
+                        data,
+                        (surface_id, DispatchMessageInner::Unfocus { seat: seat_id }),
+                    );
                 }
                 let Some(keyboard_state) = state.get_keyboard_state_mut(wl_keyboard) else {
                     return;
                 };
                 keyboard_state.current_repeat = None;
-                if let Some(token) = keyboard_state.repeat_token.take() {
+                let token = keyboard_state.repeat_token.take();
+                // a sequence does not carry over to the next focus
+                let composing = keyboard_state
+                    .compose
+                    .as_mut()
+                    .filter(|compose| compose.is_composing())
+                    .map(|compose| compose.reset())
+                    .is_some();
+                if let Some(token) = token {
                     state.to_remove_tokens.push(token);
                 }
+                if composing && surface_id.is_some() {
+                    state.push_seat_message(
+                        data,
+                        (
+                            surface_id,
+                            DispatchMessageInner::Compose {
+                                state: ComposeState::Cancelled,
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                }
             }
             wl_keyboard::Event::Key {
                 state: keystate,
+                serial,
                 key,
                 ..
             } => {
-                let surface_id = state.keyboard_focus_id();
+                let Some(seat) = state.seats.get_mut(data) else {
+                    return;
+                };
+                if keystate == WEnum::Value(KeyState::Pressed) {
+                    seat.input_serial = Some(serial);
+                    state.last_input_serial = Some(serial);
+                }
+                state.last_seat = Some(data.clone());
+                let surface_id = state.seat_keyboard_focus_id(data);
                 let pressed_state = match keystate {
                     WEnum::Value(KeyState::Pressed) => ElementState::Pressed,
                     WEnum::Value(KeyState::Released) => ElementState::Released,
@@ -306,11 +621,22 @@ impl<T> Dispatch<wl_keyboard::WlKeyboard, ()> for WindowState<T> {
                 else {
                     return;
                 };
-                if let Some(mut key_context) = keyboard_state.xkb_context.key_context() {
-                    let event = key_context.process_key_event(key, pressed_state, false);
+                if let Some((event, compose)) =
+                    keyboard_state.process_key_event(key, pressed_state, false)
+                {
+                    if let Some(compose) = compose {
+                        state.message.push((
+                            surface_id,
+                            DispatchMessageInner::Compose {
+                                state: compose,
+                                seat: seat_id,
+                            },
+                        ));
+                    }
                     let event = DispatchMessageInner::KeyboardInput {
                         event,
                         is_synthetic: false,
+                        seat: seat_id,
                     };
                     state.message.push((surface_id, event));
                 }
@@ -341,6 +667,7 @@ impl<T> Dispatch<wl_keyboard::WlKeyboard, ()> for WindowState<T> {
                             surface_id,
                             pressed_state,
                             object_id: wl_keyboard.id(),
+                            seat: data.clone(),
                         });
                     }
                     ElementState::Released => {
@@ -378,11 +705,32 @@ impl<T> Dispatch<wl_keyboard::WlKeyboard, ()> for WindowState<T> {
                 };
                 xkb_state.update_modifiers(mods_depressed, mods_latched, mods_locked, 0, 0, group);
                 let modifiers = xkb_state.modifiers();
+                let layout = keyboard_state.update_layout(group);
 
-                state.message.push((
-                    state.keyboard_focus_id(),
-                    DispatchMessageInner::ModifiersChanged(modifiers.into()),
-                ))
+                let surface_id = state.seat_keyboard_focus_id(data);
+                state.push_seat_message(
+                    data,
+                    (
+                        surface_id,
+                        DispatchMessageInner::ModifiersChanged {
+                            modifiers: modifiers.into(),
+                            seat: seat_id,
+                        },
+                    ),
+                );
+                if let Some((index, name)) = layout {
+                    state.push_seat_message(
+                        data,
+                        (
+                            surface_id,
+                            DispatchMessageInner::KeyboardLayoutChanged {
+                                index,
+                                name,
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                }
             }
             wl_keyboard::Event::RepeatInfo { rate, delay } => {
                 let Some(keyboard_state) = state
@@ -417,15 +765,25 @@ impl<T> Dispatch<wl_keyboard::WlKeyboard, ()> for WindowState<T> {
     }
 }
 
-impl<T> Dispatch<wl_touch::WlTouch, ()> for WindowState<T> {
+impl<T> Dispatch<wl_touch::WlTouch, ObjectId> for WindowState<T> {
     fn event(
         state: &mut Self,
         _proxy: &wl_touch::WlTouch,
         event: <wl_touch::WlTouch as Proxy>::Event,
-        _data: &(),
+        data: &ObjectId,
         _conn: &Connection,
         _qhandle: &QueueHandle<Self>,
     ) {
+        let Some(seat_id) = state.seat_id(data) else {
+            return;
+        };
+        let surface_id = match &event {
+            wl_touch::Event::Down { surface, .. } => state.get_id_from_surface(surface),
+            _ => None,
+        };
+        let Some(seat) = state.seats.get_mut(data) else {
+            return;
+        };
         match event {
             wl_touch::Event::Down {
                 serial,
@@ -435,42 +793,52 @@ impl<T> Dispatch<wl_touch::WlTouch, ()> for WindowState<T> {
                 x,
                 y,
             } => {
-                state.finger_locations.insert(id, (x, y));
-                let surface_id = state.get_id_from_surface(&surface);
-                state
-                    .active_surfaces
+                seat.input_serial = Some(serial);
+                state.last_input_serial = Some(serial);
+                seat.finger_locations.insert(id, (x, y));
+                seat.active_surfaces
                     .insert(Some(id), (surface.clone(), surface_id));
                 state.update_active_output(&surface);
-                state.message.push((
-                    surface_id,
-                    DispatchMessageInner::TouchDown {
-                        serial,
-                        time,
-                        id,
-                        x,
-                        y,
-                    },
-                ))
+                state.push_seat_message(
+                    data,
+                    (
+                        surface_id,
+                        DispatchMessageInner::TouchDown {
+                            serial,
+                            time,
+                            id,
+                            x,
+                            y,
+                            seat: seat_id,
+                        },
+                    ),
+                )
             }
             wl_touch::Event::Cancel => {
-                let mut mouse_surface = None;
-                for (k, v) in state.active_surfaces.drain() {
-                    if let Some(id) = k {
-                        let (x, y) = state.finger_locations.remove(&id).unwrap_or_default();
-                        state
-                            .message
-                            .push((v.1, DispatchMessageInner::TouchCancel { id, x, y }));
-                    } else {
-                        // keep the surface of mouse.
-                        mouse_surface = Some(v);
-                    }
-                }
-                if let Some(mouse_surface) = mouse_surface {
-                    state.active_surfaces.insert(None, mouse_surface);
+                let mut cancelled = Vec::new();
+                // keep the surface of mouse.
+                seat.active_surfaces.retain(|k, v| {
+                    let Some(id) = *k else {
+                        return true;
+                    };
+                    cancelled.push((id, v.1));
+                    false
+                });
+                for (id, surface_id) in cancelled {
+                    let (x, y) = seat.finger_locations.remove(&id).unwrap_or_default();
+                    state.message.push((
+                        surface_id,
+                        DispatchMessageInner::TouchCancel {
+                            id,
+                            x,
+                            y,
+                            seat: seat_id,
+                        },
+                    ));
                 }
             }
             wl_touch::Event::Up { serial, time, id } => {
-                let surface_id = state
+                let surface_id = seat
                     .active_surfaces
                     .remove(&Some(id))
                     .or_else(|| {
@@ -478,20 +846,24 @@ impl<T> Dispatch<wl_touch::WlTouch, ()> for WindowState<T> {
                         None
                     })
                     .and_then(|(_, id)| id);
-                let (x, y) = state.finger_locations.remove(&id).unwrap_or_default();
-                state.message.push((
-                    surface_id,
-                    DispatchMessageInner::TouchUp {
-                        serial,
-                        time,
-                        id,
-                        x,
-                        y,
-                    },
-                ));
+                let (x, y) = seat.finger_locations.remove(&id).unwrap_or_default();
+                state.push_seat_message(
+                    data,
+                    (
+                        surface_id,
+                        DispatchMessageInner::TouchUp {
+                            serial,
+                            time,
+                            id,
+                            x,
+                            y,
+                            seat: seat_id,
+                        },
+                    ),
+                );
             }
             wl_touch::Event::Motion { time, id, x, y } => {
-                let surface_id = state
+                let surface_id = seat
                     .active_surfaces
                     .get(&Some(id))
                     .or_else(|| {
@@ -499,197 +871,148 @@ impl<T> Dispatch<wl_touch::WlTouch, ()> for WindowState<T> {
                         None
                     })
                     .and_then(|(_, id)| *id);
-                state.finger_locations.insert(id, (x, y));
-                state.message.push((
-                    surface_id,
-                    DispatchMessageInner::TouchMotion { time, id, x, y },
-                ));
+                seat.finger_locations.insert(id, (x, y));
+                state.push_seat_message(
+                    data,
+                    (
+                        surface_id,
+                        DispatchMessageInner::TouchMotion {
+                            time,
+                            id,
+                            x,
+                            y,
+                            seat: seat_id,
+                        },
+                    ),
+                );
             }
             _ => {}
         }
     }
 }
 
-impl<T> Dispatch<wl_pointer::WlPointer, ()> for WindowState<T> {
+impl<T> Dispatch<wl_pointer::WlPointer, ObjectId> for WindowState<T> {
     fn event(
         state: &mut Self,
         pointer: &wl_pointer::WlPointer,
         event: <wl_pointer::WlPointer as Proxy>::Event,
-        _data: &(),
+        data: &ObjectId,
         _conn: &Connection,
         _qhandle: &wayland_client::QueueHandle<Self>,
     ) {
+        let Some(seat_id) = state.seat_id(data) else {
+            return;
+        };
         // All mouse events should be happened on the surface which is hovered by the mouse.
         let (mouse_surface, surface_id) = state
-            .active_surfaces
-            .get(&None)
-            .map(|(surface, id)| (Some(surface), *id))
+            .seats
+            .get(data)
+            .and_then(|seat| seat.active_surfaces.get(&None))
+            .map(|(surface, id)| (Some(surface.clone()), *id))
             .unwrap_or_else(|| (None, None));
         let scale = surface_id
             .and_then(|id| state.get_unit_with_id(id))
             .map(|unit| unit.scale_float())
             .unwrap_or(1.0);
         match event {
-            wl_pointer::Event::Axis { time, axis, value } => match axis {
-                WEnum::Value(axis) => {
-                    let (mut horizontal, mut vertical) = <(AxisScroll, AxisScroll)>::default();
-                    match axis {
-                        wl_pointer::Axis::VerticalScroll => {
-                            vertical.absolute = value;
-                        }
-                        wl_pointer::Axis::HorizontalScroll => {
-                            horizontal.absolute = value;
-                        }
-                        _ => unreachable!(),
-                    };
-
-                    state.message.push((
-                        surface_id,
-                        DispatchMessageInner::Axis {
-                            time,
-                            scale,
-                            horizontal,
-                            vertical,
-                            source: None,
-                        },
-                    ))
-                }
-                WEnum::Unknown(unknown) => {
-                    log::warn!(target: "exwlshellev", "{}: invalid pointer axis: {:x}", pointer.id(), unknown);
-                }
-            },
-            wl_pointer::Event::AxisStop { time, axis } => match axis {
-                WEnum::Value(axis) => {
-                    let (mut horizontal, mut vertical) = <(AxisScroll, AxisScroll)>::default();
-                    match axis {
-                        wl_pointer::Axis::VerticalScroll => vertical.stop = true,
-                        wl_pointer::Axis::HorizontalScroll => horizontal.stop = true,
-
-                        _ => unreachable!(),
+            wl_pointer::Event::Axis { time, axis, value } => {
+                state.update_axis_frame(pointer, data, surface_id, scale, |frame| {
+                    frame.time = time;
+                    if let Some(scroll) = frame.scroll(axis) {
+                        scroll.absolute += value;
                     }
-
-                    state.message.push((
-                        surface_id,
-                        DispatchMessageInner::Axis {
-                            time,
-                            scale,
-                            horizontal,
-                            vertical,
-                            source: None,
-                        },
-                    ));
-                }
-
-                WEnum::Unknown(unknown) => {
-                    log::warn!(target: "exwlshellev", "{}: invalid pointer axis: {:x}", pointer.id(), unknown);
-                }
-            },
+                });
+            }
+            wl_pointer::Event::AxisStop { time, axis } => {
+                state.update_axis_frame(pointer, data, surface_id, scale, |frame| {
+                    frame.time = time;
+                    if let Some(scroll) = frame.scroll(axis) {
+                        scroll.stop = true;
+                    }
+                });
+            }
             wl_pointer::Event::AxisSource { axis_source } => match axis_source {
-                WEnum::Value(source) => state.message.push((
-                    surface_id,
-                    DispatchMessageInner::Axis {
-                        horizontal: AxisScroll::default(),
-                        vertical: AxisScroll::default(),
-                        scale,
-                        source: Some(source),
-                        time: 0,
-                    },
-                )),
-                WEnum::Unknown(unknown) => {
-                    log::warn!(target: "exwlshellev", "unknown pointer axis source: {unknown:x}");
-                }
-            },
-            wl_pointer::Event::AxisValue120 { axis, value120 } => match axis {
-                WEnum::Value(axis) => {
-                    let (mut horizontal, mut vertical) = <(AxisScroll, AxisScroll)>::default();
-                    match axis {
-                        wl_pointer::Axis::VerticalScroll => vertical.discrete = value120 / 120,
-                        wl_pointer::Axis::HorizontalScroll => horizontal.discrete = value120 / 120,
-                        _ => unreachable!(),
-                    };
-
-                    state.message.push((
-                        surface_id,
-                        DispatchMessageInner::Axis {
-                            time: 0,
-                            scale,
-                            horizontal,
-                            vertical,
-                            source: None,
-                        },
-                    ));
+                WEnum::Value(source) => {
+                    state.update_axis_frame(pointer, data, surface_id, scale, |frame| {
+                        frame.source = Some(source);
+                    });
                 }
-
                 WEnum::Unknown(unknown) => {
-                    log::warn!(target: "wxwlshellev", "{}: invalid pointer axis: {:x}", pointer.id(), unknown);
+                    log::warn!(target: "exwlshellev", "unknown pointer axis source: {unknown:x}");
                 }
             },
+            wl_pointer::Event::AxisValue120 { axis, value120 } => {
+                state.update_axis_frame(pointer, data, surface_id, scale, |frame| {
+                    if let Some(scroll) = frame.scroll(axis) {
+                        scroll.value120 += value120;
+                        scroll.discrete = scroll.value120 / 120;
+                    }
+                });
+            }
             // AxisDiscrete is deprecated since wl_pointer::Event::AxisValue120 is added, but some compositors may still use it.
-            wl_pointer::Event::AxisDiscrete { axis, discrete } => match axis {
-                WEnum::Value(axis) => {
-                    let (mut horizontal, mut vertical) = <(AxisScroll, AxisScroll)>::default();
-                    match axis {
-                        wl_pointer::Axis::VerticalScroll => {
-                            vertical.discrete = discrete;
-                        }
-
-                        wl_pointer::Axis::HorizontalScroll => {
-                            horizontal.discrete = discrete;
-                        }
-
-                        _ => unreachable!(),
-                    };
-
-                    state.message.push((
-                        surface_id,
-                        DispatchMessageInner::Axis {
-                            time: 0,
-                            scale,
-                            horizontal,
-                            vertical,
-                            source: None,
-                        },
-                    ));
-                }
-
-                WEnum::Unknown(unknown) => {
-                    log::warn!(target: "exwlshellev", "{}: invalid pointer axis: {:x}", pointer.id(), unknown);
-                }
-            },
+            wl_pointer::Event::AxisDiscrete { axis, discrete } => {
+                state.update_axis_frame(pointer, data, surface_id, scale, |frame| {
+                    if let Some(scroll) = frame.scroll(axis) {
+                        scroll.discrete += discrete;
+                    }
+                });
+            }
+            wl_pointer::Event::AxisRelativeDirection { axis, direction } => {
+                state.update_axis_frame(pointer, data, surface_id, scale, |frame| {
+                    if let Some(scroll) = frame.scroll(axis) {
+                        scroll.inverted =
+                            direction == WEnum::Value(wl_pointer::AxisRelativeDirection::Inverted);
+                    }
+                });
+            }
+            wl_pointer::Event::Frame => state.flush_axis_frame(data, surface_id, scale),
             wl_pointer::Event::Button {
                 state: btnstate,
                 serial,
                 button,
                 time,
             } => {
-                if matches!(btnstate, WEnum::Value(wl_pointer::ButtonState::Pressed)) {
-                    state.button_serial = Some(serial);
+                if matches!(btnstate, WEnum::Value(wl_pointer::ButtonState::Pressed))
+                    && let Some(seat) = state.seats.get_mut(data)
+                {
+                    seat.button_serial = Some(serial);
+                    seat.input_serial = Some(serial);
+                    state.last_input_serial = Some(serial);
                 }
-                if let Some(mouse_surface) = mouse_surface.cloned() {
+                if let Some(mouse_surface) = mouse_surface {
                     state.update_active_output(&mouse_surface);
                 }
-                state.message.push((
-                    surface_id,
-                    DispatchMessageInner::MouseButton {
-                        state: btnstate,
-                        serial,
-                        button,
-                        time,
-                    },
-                ));
+                state.push_seat_message(
+                    data,
+                    (
+                        surface_id,
+                        DispatchMessageInner::MouseButton {
+                            state: btnstate,
+                            serial,
+                            button,
+                            time,
+                            seat: seat_id,
+                        },
+                    ),
+                );
             }
             wl_pointer::Event::Leave { .. } => {
                 let surface_id = state
-                    .active_surfaces
-                    .remove(&None)
+                    .seats
+                    .get_mut(data)
+                    .and_then(|seat| seat.active_surfaces.remove(&None))
                     .or_else(|| {
                         log::warn!("mouse hasn't entered.");
                         None
                     })
                     .and_then(|(_, id)| id);
-                state
-                    .message
-                    .push((surface_id, DispatchMessageInner::MouseLeave));
+                state.push_seat_message(
+                    data,
+                    (
+                        surface_id,
+                        DispatchMessageInner::MouseLeave { seat: seat_id },
+                    ),
+                );
             }
             wl_pointer::Event::Enter {
                 serial,
@@ -698,33 +1021,42 @@ impl<T> Dispatch<wl_pointer::WlPointer, ()> for WindowState<T> {
                 surface_y,
             } => {
                 let surface_id = state.get_id_from_surface(&surface);
-                state
-                    .active_surfaces
-                    .insert(None, (surface.clone(), surface_id));
-                state.enter_serial = Some(serial);
-                state.message.push((
-                    surface_id,
-                    DispatchMessageInner::MouseEnter {
-                        pointer: pointer.clone(),
-                        serial,
-                        surface_x,
-                        surface_y,
-                    },
-                ));
+                if let Some(seat) = state.seats.get_mut(data) {
+                    seat.active_surfaces
+                        .insert(None, (surface.clone(), surface_id));
+                    seat.enter_serial = Some(serial);
+                }
+                state.push_seat_message(
+                    data,
+                    (
+                        surface_id,
+                        DispatchMessageInner::MouseEnter {
+                            pointer: pointer.clone(),
+                            serial,
+                            surface_x,
+                            surface_y,
+                            seat: seat_id,
+                        },
+                    ),
+                );
             }
             wl_pointer::Event::Motion {
                 time,
                 surface_x,
                 surface_y,
             } => {
-                state.message.push((
-                    surface_id,
-                    DispatchMessageInner::MouseMotion {
-                        time,
-                        surface_x,
-                        surface_y,
-                    },
-                ));
+                state.push_seat_message(
+                    data,
+                    (
+                        surface_id,
+                        DispatchMessageInner::MouseMotion {
+                            time,
+                            surface_x,
+                            surface_y,
+                            seat: seat_id,
+                        },
+                    ),
+                );
             }
             _ => {
                 // TODO: not now
@@ -733,4 +1065,348 @@ impl<T> Dispatch<wl_pointer::WlPointer, ()> for WindowState<T> {
     }
 }
 
+/// The axis events of a pointer since its last `wl_pointer.frame`
+#[derive(Debug, Default)]
+pub(crate) struct AxisFrame {
+    time: u32,
+    horizontal: AxisScroll,
+    vertical: AxisScroll,
+    source: Option<wl_pointer::AxisSource>,
+}
+
+impl AxisFrame {
+    fn scroll(&mut self, axis: WEnum<wl_pointer::Axis>) -> Option<&mut AxisScroll> {
+        match axis {
+            WEnum::Value(wl_pointer::Axis::VerticalScroll) => Some(&mut self.vertical),
+            WEnum::Value(wl_pointer::Axis::HorizontalScroll) => Some(&mut self.horizontal),
+            axis => {
+                log::warn!(target: "exwlshellev", "invalid pointer axis: {axis:?}");
+                None
+            }
+        }
+    }
+}
+
+impl<T> WindowState<T> {
+    /// Add an axis event to the frame of the pointer of `seat`. Pointers older than version 5
+    /// have no frames, so each of their events is sent on its own.
+    fn update_axis_frame(
+        &mut self,
+        pointer: &WlPointer,
+        seat: &ObjectId,
+        surface_id: Option<Id>,
+        scale: f64,
+        update: impl FnOnce(&mut AxisFrame),
+    ) {
+        let Some(storage) = self.seats.get_mut(seat) else {
+            return;
+        };
+        update(storage.axis_frame.get_or_insert_default());
+        if pointer.version() < 5 {
+            self.flush_axis_frame(seat, surface_id, scale);
+        }
+    }
+
+    /// Send the axis events of the frame as one [`DispatchMessageInner::Axis`]
+    fn flush_axis_frame(&mut self, seat: &ObjectId, surface_id: Option<Id>, scale: f64) {
+        let Some((seat_id, frame)) = self
+            .seats
+            .get_mut(seat)
+            .and_then(|storage| Some((storage.id, storage.axis_frame.take()?)))
+        else {
+            return;
+        };
+        self.push_seat_message(
+            seat,
+            (
+                surface_id,
+                DispatchMessageInner::Axis {
+                    time: frame.time,
+                    scale,
+                    horizontal: frame.horizontal,
+                    vertical: frame.vertical,
+                    source: frame.source,
+                    seat: seat_id,
+                },
+            ),
+        );
+    }
+
+    /// Remember the surface a gesture of `seat` began on, so its updates go there too
+    fn begin_gesture(&mut self, seat: &ObjectId, surface: &WlSurface) -> Option<Id> {
+        let target = self.get_id_from_surface(surface);
+        if let Some(seat) = self.seats.get_mut(seat) {
+            seat.gesture_target = target;
+        }
+        target
+    }
+
+    fn gesture_target(&self, seat: &ObjectId) -> Option<Id> {
+        self.seats.get(seat)?.gesture_target
+    }
+
+    fn end_gesture(&mut self, seat: &ObjectId) -> Option<Id> {
+        self.seats.get_mut(seat)?.gesture_target.take()
+    }
+}
+
+impl<T> Dispatch<ZwpRelativePointerV1, ObjectId> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpRelativePointerV1,
+        event: <ZwpRelativePointerV1 as Proxy>::Event,
+        data: &ObjectId,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(seat_id) = state.seat_id(data) else {
+            return;
+        };
+        let zwp_relative_pointer_v1::Event::RelativeMotion {
+            dx,
+            dy,
+            dx_unaccel,
+            dy_unaccel,
+            ..
+        } = event
+        else {
+            return;
+        };
+        let Some(id) = state
+            .seats
+            .get(data)
+            .and_then(|seat| seat.active_surfaces.get(&None))
+            .and_then(|(_, id)| *id)
+        else {
+            return;
+        };
+        state.push_seat_message(
+            data,
+            (
+                Some(id),
+                DispatchMessageInner::RelativeMotion {
+                    dx,
+                    dy,
+                    dx_unaccel,
+                    dy_unaccel,
+                    seat: seat_id,
+                },
+            ),
+        );
+    }
+}
+
+impl<T> Dispatch<ZwpPointerGestureSwipeV1, ObjectId> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPointerGestureSwipeV1,
+        event: <ZwpPointerGestureSwipeV1 as Proxy>::Event,
+        data: &ObjectId,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(seat_id) = state.seat_id(data) else {
+            return;
+        };
+        match event {
+            zwp_pointer_gesture_swipe_v1::Event::Begin {
+                time,
+                surface,
+                fingers,
+                ..
+            } => {
+                if let Some(id) = state.begin_gesture(data, &surface) {
+                    state.push_seat_message(
+                        data,
+                        (
+                            Some(id),
+                            DispatchMessageInner::SwipeBegin {
+                                id,
+                                time,
+                                fingers,
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                }
+            }
+            zwp_pointer_gesture_swipe_v1::Event::Update { time, dx, dy } => {
+                if let Some(id) = state.gesture_target(data) {
+                    state.push_seat_message(
+                        data,
+                        (
+                            Some(id),
+                            DispatchMessageInner::SwipeUpdate {
+                                id,
+                                time,
+                                dx,
+                                dy,
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                }
+            }
+            zwp_pointer_gesture_swipe_v1::Event::End {
+                time, cancelled, ..
+            } => {
+                if let Some(id) = state.end_gesture(data) {
+                    state.push_seat_message(
+                        data,
+                        (
+                            Some(id),
+                            DispatchMessageInner::SwipeEnd {
+                                id,
+                                time,
+                                cancelled: cancelled != 0,
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T> Dispatch<ZwpPointerGesturePinchV1, ObjectId> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPointerGesturePinchV1,
+        event: <ZwpPointerGesturePinchV1 as Proxy>::Event,
+        data: &ObjectId,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(seat_id) = state.seat_id(data) else {
+            return;
+        };
+        match event {
+            zwp_pointer_gesture_pinch_v1::Event::Begin {
+                time,
+                surface,
+                fingers,
+                ..
+            } => {
+                if let Some(id) = state.begin_gesture(data, &surface) {
+                    state.push_seat_message(
+                        data,
+                        (
+                            Some(id),
+                            DispatchMessageInner::PinchBegin {
+                                id,
+                                time,
+                                fingers,
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                }
+            }
+            zwp_pointer_gesture_pinch_v1::Event::Update {
+                time,
+                dx,
+                dy,
+                scale,
+                rotation,
+            } => {
+                if let Some(id) = state.gesture_target(data) {
+                    state.push_seat_message(
+                        data,
+                        (
+                            Some(id),
+                            DispatchMessageInner::PinchUpdate {
+                                id,
+                                time,
+                                dx,
+                                dy,
+                                scale,
+                                rotation,
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                }
+            }
+            zwp_pointer_gesture_pinch_v1::Event::End {
+                time, cancelled, ..
+            } => {
+                if let Some(id) = state.end_gesture(data) {
+                    state.push_seat_message(
+                        data,
+                        (
+                            Some(id),
+                            DispatchMessageInner::PinchEnd {
+                                id,
+                                time,
+                                cancelled: cancelled != 0,
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T> Dispatch<ZwpPointerGestureHoldV1, ObjectId> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpPointerGestureHoldV1,
+        event: <ZwpPointerGestureHoldV1 as Proxy>::Event,
+        data: &ObjectId,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(seat_id) = state.seat_id(data) else {
+            return;
+        };
+        match event {
+            zwp_pointer_gesture_hold_v1::Event::Begin {
+                time,
+                surface,
+                fingers,
+                ..
+            } => {
+                if let Some(id) = state.begin_gesture(data, &surface) {
+                    state.push_seat_message(
+                        data,
+                        (
+                            Some(id),
+                            DispatchMessageInner::HoldBegin {
+                                id,
+                                time,
+                                fingers,
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                }
+            }
+            zwp_pointer_gesture_hold_v1::Event::End {
+                time, cancelled, ..
+            } => {
+                if let Some(id) = state.end_gesture(data) {
+                    state.push_seat_message(
+                        data,
+                        (
+                            Some(id),
+                            DispatchMessageInner::HoldEnd {
+                                id,
+                                time,
+                                cancelled: cancelled != 0,
+                                seat: seat_id,
+                            },
+                        ),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 delegate_noop!(@<T: 'static> WindowState<T>: ignore WlSeat);
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use wayland_backend::client::ObjectId;
+use wayland_client::{
+    Connection, Dispatch, Proxy, QueueHandle, WEnum, delegate_noop, event_created_child,
+};
+use wayland_protocols::wp::tablet::zv2::client::{
+    zwp_tablet_manager_v2::ZwpTabletManagerV2,
+    zwp_tablet_pad_group_v2::{self, ZwpTabletPadGroupV2},
+    zwp_tablet_pad_ring_v2::{self, ZwpTabletPadRingV2},
+    zwp_tablet_pad_strip_v2::{self, ZwpTabletPadStripV2},
+    zwp_tablet_pad_v2::{self, ZwpTabletPadV2},
+    zwp_tablet_seat_v2::{self, ZwpTabletSeatV2},
+    zwp_tablet_tool_v2::{self, ZwpTabletToolV2},
+    zwp_tablet_v2::{self, ZwpTabletV2},
+};
+
+use crate::id::Id;
+use crate::{DispatchMessageInner, SeatId, WindowState};
+
+/// pressure and strip positions are normalized to this range by the protocol
+const AXIS_MAX: f64 = 65535.;
+
+#[derive(Debug)]
+struct Tool {
+    /// the wl_seat of the tablet seat the tool was added to
+    seat: ObjectId,
+    tool_type: Option<zwp_tablet_tool_v2::Type>,
+    /// the surface the tool is in proximity of
+    focus: Option<Id>,
+}
+
+#[derive(Debug)]
+struct Pad {
+    /// the wl_seat of the tablet seat the pad was added to
+    seat: ObjectId,
+    /// the surface the pad is focused on
+    focus: Option<Id>,
+    /// destroyed with the pad
+    groups: Vec<ZwpTabletPadGroupV2>,
+    rings: Vec<ZwpTabletPadRingV2>,
+    strips: Vec<ZwpTabletPadStripV2>,
+}
+
+/// Tools and pads of all tablet seats, keyed by their object
+#[derive(Debug, Default)]
+pub(crate) struct TabletState {
+    tools: HashMap<ObjectId, Tool>,
+    pads: HashMap<ObjectId, Pad>,
+}
+
+impl<T> WindowState<T> {
+    fn tool_focus(&self, tool: &ZwpTabletToolV2) -> Option<(Id, ObjectId)> {
+        let tool = self.tablet.tools.get(&tool.id())?;
+        Some((tool.focus?, tool.seat.clone()))
+    }
+
+    /// The surface `pad` is focused on, with the seat of the pad and its id
+    fn pad_focus(&self, pad: &ObjectId) -> Option<(Id, ObjectId, SeatId)> {
+        let pad = self.tablet.pads.get(pad)?;
+        Some((pad.focus?, pad.seat.clone(), self.seat_id(&pad.seat)?))
+    }
+}
+
+impl<T: 'static> WindowState<T> {
+    /// Give every seat without one a tablet seat. Tablets are not a wl_seat capability, a seat
+    /// may have them and nothing else.
+    pub(crate) fn bind_tablet_seats(&mut self, qh: &QueueHandle<Self>) {
+        let Some(manager) = &self.tablet_manager else {
+            return;
+        };
+        for storage in self.seats.values_mut() {
+            if storage.tablet_seat.is_none() {
+                storage.tablet_seat =
+                    Some(manager.get_tablet_seat(&storage.seat, qh, storage.seat.id()));
+            }
+        }
+    }
+}
+
+impl<T: 'static> Dispatch<ZwpTabletSeatV2, ObjectId> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTabletSeatV2,
+        event: <ZwpTabletSeatV2 as Proxy>::Event,
+        data: &ObjectId,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_tablet_seat_v2::Event::ToolAdded { id } => {
+                let tool = Tool {
+                    seat: data.clone(),
+                    tool_type: None,
+                    focus: None,
+                };
+                state.tablet.tools.insert(id.id(), tool);
+            }
+            zwp_tablet_seat_v2::Event::PadAdded { id } => {
+                let pad = Pad {
+                    seat: data.clone(),
+                    focus: None,
+                    groups: Vec::new(),
+                    rings: Vec::new(),
+                    strips: Vec::new(),
+                };
+                state.tablet.pads.insert(id.id(), pad);
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(WindowState<T>, ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (ZwpTabletV2, ()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (ZwpTabletToolV2, ()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (ZwpTabletPadV2, ()),
+    ]);
+}
+
+impl<T> Dispatch<ZwpTabletV2, ()> for WindowState<T> {
+    fn event(
+        _state: &mut Self,
+        proxy: &ZwpTabletV2,
+        event: <ZwpTabletV2 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_v2::Event::Removed = event {
+            proxy.destroy();
+        }
+    }
+}
+
+impl<T> Dispatch<ZwpTabletToolV2, ()> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwpTabletToolV2,
+        event: <ZwpTabletToolV2 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let zwp_tablet_tool_v2::Event::Removed = event {
+            state.tablet.tools.remove(&proxy.id());
+            proxy.destroy();
+            return;
+        }
+        let Some(seat) = state
+            .tablet
+            .tools
+            .get(&proxy.id())
+            .and_then(|tool| state.seat_id(&tool.seat))
+        else {
+            return;
+        };
+        let message = match event {
+            zwp_tablet_tool_v2::Event::Type { tool_type } => {
+                if let Some(tool) = state.tablet.tools.get_mut(&proxy.id()) {
+                    tool.tool_type = tool_type.into_result().ok();
+                }
+                return;
+            }
+            zwp_tablet_tool_v2::Event::ProximityIn { surface, .. } => {
+                let focus = state.get_id_from_surface(&surface);
+                let Some(tool) = state.tablet.tools.get_mut(&proxy.id()) else {
+                    return;
+                };
+                tool.focus = focus;
+                DispatchMessageInner::TabletToolProximityIn {
+                    tool_type: tool.tool_type,
+                    seat,
+                }
+            }
+            zwp_tablet_tool_v2::Event::ProximityOut => {
+                if let Some((id, seat_object)) = state.tool_focus(proxy) {
+                    state.push_seat_message(
+                        &seat_object,
+                        (
+                            Some(id),
+                            DispatchMessageInner::TabletToolProximityOut { seat },
+                        ),
+                    );
+                }
+                if let Some(tool) = state.tablet.tools.get_mut(&proxy.id()) {
+                    tool.focus = None;
+                }
+                return;
+            }
+            zwp_tablet_tool_v2::Event::Down { .. } => DispatchMessageInner::TabletToolDown { seat },
+            zwp_tablet_tool_v2::Event::Up => DispatchMessageInner::TabletToolUp { seat },
+            zwp_tablet_tool_v2::Event::Motion { x, y } => DispatchMessageInner::TabletToolMotion {
+                surface_x: x,
+                surface_y: y,
+                seat,
+            },
+            zwp_tablet_tool_v2::Event::Pressure { pressure } => {
+                DispatchMessageInner::TabletToolPressure {
+                    pressure: pressure as f64 / AXIS_MAX,
+                    seat,
+                }
+            }
+            zwp_tablet_tool_v2::Event::Tilt { tilt_x, tilt_y } => {
+                DispatchMessageInner::TabletToolTilt {
+                    x: tilt_x,
+                    y: tilt_y,
+                    seat,
+                }
+            }
+            zwp_tablet_tool_v2::Event::Rotation { degrees } => {
+                DispatchMessageInner::TabletToolRotation { degrees, seat }
+            }
+            zwp_tablet_tool_v2::Event::Button {
+                button,
+                state: button_state,
+                ..
+            } => DispatchMessageInner::TabletToolButton {
+                button,
+                pressed: button_state == WEnum::Value(zwp_tablet_tool_v2::ButtonState::Pressed),
+                seat,
+            },
+            _ => return,
+        };
+        if let Some((id, seat_object)) = state.tool_focus(proxy) {
+            state.push_seat_message(&seat_object, (Some(id), message));
+        }
+    }
+}
+
+impl<T: 'static> Dispatch<ZwpTabletPadV2, ()> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        proxy: &ZwpTabletPadV2,
+        event: <ZwpTabletPadV2 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_tablet_pad_v2::Event::Group { pad_group } => {
+                // rings and strips of the group find the pad through this
+                if let Some(pad) = pad_group.data::<OnceLock<ObjectId>>() {
+                    let _ = pad.set(proxy.id());
+                }
+                if let Some(pad) = state.tablet.pads.get_mut(&proxy.id()) {
+                    pad.groups.push(pad_group);
+                }
+            }
+            zwp_tablet_pad_v2::Event::Enter { surface, .. } => {
+                let focus = state.get_id_from_surface(&surface);
+                if let Some(pad) = state.tablet.pads.get_mut(&proxy.id()) {
+                    pad.focus = focus;
+                }
+            }
+            zwp_tablet_pad_v2::Event::Leave { .. } => {
+                if let Some(pad) = state.tablet.pads.get_mut(&proxy.id()) {
+                    pad.focus = None;
+                }
+            }
+            zwp_tablet_pad_v2::Event::Button {
+                button,
+                state: button_state,
+                ..
+            } => {
+                if let Some((id, seat_object, seat)) = state.pad_focus(&proxy.id()) {
+                    state.push_seat_message(
+                        &seat_object,
+                        (
+                            Some(id),
+                            DispatchMessageInner::TabletPadButton {
+                                button,
+                                pressed: button_state
+                                    == WEnum::Value(zwp_tablet_pad_v2::ButtonState::Pressed),
+                                seat,
+                            },
+                        ),
+                    );
+                }
+            }
+            zwp_tablet_pad_v2::Event::Removed => {
+                // the objects of the pad go before the pad
+                if let Some(pad) = state.tablet.pads.remove(&proxy.id()) {
+                    pad.rings.iter().for_each(ZwpTabletPadRingV2::destroy);
+                    pad.strips.iter().for_each(ZwpTabletPadStripV2::destroy);
+                    pad.groups.iter().for_each(ZwpTabletPadGroupV2::destroy);
+                }
+                proxy.destroy();
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(WindowState<T>, ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (ZwpTabletPadGroupV2, OnceLock::<ObjectId>::new()),
+    ]);
+}
+
+impl<T: 'static> Dispatch<ZwpTabletPadGroupV2, OnceLock<ObjectId>> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTabletPadGroupV2,
+        event: <ZwpTabletPadGroupV2 as Proxy>::Event,
+        data: &OnceLock<ObjectId>,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(pad_id) = data.get() else {
+            return;
+        };
+        let child = match &event {
+            zwp_tablet_pad_group_v2::Event::Ring { ring } => ring.data::<OnceLock<ObjectId>>(),
+            zwp_tablet_pad_group_v2::Event::Strip { strip } => strip.data::<OnceLock<ObjectId>>(),
+            _ => None,
+        };
+        if let Some(child) = child {
+            let _ = child.set(pad_id.clone());
+        }
+        let Some(pad) = state.tablet.pads.get_mut(pad_id) else {
+            return;
+        };
+        match event {
+            zwp_tablet_pad_group_v2::Event::Ring { ring } => pad.rings.push(ring),
+            zwp_tablet_pad_group_v2::Event::Strip { strip } => pad.strips.push(strip),
+            _ => {}
+        }
+    }
+
+    event_created_child!(WindowState<T>, ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (ZwpTabletPadRingV2, OnceLock::<ObjectId>::new()),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (ZwpTabletPadStripV2, OnceLock::<ObjectId>::new()),
+    ]);
+}
+
+impl<T> Dispatch<ZwpTabletPadRingV2, OnceLock<ObjectId>> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTabletPadRingV2,
+        event: <ZwpTabletPadRingV2 as Proxy>::Event,
+        data: &OnceLock<ObjectId>,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let angle = match event {
+            zwp_tablet_pad_ring_v2::Event::Angle { degrees } => Some(degrees),
+            zwp_tablet_pad_ring_v2::Event::Stop => None,
+            _ => return,
+        };
+        if let Some((id, seat_object, seat)) = data.get().and_then(|pad| state.pad_focus(pad)) {
+            state.push_seat_message(
+                &seat_object,
+                (
+                    Some(id),
+                    DispatchMessageInner::TabletPadRing { angle, seat },
+                ),
+            );
+        }
+    }
+}
+
+impl<T> Dispatch<ZwpTabletPadStripV2, OnceLock<ObjectId>> for WindowState<T> {
+    fn event(
+        state: &mut Self,
+        _proxy: &ZwpTabletPadStripV2,
+        event: <ZwpTabletPadStripV2 as Proxy>::Event,
+        data: &OnceLock<ObjectId>,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let position = match event {
+            zwp_tablet_pad_strip_v2::Event::Position { position } => {
+                Some(position as f64 / AXIS_MAX)
+            }
+            zwp_tablet_pad_strip_v2::Event::Stop => None,
+            _ => return,
+        };
+        if let Some((id, seat_object, seat)) = data.get().and_then(|pad| state.pad_focus(pad)) {
+            state.push_seat_message(
+                &seat_object,
+                (
+                    Some(id),
+                    DispatchMessageInner::TabletPadStrip { position, seat },
+                ),
+            );
+        }
+    }
+}
+
+delegate_noop!(@<T> WindowState<T>: ignore ZwpTabletManagerV2);
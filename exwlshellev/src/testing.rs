@@ -0,0 +1,1340 @@
+//! In-process mock compositor, so [`WindowState`](crate::WindowState) can be driven without a
+//! running wlroots session.
+//!
+//! The mock advertises `wl_compositor`, `wl_shm`, `wl_seat`, `wl_output`, `xdg_wm_base`,
+//! `zwlr_layer_shell_v1` and `ext_session_lock_manager_v1`. Every shell surface gets its initial
+//! configure on the first commit, so a fresh [`WindowState`](crate::WindowState) reaches
+//! [`DispatchMessage::RequestRefresh`](crate::DispatchMessage::RequestRefresh) on its own. Anything
+//! else (input, output hotplug, lock replies) is scripted from the test.
+//!
+//! Shell surfaces are addressed by the order the client gave them a role in: the first layer
+//! surface created by [`WindowState::build`](crate::WindowState::build) is surface `0`.
+//!
+//! ```rust, no_run
+//! use exwlshellev::testing::MockCompositor;
+//! use exwlshellev::*;
+//!
+//! let mut mock = MockCompositor::new();
+//! let ev: WindowState<()> = WindowState::new("test")
+//!     .with_use_display_handle(true)
+//!     .with_connection(Some(mock.connection()))
+//!     .build()
+//!     .unwrap();
+//!
+//! ev.running(move |event, _, _| match event {
+//!     ExWlShellEvent::RequestMessages(DispatchMessage::RequestRefresh { .. }) => {
+//!         mock.pointer_enter(0, 10., 10.);
+//!         ReturnData::None
+//!     }
+//!     ExWlShellEvent::RequestMessages(DispatchMessage::MouseEnter { .. }) => {
+//!         ReturnData::RequestExit
+//!     }
+//!     _ => ReturnData::None,
+//! })
+//! .unwrap();
+//! ```
+
+use std::fs::File;
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::os::unix::net::UnixStream;
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use calloop::channel::{self, Channel, Sender};
+use calloop::generic::Generic;
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{EventLoop, Interest, LoopSignal, Mode, PostAction};
+use wayland_client::Connection;
+use wayland_protocols::ext::session_lock::v1::server::{
+    ext_session_lock_manager_v1::{self, ExtSessionLockManagerV1},
+    ext_session_lock_surface_v1::{self, ExtSessionLockSurfaceV1},
+    ext_session_lock_v1::{self, ExtSessionLockV1},
+};
+use wayland_protocols::xdg::shell::server::{
+    xdg_popup::{self, XdgPopup},
+    xdg_positioner::{self, XdgPositioner},
+    xdg_surface::{self, XdgSurface},
+    xdg_toplevel::{self, XdgToplevel},
+    xdg_wm_base::{self, XdgWmBase},
+};
+use wayland_protocols_wlr::layer_shell::v1::server::{
+    zwlr_layer_shell_v1::{self, ZwlrLayerShellV1},
+    zwlr_layer_surface_v1::{self, ZwlrLayerSurfaceV1},
+};
+use wayland_server::backend::{ClientData, ClientId, DisconnectReason, GlobalId};
+use wayland_server::protocol::{
+    wl_buffer::{self, WlBuffer},
+    wl_callback::WlCallback,
+    wl_compositor::{self, WlCompositor},
+    wl_keyboard::{self, WlKeyboard},
+    wl_output::{self, WlOutput},
+    wl_pointer::{self, WlPointer},
+    wl_region::{self, WlRegion},
+    wl_seat::{self, WlSeat},
+    wl_shm::{self, WlShm},
+    wl_shm_pool::{self, WlShmPool},
+    wl_surface::{self, WlSurface},
+    wl_touch::{self, WlTouch},
+};
+use wayland_server::{
+    Client, DataInit, Dispatch, Display, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::WithConnection;
+
+/// Size used for shell surfaces which are not bound to any output.
+const FALLBACK_OUTPUT_SIZE: (i32, i32) = (1920, 1080);
+
+/// The client is disconnected after this long, so a test waiting for an event that never comes
+/// fails instead of hanging.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+const KEYMAP: &str = r#"xkb_keymap {
+    xkb_keycodes { include "evdev+aliases(qwerty)" };
+    xkb_types { include "complete" };
+    xkb_compat { include "complete" };
+    xkb_symbols { include "pc+us+inet(evdev)" };
+};
+"#;
+
+/// An output advertised by the [`MockCompositor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockOutput {
+    pub name: String,
+    pub size: (i32, i32),
+    pub scale: i32,
+}
+
+impl MockOutput {
+    pub fn new(name: impl Into<String>, width: i32, height: i32) -> Self {
+        Self {
+            name: name.into(),
+            size: (width, height),
+            scale: 1,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: i32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+/// How the mock answers `ext_session_lock_manager_v1.lock`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockResponse {
+    /// send `locked` right away
+    #[default]
+    Grant,
+    /// send `finished` right away, as a compositor does when another client holds the lock
+    Deny,
+    /// reply nothing, the test answers with [`MockCompositor::grant_lock`] or
+    /// [`MockCompositor::finish_lock`]
+    Manual,
+}
+
+/// A request the mock received from the client, recorded in arrival order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockRequest {
+    LayerSurface {
+        surface: usize,
+        namespace: String,
+        output: Option<String>,
+    },
+    XdgToplevel {
+        surface: usize,
+    },
+    XdgPopup {
+        surface: usize,
+    },
+    AckConfigure {
+        surface: usize,
+        serial: u32,
+    },
+    Lock,
+    LockSurface {
+        surface: usize,
+        output: Option<String>,
+    },
+    UnlockAndDestroy,
+    LockDestroyed,
+    SurfaceDestroyed {
+        surface: usize,
+    },
+}
+
+type Command = Box<dyn FnOnce(&mut Server, &DisplayHandle) + Send>;
+
+/// Handle to a fake compositor running on its own thread.
+///
+/// Every scripting method is asynchronous: it queues the action and returns, the client sees the
+/// resulting events on its next dispatch. Dropping the handle shuts the compositor down.
+pub struct MockCompositor {
+    client_stream: Option<UnixStream>,
+    commands: Option<Sender<Command>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for MockCompositor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MockCompositor")
+    }
+}
+
+impl Default for MockCompositor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockCompositor {
+    /// start a compositor with a single 1920x1080 output named `MOCK-1`
+    pub fn new() -> Self {
+        Self::with_outputs(vec![MockOutput::new(
+            "MOCK-1",
+            FALLBACK_OUTPUT_SIZE.0,
+            FALLBACK_OUTPUT_SIZE.1,
+        )])
+    }
+
+    /// start a compositor advertising the given outputs
+    pub fn with_outputs(outputs: Vec<MockOutput>) -> Self {
+        let (client_stream, server_stream) =
+            UnixStream::pair().expect("Cannot create the mock compositor socket");
+        let (commands, channel) = channel::channel();
+        let thread = std::thread::Builder::new()
+            .name("mock-compositor".to_owned())
+            .spawn(move || run_server(server_stream, outputs, channel))
+            .expect("Cannot spawn the mock compositor thread");
+        Self {
+            client_stream: Some(client_stream),
+            commands: Some(commands),
+            thread: Some(thread),
+        }
+    }
+
+    /// The client side of the socket, for [`WindowState::with_connection`](crate::WindowState::with_connection).
+    /// The mock serves a single client, so this can only be taken once.
+    pub fn connection(&mut self) -> WithConnection {
+        let stream = self
+            .client_stream
+            .take()
+            .expect("the mock compositor connection was already taken");
+        WithConnection::from(move || Connection::from_socket(stream))
+    }
+
+    fn send(&self, command: impl FnOnce(&mut Server, &DisplayHandle) + Send + 'static) {
+        if let Some(commands) = &self.commands {
+            // the server only goes away on drop or after the client timed out
+            let _ = commands.send(Box::new(command));
+        }
+    }
+
+    /// all requests received so far. Requests the client already flushed are processed before
+    /// the log is read.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        let (sender, receiver) = mpsc::channel();
+        self.send(move |server, _| {
+            let _ = sender.send(server.requests.clone());
+        });
+        receiver.recv().unwrap_or_default()
+    }
+
+    /// send a configure with the given size to a shell surface
+    pub fn configure(&self, surface: usize, width: i32, height: i32) {
+        self.send(move |server, _| server.configure(surface, width, height));
+    }
+
+    /// ask the client to close a shell surface: `closed` for layer surfaces, `close` for
+    /// toplevels and `popup_done` for popups
+    pub fn close(&self, surface: usize) {
+        self.send(move |server, _| {
+            let Some(entry) = server.surfaces.get(surface) else {
+                return;
+            };
+            match &entry.role {
+                Role::Layer(layer) => layer.closed(),
+                Role::Toplevel(_, toplevel) => toplevel.close(),
+                Role::Popup(_, popup) => popup.popup_done(),
+                Role::Lock(_) => {}
+            }
+        });
+    }
+
+    pub fn add_output(&self, output: MockOutput) {
+        self.send(move |server, handle| server.add_output(handle, output));
+    }
+
+    pub fn remove_output(&self, name: impl Into<String>) {
+        let name = name.into();
+        self.send(move |server, handle| {
+            let Some(index) = server.outputs.iter().position(|o| o.spec.name == name) else {
+                return;
+            };
+            let output = server.outputs.remove(index);
+            handle.remove_global::<Server>(output.global);
+        });
+    }
+
+    pub fn pointer_enter(&self, surface: usize, x: f64, y: f64) {
+        self.send(move |server, _| {
+            let Some(wl_surface) = server.surface(surface) else {
+                return;
+            };
+            let serial = server.next_serial();
+            for pointer in &server.pointers {
+                pointer.enter(serial, &wl_surface, x, y);
+                pointer_frame(pointer);
+            }
+            server.pointer_focus = Some(wl_surface);
+        });
+    }
+
+    pub fn pointer_motion(&self, x: f64, y: f64) {
+        self.send(move |server, _| {
+            let time = server.time();
+            for pointer in &server.pointers {
+                pointer.motion(time, x, y);
+                pointer_frame(pointer);
+            }
+        });
+    }
+
+    /// `button` is a linux input event code, like `BTN_LEFT` (0x110)
+    pub fn pointer_button(&self, button: u32, pressed: bool) {
+        self.send(move |server, _| {
+            let serial = server.next_serial();
+            let time = server.time();
+            let state = if pressed {
+                wl_pointer::ButtonState::Pressed
+            } else {
+                wl_pointer::ButtonState::Released
+            };
+            for pointer in &server.pointers {
+                pointer.button(serial, time, button, state);
+                pointer_frame(pointer);
+            }
+        });
+    }
+
+    /// scroll the vertical wheel by `value120` fractions of 120 per step, as one frame
+    pub fn pointer_axis(&self, value120: i32, inverted: bool) {
+        self.send(move |server, _| {
+            let time = server.time();
+            let axis = wl_pointer::Axis::VerticalScroll;
+            let direction = if inverted {
+                wl_pointer::AxisRelativeDirection::Inverted
+            } else {
+                wl_pointer::AxisRelativeDirection::Identical
+            };
+            for pointer in &server.pointers {
+                if pointer.version() >= 5 {
+                    pointer.axis_source(wl_pointer::AxisSource::Wheel);
+                }
+                if pointer.version() >= 8 {
+                    pointer.axis_value120(axis, value120);
+                }
+                if pointer.version() >= 9 {
+                    pointer.axis_relative_direction(axis, direction);
+                }
+                // 15 pixels per step, like libinput
+                pointer.axis(time, axis, f64::from(value120) / 8.);
+                pointer_frame(pointer);
+            }
+        });
+    }
+
+    pub fn pointer_leave(&self) {
+        self.send(move |server, _| {
+            let Some(wl_surface) = server.pointer_focus.take() else {
+                return;
+            };
+            let serial = server.next_serial();
+            for pointer in &server.pointers {
+                pointer.leave(serial, &wl_surface);
+                pointer_frame(pointer);
+            }
+        });
+    }
+
+    pub fn keyboard_enter(&self, surface: usize) {
+        self.send(move |server, _| {
+            let Some(wl_surface) = server.surface(surface) else {
+                return;
+            };
+            let serial = server.next_serial();
+            for keyboard in &server.keyboards {
+                keyboard.enter(serial, &wl_surface, Vec::new());
+            }
+            server.keyboard_focus = Some(wl_surface);
+        });
+    }
+
+    /// `key` is a linux input event code, like `KEY_ESC` (1)
+    pub fn keyboard_key(&self, key: u32, pressed: bool) {
+        self.send(move |server, _| {
+            let serial = server.next_serial();
+            let time = server.time();
+            let state = if pressed {
+                wl_keyboard::KeyState::Pressed
+            } else {
+                wl_keyboard::KeyState::Released
+            };
+            for keyboard in &server.keyboards {
+                keyboard.key(serial, time, key, state);
+            }
+        });
+    }
+
+    pub fn keyboard_modifiers(&self, depressed: u32, latched: u32, locked: u32, group: u32) {
+        self.send(move |server, _| {
+            let serial = server.next_serial();
+            for keyboard in &server.keyboards {
+                keyboard.modifiers(serial, depressed, latched, locked, group);
+            }
+        });
+    }
+
+    pub fn keyboard_leave(&self) {
+        self.send(move |server, _| {
+            let Some(wl_surface) = server.keyboard_focus.take() else {
+                return;
+            };
+            let serial = server.next_serial();
+            for keyboard in &server.keyboards {
+                keyboard.leave(serial, &wl_surface);
+            }
+        });
+    }
+
+    /// change how later lock requests are answered
+    pub fn set_lock_response(&self, response: LockResponse) {
+        self.send(move |server, _| server.lock_response = response);
+    }
+
+    /// send `locked` to every lock which has not been answered yet
+    pub fn grant_lock(&self) {
+        self.send(move |server, _| {
+            for lock in server.pending_locks.drain(..) {
+                lock.locked();
+                server.locks.push(lock);
+            }
+        });
+    }
+
+    /// send `finished` to every live lock, pending or granted
+    pub fn finish_lock(&self) {
+        self.send(move |server, _| {
+            for lock in server.pending_locks.drain(..).chain(server.locks.drain(..)) {
+                lock.finished();
+            }
+        });
+    }
+}
+
+impl Drop for MockCompositor {
+    fn drop(&mut self) {
+        // closing the channel stops the server loop
+        self.commands.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct MockClient;
+
+impl ClientData for MockClient {
+    fn initialized(&self, _client_id: ClientId) {}
+    fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {}
+}
+
+struct OutputEntry {
+    spec: MockOutput,
+    global: GlobalId,
+    resources: Vec<WlOutput>,
+}
+
+enum Role {
+    Layer(ZwlrLayerSurfaceV1),
+    Lock(ExtSessionLockSurfaceV1),
+    Toplevel(XdgSurface, XdgToplevel),
+    Popup(XdgSurface, XdgPopup),
+}
+
+struct SurfaceEntry {
+    wl_surface: WlSurface,
+    role: Role,
+    output: Option<usize>,
+    requested_size: (i32, i32),
+    configured: bool,
+}
+
+struct Server {
+    start: Instant,
+    serial: u32,
+    keymap: File,
+    outputs: Vec<OutputEntry>,
+    surfaces: Vec<SurfaceEntry>,
+    frame_callbacks: Vec<(WlSurface, WlCallback)>,
+    pointers: Vec<WlPointer>,
+    keyboards: Vec<WlKeyboard>,
+    pointer_focus: Option<WlSurface>,
+    keyboard_focus: Option<WlSurface>,
+    lock_response: LockResponse,
+    pending_locks: Vec<ExtSessionLockV1>,
+    locks: Vec<ExtSessionLockV1>,
+    requests: Vec<MockRequest>,
+}
+
+impl Server {
+    fn next_serial(&mut self) -> u32 {
+        self.serial = self.serial.wrapping_add(1);
+        self.serial
+    }
+
+    fn time(&self) -> u32 {
+        self.start.elapsed().as_millis() as u32
+    }
+
+    fn surface(&self, surface: usize) -> Option<WlSurface> {
+        self.surfaces
+            .get(surface)
+            .map(|entry| entry.wl_surface.clone())
+    }
+
+    fn add_output(&mut self, handle: &DisplayHandle, spec: MockOutput) {
+        let global = handle.create_global::<Server, WlOutput, String>(4, spec.name.clone());
+        self.outputs.push(OutputEntry {
+            spec,
+            global,
+            resources: Vec::new(),
+        });
+    }
+
+    fn output_of(&self, output: &WlOutput) -> Option<usize> {
+        self.outputs
+            .iter()
+            .position(|entry| entry.resources.contains(output))
+    }
+
+    fn output_name(&self, output: Option<usize>) -> Option<String> {
+        output.map(|index| self.outputs[index].spec.name.clone())
+    }
+
+    fn push_surface(&mut self, wl_surface: &WlSurface, role: Role, output: Option<usize>) -> usize {
+        self.surfaces.push(SurfaceEntry {
+            wl_surface: wl_surface.clone(),
+            role,
+            output,
+            requested_size: (0, 0),
+            configured: false,
+        });
+        self.surfaces.len() - 1
+    }
+
+    fn index_of(&self, matches: impl Fn(&Role) -> bool) -> Option<usize> {
+        self.surfaces.iter().position(|entry| matches(&entry.role))
+    }
+
+    fn configure(&mut self, surface: usize, width: i32, height: i32) {
+        let serial = self.next_serial();
+        let Some(entry) = self.surfaces.get_mut(surface) else {
+            return;
+        };
+        entry.configured = true;
+        match &entry.role {
+            Role::Layer(layer) => layer.configure(serial, width as u32, height as u32),
+            Role::Lock(lock_surface) => lock_surface.configure(serial, width as u32, height as u32),
+            Role::Toplevel(xdg_surface, toplevel) => {
+                toplevel.configure(width, height, Vec::new());
+                xdg_surface.configure(serial);
+            }
+            Role::Popup(xdg_surface, popup) => {
+                popup.configure(0, 0, width, height);
+                xdg_surface.configure(serial);
+            }
+        }
+    }
+
+    fn commit(&mut self, wl_surface: &WlSurface) {
+        if let Some(index) = self
+            .surfaces
+            .iter()
+            .position(|entry| entry.wl_surface == *wl_surface && !entry.configured)
+        {
+            let entry = &self.surfaces[index];
+            let output_size = entry
+                .output
+                .or(if self.outputs.is_empty() {
+                    None
+                } else {
+                    Some(0)
+                })
+                .map(|output| self.outputs[output].spec.size)
+                .unwrap_or(FALLBACK_OUTPUT_SIZE);
+            let (width, height) = match entry.role {
+                Role::Layer(_) => {
+                    let (width, height) = entry.requested_size;
+                    (
+                        if width == 0 { output_size.0 } else { width },
+                        if height == 0 { output_size.1 } else { height },
+                    )
+                }
+                Role::Lock(_) => output_size,
+                Role::Toplevel(..) => (0, 0),
+                Role::Popup(..) => entry.requested_size,
+            };
+            if let Some(output) = entry.output
+                && let Some(resource) = self.outputs[output].resources.first()
+            {
+                entry.wl_surface.enter(resource);
+            }
+            self.configure(index, width, height);
+        }
+
+        let time = self.time();
+        self.frame_callbacks.retain(|(surface, callback)| {
+            if surface != wl_surface {
+                return true;
+            }
+            callback.done(time);
+            false
+        });
+    }
+
+    fn respond_to_lock(&mut self, lock: ExtSessionLockV1) {
+        match self.lock_response {
+            LockResponse::Grant => {
+                lock.locked();
+                self.locks.push(lock);
+            }
+            LockResponse::Deny => lock.finished(),
+            LockResponse::Manual => self.pending_locks.push(lock),
+        }
+    }
+}
+
+struct ServerLoop {
+    display: Display<Server>,
+    server: Server,
+    client: Option<ClientId>,
+    signal: LoopSignal,
+}
+
+impl ServerLoop {
+    fn dispatch(&mut self) {
+        if let Err(err) = self.display.dispatch_clients(&mut self.server) {
+            log::warn!(target: "exwlshellev", "mock compositor failed to dispatch: {err}");
+        }
+        let _ = self.display.flush_clients();
+    }
+}
+
+fn pointer_frame(pointer: &WlPointer) {
+    if pointer.version() >= 5 {
+        pointer.frame();
+    }
+}
+
+fn run_server(stream: UnixStream, outputs: Vec<MockOutput>, commands: Channel<Command>) {
+    let mut display: Display<Server> =
+        Display::new().expect("Cannot create the mock compositor display");
+    let mut handle = display.handle();
+
+    handle.create_global::<Server, WlCompositor, ()>(5, ());
+    handle.create_global::<Server, WlShm, ()>(1, ());
+    handle.create_global::<Server, WlSeat, ()>(9, ());
+    handle.create_global::<Server, XdgWmBase, ()>(6, ());
+    handle.create_global::<Server, ZwlrLayerShellV1, ()>(4, ());
+    handle.create_global::<Server, ExtSessionLockManagerV1, ()>(1, ());
+
+    let mut keymap = tempfile::tempfile().expect("Cannot create the mock keymap file");
+    keymap
+        .write_all(KEYMAP.as_bytes())
+        .and_then(|_| keymap.write_all(&[0]))
+        .expect("Cannot write the mock keymap");
+
+    let mut server = Server {
+        start: Instant::now(),
+        serial: 0,
+        keymap,
+        outputs: Vec::new(),
+        surfaces: Vec::new(),
+        frame_callbacks: Vec::new(),
+        pointers: Vec::new(),
+        keyboards: Vec::new(),
+        pointer_focus: None,
+        keyboard_focus: None,
+        lock_response: LockResponse::default(),
+        pending_locks: Vec::new(),
+        locks: Vec::new(),
+        requests: Vec::new(),
+    };
+    for output in outputs {
+        server.add_output(&handle, output);
+    }
+
+    let client = handle
+        .insert_client(stream, Arc::new(MockClient))
+        .expect("Cannot insert the mock client")
+        .id();
+
+    let mut event_loop: EventLoop<ServerLoop> =
+        EventLoop::try_new().expect("Failed to initialize the mock compositor event loop");
+    let poll_fd = display
+        .backend()
+        .poll_fd()
+        .try_clone_to_owned()
+        .expect("Cannot clone the mock compositor poll fd");
+    let loop_handle = event_loop.handle();
+    loop_handle
+        .insert_source(
+            Generic::new(poll_fd, Interest::READ, Mode::Level),
+            |_, _, state| {
+                state.dispatch();
+                Ok(PostAction::Continue)
+            },
+        )
+        .expect("Cannot watch the mock compositor socket");
+    loop_handle
+        .insert_source(commands, |event, _, state| match event {
+            channel::Event::Msg(command) => {
+                // handle whatever the client sent before the command was queued first
+                state.dispatch();
+                let handle = state.display.handle();
+                command(&mut state.server, &handle);
+                let _ = state.display.flush_clients();
+            }
+            channel::Event::Closed => state.signal.stop(),
+        })
+        .expect("Cannot listen for mock compositor commands");
+    loop_handle
+        .insert_source(Timer::from_duration(CLIENT_TIMEOUT), |_, _, state| {
+            if let Some(client) = state.client.take() {
+                log::error!(target: "exwlshellev", "mock compositor timed out, disconnecting the client");
+                state
+                    .display
+                    .handle()
+                    .backend_handle()
+                    .kill_client(client, DisconnectReason::ConnectionClosed);
+                // the socket is only closed once the killed client is cleaned up
+                state.dispatch();
+            }
+            TimeoutAction::Drop
+        })
+        .expect("Cannot arm the mock compositor timeout");
+
+    let mut state = ServerLoop {
+        display,
+        server,
+        client: Some(client),
+        signal: event_loop.get_signal(),
+    };
+    let _ = event_loop.run(None, &mut state, |state| {
+        let _ = state.display.flush_clients();
+    });
+}
+
+impl GlobalDispatch<WlCompositor, ()> for Server {
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<WlCompositor>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl Dispatch<WlCompositor, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &WlCompositor,
+        request: wl_compositor::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            wl_compositor::Request::CreateSurface { id } => {
+                data_init.init(id, ());
+            }
+            wl_compositor::Request::CreateRegion { id } => {
+                data_init.init(id, ());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlSurface, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &WlSurface,
+        request: wl_surface::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            wl_surface::Request::Frame { callback } => {
+                let callback = data_init.init(callback, ());
+                state.frame_callbacks.push((resource.clone(), callback));
+            }
+            wl_surface::Request::Commit => state.commit(resource),
+            _ => {}
+        }
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: &WlSurface, _data: &()) {
+        state
+            .frame_callbacks
+            .retain(|(surface, _)| surface != resource);
+        if let Some(surface) = state
+            .surfaces
+            .iter()
+            .position(|entry| entry.wl_surface == *resource)
+        {
+            state
+                .requests
+                .push(MockRequest::SurfaceDestroyed { surface });
+        }
+    }
+}
+
+impl Dispatch<WlRegion, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &WlRegion,
+        _request: wl_region::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlCallback, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &WlCallback,
+        _request: <WlCallback as Resource>::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<WlShm, ()> for Server {
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<WlShm>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let shm = data_init.init(resource, ());
+        shm.format(wl_shm::Format::Argb8888);
+        shm.format(wl_shm::Format::Xrgb8888);
+    }
+}
+
+impl Dispatch<WlShm, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &WlShm,
+        request: wl_shm::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_shm::Request::CreatePool { id, .. } = request {
+            data_init.init(id, ());
+        }
+    }
+}
+
+impl Dispatch<WlShmPool, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &WlShmPool,
+        request: wl_shm_pool::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_shm_pool::Request::CreateBuffer { id, .. } = request {
+            data_init.init(id, ());
+        }
+    }
+}
+
+impl Dispatch<WlBuffer, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &WlBuffer,
+        _request: wl_buffer::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<WlOutput, String> for Server {
+    fn bind(
+        state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<WlOutput>,
+        global_name: &String,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let output = data_init.init(resource, ());
+        let Some(entry) = state
+            .outputs
+            .iter_mut()
+            .find(|entry| entry.spec.name == *global_name)
+        else {
+            return;
+        };
+        let MockOutput { name, size, scale } = &entry.spec;
+        output.geometry(
+            0,
+            0,
+            0,
+            0,
+            wl_output::Subpixel::Unknown,
+            "mock".to_owned(),
+            name.clone(),
+            wl_output::Transform::Normal,
+        );
+        output.mode(wl_output::Mode::Current, size.0, size.1, 60_000);
+        if output.version() >= 2 {
+            output.scale(*scale);
+        }
+        if output.version() >= 4 {
+            output.name(name.clone());
+            output.description(format!("mock output {name}"));
+        }
+        if output.version() >= 2 {
+            output.done();
+        }
+        entry.resources.push(output);
+    }
+}
+
+impl Dispatch<WlOutput, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &WlOutput,
+        _request: wl_output::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: &WlOutput, _data: &()) {
+        for entry in &mut state.outputs {
+            entry.resources.retain(|output| output != resource);
+        }
+    }
+}
+
+impl GlobalDispatch<WlSeat, ()> for Server {
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<WlSeat>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let seat = data_init.init(resource, ());
+        seat.capabilities(wl_seat::Capability::Pointer | wl_seat::Capability::Keyboard);
+        if seat.version() >= 2 {
+            seat.name("seat0".to_owned());
+        }
+    }
+}
+
+impl Dispatch<WlSeat, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &WlSeat,
+        request: wl_seat::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            wl_seat::Request::GetPointer { id } => {
+                let pointer = data_init.init(id, ());
+                state.pointers.push(pointer);
+            }
+            wl_seat::Request::GetKeyboard { id } => {
+                let keyboard = data_init.init(id, ());
+                let size = KEYMAP.len() as u32 + 1;
+                keyboard.keymap(wl_keyboard::KeymapFormat::XkbV1, state.keymap.as_fd(), size);
+                if keyboard.version() >= 4 {
+                    // no repeat, so a held key never produces extra events in a test
+                    keyboard.repeat_info(0, 0);
+                }
+                state.keyboards.push(keyboard);
+            }
+            wl_seat::Request::GetTouch { id } => {
+                data_init.init(id, ());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlPointer, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &WlPointer,
+        _request: wl_pointer::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: &WlPointer, _data: &()) {
+        state.pointers.retain(|pointer| pointer != resource);
+    }
+}
+
+impl Dispatch<WlKeyboard, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &WlKeyboard,
+        _request: wl_keyboard::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: &WlKeyboard, _data: &()) {
+        state.keyboards.retain(|keyboard| keyboard != resource);
+    }
+}
+
+impl Dispatch<WlTouch, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &WlTouch,
+        _request: wl_touch::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<XdgWmBase, ()> for Server {
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<XdgWmBase>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl Dispatch<XdgWmBase, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &XdgWmBase,
+        request: xdg_wm_base::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            xdg_wm_base::Request::CreatePositioner { id } => {
+                data_init.init(id, Mutex::new((0, 0)));
+            }
+            xdg_wm_base::Request::GetXdgSurface { id, surface } => {
+                data_init.init(id, surface);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<XdgPositioner, Mutex<(i32, i32)>> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &XdgPositioner,
+        request: xdg_positioner::Request,
+        data: &Mutex<(i32, i32)>,
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let xdg_positioner::Request::SetSize { width, height } = request {
+            *data.lock().unwrap() = (width, height);
+        }
+    }
+}
+
+impl Dispatch<XdgSurface, WlSurface> for Server {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &XdgSurface,
+        request: xdg_surface::Request,
+        wl_surface: &WlSurface,
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            xdg_surface::Request::GetToplevel { id } => {
+                let toplevel = data_init.init(id, ());
+                let surface =
+                    state.push_surface(wl_surface, Role::Toplevel(resource.clone(), toplevel), None);
+                state.requests.push(MockRequest::XdgToplevel { surface });
+            }
+            xdg_surface::Request::GetPopup { id, positioner, .. } => {
+                let popup = data_init.init(id, ());
+                let size = *positioner
+                    .data::<Mutex<(i32, i32)>>()
+                    .expect("positioner created by the mock")
+                    .lock()
+                    .unwrap();
+                let surface =
+                    state.push_surface(wl_surface, Role::Popup(resource.clone(), popup), None);
+                state.surfaces[surface].requested_size = size;
+                state.requests.push(MockRequest::XdgPopup { surface });
+            }
+            xdg_surface::Request::AckConfigure { serial } => {
+                if let Some(surface) = state.index_of(|role| {
+                    matches!(role, Role::Toplevel(xdg, _) | Role::Popup(xdg, _) if xdg == resource)
+                }) {
+                    state
+                        .requests
+                        .push(MockRequest::AckConfigure { surface, serial });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<XdgToplevel, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &XdgToplevel,
+        _request: xdg_toplevel::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl Dispatch<XdgPopup, ()> for Server {
+    fn request(
+        _state: &mut Self,
+        _client: &Client,
+        _resource: &XdgPopup,
+        _request: xdg_popup::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<ZwlrLayerShellV1, ()> for Server {
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrLayerShellV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl Dispatch<ZwlrLayerShellV1, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &ZwlrLayerShellV1,
+        request: zwlr_layer_shell_v1::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let zwlr_layer_shell_v1::Request::GetLayerSurface {
+            id,
+            surface: wl_surface,
+            output,
+            namespace,
+            ..
+        } = request
+        {
+            let layer = data_init.init(id, ());
+            let output = output.and_then(|output| state.output_of(&output));
+            let surface = state.push_surface(&wl_surface, Role::Layer(layer), output);
+            state.requests.push(MockRequest::LayerSurface {
+                surface,
+                namespace,
+                output: state.output_name(output),
+            });
+        }
+    }
+}
+
+impl Dispatch<ZwlrLayerSurfaceV1, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ZwlrLayerSurfaceV1,
+        request: zwlr_layer_surface_v1::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        let Some(surface) =
+            state.index_of(|role| matches!(role, Role::Layer(layer) if layer == resource))
+        else {
+            return;
+        };
+        match request {
+            zwlr_layer_surface_v1::Request::SetSize { width, height } => {
+                state.surfaces[surface].requested_size = (width as i32, height as i32);
+            }
+            zwlr_layer_surface_v1::Request::AckConfigure { serial } => {
+                state
+                    .requests
+                    .push(MockRequest::AckConfigure { surface, serial });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl GlobalDispatch<ExtSessionLockManagerV1, ()> for Server {
+    fn bind(
+        _state: &mut Self,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtSessionLockManagerV1>,
+        _global_data: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl Dispatch<ExtSessionLockManagerV1, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &ExtSessionLockManagerV1,
+        request: ext_session_lock_manager_v1::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let ext_session_lock_manager_v1::Request::Lock { id } = request {
+            let lock = data_init.init(id, ());
+            state.requests.push(MockRequest::Lock);
+            state.respond_to_lock(lock);
+        }
+    }
+}
+
+impl Dispatch<ExtSessionLockV1, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        _resource: &ExtSessionLockV1,
+        request: ext_session_lock_v1::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        match request {
+            ext_session_lock_v1::Request::GetLockSurface {
+                id,
+                surface: wl_surface,
+                output,
+            } => {
+                let lock_surface = data_init.init(id, ());
+                let output = state.output_of(&output);
+                let surface = state.push_surface(&wl_surface, Role::Lock(lock_surface), output);
+                state.requests.push(MockRequest::LockSurface {
+                    surface,
+                    output: state.output_name(output),
+                });
+            }
+            ext_session_lock_v1::Request::UnlockAndDestroy => {
+                state.requests.push(MockRequest::UnlockAndDestroy);
+            }
+            ext_session_lock_v1::Request::Destroy => {
+                state.requests.push(MockRequest::LockDestroyed);
+            }
+            _ => {}
+        }
+    }
+
+    fn destroyed(state: &mut Self, _client: ClientId, resource: &ExtSessionLockV1, _data: &()) {
+        state.pending_locks.retain(|lock| lock != resource);
+        state.locks.retain(|lock| lock != resource);
+    }
+}
+
+impl Dispatch<ExtSessionLockSurfaceV1, ()> for Server {
+    fn request(
+        state: &mut Self,
+        _client: &Client,
+        resource: &ExtSessionLockSurfaceV1,
+        request: ext_session_lock_surface_v1::Request,
+        _data: &(),
+        _dhandle: &DisplayHandle,
+        _data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let ext_session_lock_surface_v1::Request::AckConfigure { serial } = request
+            && let Some(surface) = state.index_of(
+                |role| matches!(role, Role::Lock(lock_surface) if lock_surface == resource),
+            )
+        {
+            state
+                .requests
+                .push(MockRequest::AckConfigure { surface, serial });
+        }
+    }
+}
@@ -0,0 +1,314 @@
+//! Typing text through virtual-keyboard-unstable-v1
+//!
+//! [`WindowState::virtual_keyboard_type`] maps text to keysyms. Keys of the keymap uploaded with
+//! [`WindowState::set_virtual_keyboard_keymap`] are used when it has all of them, on any shift
+//! level, otherwise a temporary keymap holding exactly the typed keysyms is uploaded for the
+//! text and the application keymap restored afterwards, the way `wtype` does.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::os::fd::AsFd;
+use std::time::Instant;
+
+use wayland_client::protocol::wl_keyboard::{KeyState, KeymapFormat};
+use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1;
+
+use crate::xkb_keyboard::{KeyLevel, XkbContext, XkbKeymap};
+use crate::{SeatId, WindowState};
+
+/// Modifier masks of keymaps including the `complete` compatibility, like the ones of
+/// xkeyboard-config, for [`WindowState::set_virtual_keyboard_modifiers`]
+pub mod modifier {
+    pub const SHIFT: u32 = 1;
+    pub const CAPS_LOCK: u32 = 1 << 1;
+    pub const CONTROL: u32 = 1 << 2;
+    pub const ALT: u32 = 1 << 3;
+    pub const NUM_LOCK: u32 = 1 << 4;
+    pub const LOGO: u32 = 1 << 6;
+}
+
+/// wayland keycodes are evdev ones, xkb ones are offset by 8
+const EVDEV_OFFSET: u32 = 8;
+
+#[derive(Debug)]
+struct Keymap {
+    format: KeymapFormat,
+    file: File,
+    size: u32,
+    /// the key typing each keysym of the first layout, empty if the keymap cannot be parsed
+    keys: HashMap<u32, KeyLevel>,
+}
+
+#[derive(Debug)]
+pub(crate) struct VirtualKeyboardState {
+    /// the keymap of the application, restored after typing through a temporary one
+    keymap: Option<Keymap>,
+    /// depressed, latched and locked modifiers
+    modifiers: (u32, u32, u32),
+    /// reference of the millisecond timestamps of the key events
+    start: Instant,
+}
+
+impl Default for VirtualKeyboardState {
+    fn default() -> Self {
+        Self {
+            keymap: None,
+            modifiers: (0, 0, 0),
+            start: Instant::now(),
+        }
+    }
+}
+
+/// The keysym typing `c` produces
+pub fn keysym_from_char(c: char) -> u32 {
+    match c {
+        '\n' | '\r' => 0xff0d,
+        '\t' => 0xff09,
+        '\u{8}' => 0xff08,
+        '\u{1b}' => 0xff1b,
+        '\u{7f}' => 0xffff,
+        ' '..='~' | '\u{a0}'..='\u{ff}' => c as u32,
+        _ => 0x0100_0000 | c as u32,
+    }
+}
+
+fn keysym_name(keysym: u32) -> String {
+    match keysym {
+        0xff0d => "Return".to_owned(),
+        0xff09 => "Tab".to_owned(),
+        0xff08 => "BackSpace".to_owned(),
+        0xff1b => "Escape".to_owned(),
+        0xffff => "Delete".to_owned(),
+        0x20..=0xff => format!("U{keysym:04X}"),
+        _ if keysym & 0xff00_0000 == 0x0100_0000 => format!("U{:04X}", keysym & 0x00ff_ffff),
+        _ => format!("{keysym:#x}"),
+    }
+}
+
+/// A keymap with one key per keysym, the key of `keysyms[i]` has the evdev keycode `i + 1`
+fn typing_keymap(keysyms: &[u32]) -> String {
+    let mut keycodes = String::new();
+    let mut symbols = String::new();
+    for (index, keysym) in keysyms.iter().enumerate() {
+        let key = index as u32 + 1;
+        keycodes += &format!("    <K{key}> = {};\n", key + EVDEV_OFFSET);
+        symbols += &format!("    key <K{key}> {{ [ {} ] }};\n", keysym_name(*keysym));
+    }
+    format!(
+        "xkb_keymap {{\n\
+         xkb_keycodes \"(unnamed)\" {{\n    minimum = {};\n    maximum = {};\n{keycodes}}};\n\
+         xkb_types \"(unnamed)\" {{ include \"complete\" }};\n\
+         xkb_compatibility \"(unnamed)\" {{ include \"complete\" }};\n\
+         xkb_symbols \"(unnamed)\" {{\n{symbols}}};\n\
+         }};\n",
+        EVDEV_OFFSET,
+        keysyms.len() as u32 + EVDEV_OFFSET + 1,
+    )
+}
+
+impl Keymap {
+    fn new(format: KeymapFormat, file: File, size: u32) -> Self {
+        let keys = if format == KeymapFormat::XkbV1 {
+            Self::parse_keys(&file, size).unwrap_or_default()
+        } else {
+            HashMap::new()
+        };
+        Self {
+            format,
+            file,
+            size,
+            keys,
+        }
+    }
+
+    fn parse_keys(file: &File, size: u32) -> Option<HashMap<u32, KeyLevel>> {
+        let context = XkbContext::new().ok()?;
+        let fd = file.try_clone().ok()?.into();
+        let mut keymap = XkbKeymap::from_fd(&context, fd, size as usize)?;
+        Some(keymap.keysym_levels(0))
+    }
+
+    /// Keys typing `keysyms`, with evdev keycodes, if the keymap has all of them
+    fn keys(&self, keysyms: &[u32]) -> Option<Vec<KeyLevel>> {
+        keysyms
+            .iter()
+            .map(|keysym| {
+                let key = self.keys.get(keysym)?;
+                Some(KeyLevel {
+                    keycode: key.keycode - EVDEV_OFFSET,
+                    ..*key
+                })
+            })
+            .collect()
+    }
+
+    fn upload(&self, keyboard: &ZwpVirtualKeyboardV1) {
+        keyboard.keymap(self.format.into(), self.file.as_fd(), self.size);
+    }
+}
+
+impl<T: 'static> WindowState<T> {
+    /// The saved virtual keyboard, or a new one of the seat of the latest input if none was
+    /// saved
+    fn ensure_virtual_keyboard(&mut self) -> Option<ZwpVirtualKeyboardV1> {
+        if self.virtual_keyboard.is_none() {
+            let manager = self.virtual_keyboard_manager.as_ref()?;
+            let seat = &self.current_seat()?.seat;
+            let qh = self.queue_handle.as_ref()?;
+            self.virtual_keyboard = Some(manager.create_virtual_keyboard(seat, qh, ()));
+        }
+        self.virtual_keyboard.clone()
+    }
+
+    /// Type on `seat` from now on, replacing the virtual keyboard. The keymap uploaded with
+    /// [`WindowState::set_virtual_keyboard_keymap`] is uploaded again. Without it, the virtual
+    /// keyboard is created on the seat of the latest input.
+    ///
+    /// Returns `None` if the compositor does not support virtual-keyboard-unstable-v1 or there
+    /// is no such seat.
+    pub fn set_virtual_keyboard_seat(&mut self, seat: SeatId) -> Option<()> {
+        let manager = self.virtual_keyboard_manager.as_ref()?;
+        let seat = &self.seat_storage(seat)?.seat;
+        let qh = self.queue_handle.as_ref()?;
+        let keyboard = manager.create_virtual_keyboard(seat, qh, ());
+        if let Some(keymap) = &self.virtual_keyboard_state.keymap {
+            keymap.upload(&keyboard);
+        }
+        if let Some(old) = self.virtual_keyboard.replace(keyboard) {
+            old.destroy();
+        }
+        Some(())
+    }
+
+    fn virtual_keyboard_time(&self) -> u32 {
+        self.virtual_keyboard_state.start.elapsed().as_millis() as u32
+    }
+
+    /// Upload the keymap of the virtual keyboard. Text typed with
+    /// [`WindowState::virtual_keyboard_type`] uses its keys when possible, and it is restored
+    /// after a temporary keymap was needed.
+    ///
+    /// Returns `None` if the compositor does not support virtual-keyboard-unstable-v1.
+    pub fn set_virtual_keyboard_keymap(
+        &mut self,
+        format: KeymapFormat,
+        file: File,
+        size: u32,
+    ) -> Option<()> {
+        let keyboard = self.ensure_virtual_keyboard()?;
+        let keymap = Keymap::new(format, file, size);
+        keymap.upload(&keyboard);
+        self.virtual_keyboard_state.keymap = Some(keymap);
+        Some(())
+    }
+
+    /// Press or release the key with the evdev `key` code of the current keymap.
+    ///
+    /// Returns `None` if there is no virtual keyboard.
+    pub fn virtual_keyboard_key(&mut self, key: u32, state: KeyState) -> Option<()> {
+        let keyboard = self.ensure_virtual_keyboard()?;
+        keyboard.key(self.virtual_keyboard_time(), key, state.into());
+        Some(())
+    }
+
+    /// Set the modifiers of the virtual keyboard, masks as in [`modifier`]. Latched modifiers
+    /// apply to the next key only.
+    ///
+    /// Returns `None` if there is no virtual keyboard.
+    pub fn set_virtual_keyboard_modifiers(
+        &mut self,
+        depressed: u32,
+        latched: u32,
+        locked: u32,
+    ) -> Option<()> {
+        let keyboard = self.ensure_virtual_keyboard()?;
+        keyboard.modifiers(depressed, latched, locked, 0);
+        self.virtual_keyboard_state.modifiers = (depressed, latched, locked);
+        Some(())
+    }
+
+    /// Type `text` on the virtual keyboard.
+    ///
+    /// Returns `None` if there is no virtual keyboard or the temporary keymap cannot be written.
+    pub fn virtual_keyboard_type(&mut self, text: &str) -> Option<()> {
+        let keyboard = self.ensure_virtual_keyboard()?;
+        let keysyms: Vec<u32> = text.chars().map(keysym_from_char).collect();
+        let state = &self.virtual_keyboard_state;
+        if let Some(keys) = state
+            .keymap
+            .as_ref()
+            .and_then(|keymap| keymap.keys(&keysyms))
+        {
+            let (depressed, latched, locked) = state.modifiers;
+            for key in keys {
+                if key.mods == 0 {
+                    self.tap_virtual_key(&keyboard, key.keycode);
+                    continue;
+                }
+                // hold the modifiers of the shift level for this key only
+                keyboard.modifiers(depressed | key.mods, latched, locked, 0);
+                self.tap_virtual_key(&keyboard, key.keycode);
+                keyboard.modifiers(depressed, latched, locked, 0);
+            }
+            return Some(());
+        }
+
+        let mut unique = keysyms.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        let keymap = typing_keymap(&unique);
+        let mut file = tempfile::tempfile().ok()?;
+        file.write_all(keymap.as_bytes()).ok()?;
+        // the size includes the terminating nul
+        file.write_all(&[0]).ok()?;
+        keyboard.keymap(
+            KeymapFormat::XkbV1.into(),
+            file.as_fd(),
+            keymap.len() as u32 + 1,
+        );
+        // the masks of the application keymap mean nothing in the temporary one
+        keyboard.modifiers(0, 0, 0, 0);
+        for keysym in keysyms {
+            let index = unique.binary_search(&keysym).ok()? as u32;
+            self.tap_virtual_key(&keyboard, index + 1);
+        }
+
+        let state = &self.virtual_keyboard_state;
+        if let Some(keymap) = &state.keymap {
+            keymap.upload(&keyboard);
+            let (depressed, latched, locked) = state.modifiers;
+            keyboard.modifiers(depressed, latched, locked, 0);
+        }
+        Some(())
+    }
+
+    fn tap_virtual_key(&self, keyboard: &ZwpVirtualKeyboardV1, key: u32) {
+        let time = self.virtual_keyboard_time();
+        keyboard.key(time, key, KeyState::Pressed.into());
+        keyboard.key(time, key, KeyState::Released.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chars_map_to_latin1_or_unicode_keysyms() {
+        assert_eq!(keysym_from_char('a'), 0x61);
+        assert_eq!(keysym_from_char('é'), 0xe9);
+        assert_eq!(keysym_from_char('\n'), 0xff0d);
+        assert_eq!(keysym_from_char('😀'), 0x0101_f600);
+        assert_eq!(keysym_name(keysym_from_char('😀')), "U1F600");
+        assert_eq!(keysym_name(keysym_from_char('\t')), "Tab");
+    }
+
+    #[test]
+    fn typing_keymap_has_one_key_per_keysym() {
+        let keymap = typing_keymap(&[0x61, 0x0101_f600]);
+        assert!(keymap.contains("<K1> = 9;"));
+        assert!(keymap.contains("<K2> = 10;"));
+        assert!(keymap.contains("key <K2> { [ U1F600 ] };"));
+        assert!(keymap.contains("maximum = 11;"));
+    }
+}
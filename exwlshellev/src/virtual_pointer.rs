@@ -0,0 +1,150 @@
+//! Synthesizing pointer input through wlr-virtual-pointer-unstable-v1
+//!
+//! Every request is sent as one pointer frame. The virtual pointer is created on first use,
+//! for the seat of the latest input, or the seat the compositor picks before there was input.
+use std::time::Instant;
+
+use wayland_client::{
+    Proxy, delegate_noop,
+    protocol::{
+        wl_output::WlOutput,
+        wl_pointer::{Axis, AxisSource, ButtonState},
+    },
+};
+use wayland_protocols_wlr::virtual_pointer::v1::client::{
+    zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1,
+    zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1,
+};
+
+use crate::WindowState;
+
+#[derive(Debug)]
+pub(crate) struct VirtualPointerState {
+    pub(crate) manager: Option<ZwlrVirtualPointerManagerV1>,
+    pointer: Option<ZwlrVirtualPointerV1>,
+    /// reference of the millisecond timestamps of the pointer events
+    start: Instant,
+}
+
+impl Default for VirtualPointerState {
+    fn default() -> Self {
+        Self {
+            manager: None,
+            pointer: None,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl<T: 'static> WindowState<T> {
+    /// The virtual pointer, created on the seat of the latest input the first time
+    fn virtual_pointer(&mut self) -> Option<(ZwlrVirtualPointerV1, u32)> {
+        if self.virtual_pointer.pointer.is_none() {
+            let manager = self.virtual_pointer.manager.as_ref()?;
+            let qh = self.queue_handle.as_ref()?;
+            let seat = self.current_seat().map(|storage| &storage.seat);
+            let pointer = manager.create_virtual_pointer(seat, qh, ());
+            self.virtual_pointer.pointer = Some(pointer);
+        }
+        let state = &self.virtual_pointer;
+        let time = state.start.elapsed().as_millis() as u32;
+        state.pointer.clone().map(|pointer| (pointer, time))
+    }
+
+    /// Bind the virtual pointer to `output`, absolute motion then spans only it instead of the
+    /// whole layout. Needs version 2 of the protocol.
+    ///
+    /// Returns `None` if the compositor does not support wlr-virtual-pointer-unstable-v1 v2.
+    pub fn set_virtual_pointer_output(&mut self, output: Option<&WlOutput>) -> Option<()> {
+        let manager = self
+            .virtual_pointer
+            .manager
+            .as_ref()
+            .filter(|manager| manager.version() >= 2)?;
+        let qh = self.queue_handle.as_ref()?;
+        let seat = self.current_seat().map(|storage| &storage.seat);
+        let pointer = manager.create_virtual_pointer_with_output(seat, output, qh, ());
+        if let Some(old) = self.virtual_pointer.pointer.replace(pointer) {
+            old.destroy();
+        }
+        Some(())
+    }
+
+    /// Move the pointer by `dx`, `dy`.
+    ///
+    /// Returns `None` if the compositor does not support wlr-virtual-pointer-unstable-v1.
+    pub fn virtual_pointer_motion(&mut self, dx: f64, dy: f64) -> Option<()> {
+        let (pointer, time) = self.virtual_pointer()?;
+        pointer.motion(time, dx, dy);
+        pointer.frame();
+        Some(())
+    }
+
+    /// Move the pointer to `x`, `y` out of `width`, `height`, which span the whole layout or
+    /// the output set with [`WindowState::set_virtual_pointer_output`].
+    ///
+    /// Returns `None` if the compositor does not support wlr-virtual-pointer-unstable-v1.
+    pub fn virtual_pointer_motion_absolute(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Option<()> {
+        let (pointer, time) = self.virtual_pointer()?;
+        pointer.motion_absolute(time, x, y, width, height);
+        pointer.frame();
+        Some(())
+    }
+
+    /// Press or release the evdev `button`, like `BTN_LEFT`.
+    ///
+    /// Returns `None` if the compositor does not support wlr-virtual-pointer-unstable-v1.
+    pub fn virtual_pointer_button(&mut self, button: u32, state: ButtonState) -> Option<()> {
+        let (pointer, time) = self.virtual_pointer()?;
+        pointer.button(time, button, state);
+        pointer.frame();
+        Some(())
+    }
+
+    /// Scroll a wheel by `value` along `axis`, over `discrete` steps if given.
+    ///
+    /// Returns `None` if the compositor does not support wlr-virtual-pointer-unstable-v1.
+    pub fn virtual_pointer_axis(
+        &mut self,
+        axis: Axis,
+        value: f64,
+        discrete: Option<i32>,
+    ) -> Option<()> {
+        let (pointer, time) = self.virtual_pointer()?;
+        pointer.axis_source(AxisSource::Wheel);
+        match discrete {
+            Some(discrete) => pointer.axis_discrete(time, axis, value, discrete),
+            None => pointer.axis(time, axis, value),
+        }
+        pointer.frame();
+        Some(())
+    }
+
+    /// Scroll a wheel by `horizontal` and `vertical` at once, in a single frame. Zero values
+    /// are left out.
+    ///
+    /// Returns `None` if the compositor does not support wlr-virtual-pointer-unstable-v1.
+    pub fn virtual_pointer_scroll(&mut self, horizontal: f64, vertical: f64) -> Option<()> {
+        let (pointer, time) = self.virtual_pointer()?;
+        pointer.axis_source(AxisSource::Wheel);
+        for (axis, value) in [
+            (Axis::HorizontalScroll, horizontal),
+            (Axis::VerticalScroll, vertical),
+        ] {
+            if value != 0. {
+                pointer.axis(time, axis, value);
+            }
+        }
+        pointer.frame();
+        Some(())
+    }
+}
+
+delegate_noop!(@<T> WindowState<T>: ignore ZwlrVirtualPointerManagerV1);
+delegate_noop!(@<T> WindowState<T>: ignore ZwlrVirtualPointerV1);
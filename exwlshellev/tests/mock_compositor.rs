@@ -0,0 +1,327 @@
+#![cfg(feature = "testing")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use exwlshellev::keyboard::{KeyCode, PhysicalKey};
+use exwlshellev::reexport::wayland_client::wl_pointer;
+use exwlshellev::testing::{LockResponse, MockCompositor, MockOutput, MockRequest};
+use exwlshellev::xkb_keyboard::ElementState;
+use exwlshellev::*;
+
+fn window_state(mock: &mut MockCompositor) -> WindowState<()> {
+    WindowState::new("mock")
+        .with_use_display_handle(true)
+        .with_size(LayerSize::px(400, 300))
+        .with_connection(Some(mock.connection()))
+        .build()
+        .expect("build against the mock compositor")
+}
+
+#[test]
+fn initial_configure_requests_refresh() {
+    let mut mock = MockCompositor::new();
+    let ev = window_state(&mut mock);
+    let sizes = Rc::new(RefCell::new(Vec::new()));
+    let recorded = sizes.clone();
+    ev.running(move |event, _, _| match event {
+        ExWlShellEvent::RequestMessages(DispatchMessage::RequestRefresh {
+            width, height, ..
+        }) => {
+            recorded.borrow_mut().push((*width, *height));
+            ReturnData::RequestExit
+        }
+        _ => ReturnData::None,
+    })
+    .unwrap();
+    assert_eq!(*sizes.borrow(), [(400, 300)]);
+    assert_eq!(
+        mock.requests()[0],
+        MockRequest::LayerSurface {
+            surface: 0,
+            namespace: "mock".to_owned(),
+            output: None,
+        }
+    );
+}
+
+#[test]
+fn output_hotplug_follows_allscreens() {
+    let mut mock = MockCompositor::new();
+    let ev: WindowState<()> = WindowState::new("mock")
+        .with_use_display_handle(true)
+        .with_allscreens()
+        .with_connection(Some(mock.connection()))
+        .build()
+        .unwrap();
+    let mock = Rc::new(mock);
+    let script = mock.clone();
+    let names = Rc::new(RefCell::new(Vec::new()));
+    let recorded = names.clone();
+    let mut refreshed = Vec::new();
+    ev.running(move |event, _, id| {
+        match event {
+            ExWlShellEvent::RequestMessages(DispatchMessage::RequestRefresh { .. })
+                if !refreshed.contains(&id) =>
+            {
+                refreshed.push(id);
+                match refreshed.len() {
+                    1 => script.add_output(MockOutput::new("MOCK-2", 800, 600)),
+                    _ => script.remove_output("MOCK-2"),
+                }
+            }
+            ExWlShellEvent::RequestMessages(DispatchMessage::OutputAdded(info)) => {
+                recorded
+                    .borrow_mut()
+                    .push(format!("added {}", info.name.clone().unwrap_or_default()));
+            }
+            ExWlShellEvent::RequestMessages(DispatchMessage::OutputRemoved(info)) => {
+                recorded
+                    .borrow_mut()
+                    .push(format!("removed {}", info.name.clone().unwrap_or_default()));
+            }
+            ExWlShellEvent::RequestMessages(DispatchMessage::Closed) => {
+                recorded.borrow_mut().push("closed".to_owned());
+                return ReturnData::RequestExit;
+            }
+            _ => {}
+        }
+        ReturnData::None
+    })
+    .unwrap();
+    assert_eq!(
+        *names.borrow(),
+        ["added MOCK-1", "added MOCK-2", "removed MOCK-2", "closed"]
+    );
+    let layer_outputs: Vec<_> = mock
+        .requests()
+        .into_iter()
+        .filter_map(|request| match request {
+            MockRequest::LayerSurface { output, .. } => output,
+            _ => None,
+        })
+        .collect();
+    assert_eq!(layer_outputs, ["MOCK-1", "MOCK-2"]);
+}
+
+#[test]
+fn pointer_and_keyboard_reach_the_surface() {
+    let mut mock = MockCompositor::new();
+    let ev = window_state(&mut mock);
+    let mut seen = Vec::new();
+    let mut started = false;
+    let mock = Rc::new(mock);
+    let script = mock.clone();
+    ev.running(move |event, ev, id| {
+        let ExWlShellEvent::RequestMessages(message) = event else {
+            return ReturnData::None;
+        };
+        match message {
+            DispatchMessage::RequestRefresh { .. } if !started => {
+                started = true;
+                script.pointer_enter(0, 10., 20.);
+                script.pointer_button(0x110, true);
+                script.keyboard_enter(0);
+                // KEY_ESC
+                script.keyboard_key(1, true);
+            }
+            DispatchMessage::MouseEnter {
+                surface_x,
+                surface_y,
+                ..
+            } => {
+                assert_eq!((*surface_x, *surface_y), (10., 20.));
+                seen.push("enter");
+            }
+            DispatchMessage::MouseButton { button, .. } => {
+                assert_eq!(*button, 0x110);
+                assert!(ev.take_popup_grab_serial().is_some());
+                seen.push("button");
+            }
+            DispatchMessage::Focused { id: focused, .. } => {
+                assert_eq!(Some(*focused), id);
+                seen.push("focus");
+            }
+            DispatchMessage::KeyboardInput { event, .. } => {
+                assert_eq!(event.physical_key, PhysicalKey::Code(KeyCode::Escape));
+                seen.push("key");
+                assert_eq!(seen, ["enter", "button", "focus", "key"]);
+                return ReturnData::RequestExit;
+            }
+            _ => {}
+        }
+        ReturnData::None
+    })
+    .unwrap();
+}
+
+#[test]
+fn axis_events_of_a_frame_are_one_message() {
+    let mut mock = MockCompositor::new();
+    let ev = window_state(&mut mock);
+    let mut started = false;
+    let mock = Rc::new(mock);
+    let script = mock.clone();
+    ev.running(move |event, _, _| {
+        let ExWlShellEvent::RequestMessages(message) = event else {
+            return ReturnData::None;
+        };
+        match message {
+            DispatchMessage::RequestRefresh { .. } if !started => {
+                started = true;
+                script.pointer_enter(0, 10., 20.);
+                // half a step of a high-resolution wheel
+                script.pointer_axis(60, true);
+            }
+            DispatchMessage::Axis {
+                vertical,
+                horizontal,
+                source,
+                ..
+            } => {
+                assert_eq!(*source, Some(wl_pointer::AxisSource::Wheel));
+                assert_eq!(vertical.absolute, 7.5);
+                assert_eq!((vertical.value120, vertical.discrete), (60, 0));
+                assert!(vertical.inverted);
+                assert_eq!(*horizontal, AxisScroll::default());
+                return ReturnData::RequestExit;
+            }
+            _ => {}
+        }
+        ReturnData::None
+    })
+    .unwrap();
+}
+
+#[test]
+fn held_keys_are_released_on_leave() {
+    let mut mock = MockCompositor::new();
+    let ev = window_state(&mut mock);
+    let mut started = false;
+    let mut released = false;
+    let mock = Rc::new(mock);
+    let script = mock.clone();
+    ev.running(move |event, _, _| {
+        let ExWlShellEvent::RequestMessages(message) = event else {
+            return ReturnData::None;
+        };
+        match message {
+            DispatchMessage::RequestRefresh { .. } if !started => {
+                started = true;
+                script.keyboard_enter(0);
+                // KEY_ESC
+                script.keyboard_key(1, true);
+                script.keyboard_leave();
+            }
+            DispatchMessage::KeyboardInput {
+                event,
+                is_synthetic,
+                ..
+            } => {
+                assert_eq!(event.physical_key, PhysicalKey::Code(KeyCode::Escape));
+                if *is_synthetic {
+                    assert_eq!(event.state, ElementState::Released);
+                    released = true;
+                } else {
+                    assert_eq!(event.state, ElementState::Pressed);
+                }
+            }
+            DispatchMessage::Unfocus { .. } => {
+                assert!(released, "the held key was not released");
+                return ReturnData::RequestExit;
+            }
+            _ => {}
+        }
+        ReturnData::None
+    })
+    .unwrap();
+}
+
+#[test]
+fn input_carries_its_seat() {
+    let mut mock = MockCompositor::new();
+    let ev = window_state(&mut mock);
+    let mut added = None;
+    let mut started = false;
+    let mock = Rc::new(mock);
+    let script = mock.clone();
+    ev.running(move |event, ev, _| {
+        let ExWlShellEvent::RequestMessages(message) = event else {
+            return ReturnData::None;
+        };
+        match message {
+            DispatchMessage::SeatAdded(seat) => {
+                added = Some(*seat);
+            }
+            DispatchMessage::RequestRefresh { .. } if !started => {
+                started = true;
+                script.pointer_enter(0, 10., 20.);
+            }
+            DispatchMessage::MouseEnter { seat, .. } => {
+                assert_eq!(Some(*seat), added);
+                assert_eq!(ev.seat_ids(), Vec::from_iter(added));
+                return ReturnData::RequestExit;
+            }
+            _ => {}
+        }
+        ReturnData::None
+    })
+    .unwrap();
+}
+
+#[test]
+fn lock_then_finished() {
+    let mut mock = MockCompositor::new();
+    let ev = window_state(&mut mock);
+    let mock = Rc::new(mock);
+    let script = mock.clone();
+    let mut requested = false;
+    let messages = Rc::new(RefCell::new(Vec::new()));
+    let recorded = messages.clone();
+    ev.running(move |event, _, _| match event {
+        ExWlShellEvent::RequestMessages(DispatchMessage::RequestRefresh { .. }) if !requested => {
+            requested = true;
+            ReturnData::RequestLock
+        }
+        ExWlShellEvent::RequestMessages(DispatchMessage::Locked) => {
+            recorded.borrow_mut().push("locked");
+            script.finish_lock();
+            ReturnData::None
+        }
+        ExWlShellEvent::RequestMessages(DispatchMessage::LockFinished) => {
+            recorded.borrow_mut().push("finished");
+            ReturnData::RequestExit
+        }
+        _ => ReturnData::None,
+    })
+    .unwrap();
+    assert_eq!(*messages.borrow(), ["locked", "finished"]);
+    let requests = mock.requests();
+    assert!(requests.contains(&MockRequest::Lock));
+    assert!(requests.contains(&MockRequest::LockSurface {
+        surface: 1,
+        output: Some("MOCK-1".to_owned()),
+    }));
+    assert!(requests.contains(&MockRequest::UnlockAndDestroy));
+}
+
+#[test]
+fn denied_lock_is_reported() {
+    let mut mock = MockCompositor::new();
+    mock.set_lock_response(LockResponse::Deny);
+    let ev = window_state(&mut mock);
+    let mut requested = false;
+    ev.running(move |event, _, _| match event {
+        ExWlShellEvent::RequestMessages(DispatchMessage::RequestRefresh { .. }) if !requested => {
+            requested = true;
+            ReturnData::RequestLock
+        }
+        ExWlShellEvent::RequestMessages(DispatchMessage::Locked) => {
+            panic!("the mock denies every lock")
+        }
+        ExWlShellEvent::RequestMessages(DispatchMessage::LockDenied) => ReturnData::RequestExit,
+        _ => ReturnData::None,
+    })
+    .unwrap();
+    assert!(mock.requests().contains(&MockRequest::LockDestroyed));
+}
@@ -12,12 +12,19 @@ use iced_core::window::Id as IcedId;
 
 use std::sync::Arc;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct IcedXdgWindowSettings {
     /// The initial window size.
     pub size: Option<PixelSize>,
-    /// Request client-side decorations instead of the default server-side mode.
+    /// Request client-side decorations instead of the default server-side mode. The window
+    /// draws the frame of [`crate::csd`] whenever the compositor leaves it to us.
     pub client_side_decorations: bool,
+    /// The application id, desktop entries are matched against it.
+    pub app_id: Option<String>,
+    /// The minimum size the window can be resized to.
+    pub min_size: Option<PixelSize>,
+    /// The maximum size the window can be resized to.
+    pub max_size: Option<PixelSize>,
 }
 
 impl From<IcedXdgWindowSettings> for NewXdgWindowSettings {
@@ -26,6 +33,9 @@ impl From<IcedXdgWindowSettings> for NewXdgWindowSettings {
             title: None,
             size: val.size,
             client_side_decorations: val.client_side_decorations,
+            app_id: val.app_id,
+            min_size: val.min_size,
+            max_size: val.max_size,
         }
     }
 }
@@ -158,7 +168,45 @@ pub enum ExwlShellCustomAction {
     VirtualKeyboardPressed {
         key: u32,
     },
+    /// Type text, with a temporary keymap for the characters missing from the keymap
+    VirtualKeyboardType(String),
+    /// Press or release a key, without the automatic release of `VirtualKeyboardPressed`
+    VirtualKeyboardKey {
+        key: u32,
+        pressed: bool,
+    },
+    /// Set the modifiers, masks as in `exwlshellev::virtual_keyboard::modifier`
+    VirtualKeyboardModifiers {
+        depressed: u32,
+        latched: u32,
+        locked: u32,
+    },
+    /// Move the virtual pointer
+    VirtualPointerMotion {
+        dx: f64,
+        dy: f64,
+    },
+    /// Move the virtual pointer to `x`, `y` out of `width`, `height`, which span the whole
+    /// output layout
+    VirtualPointerMotionAbsolute {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    /// Press or release an evdev button, like `BTN_LEFT`, of the virtual pointer
+    VirtualPointerButton {
+        button: u32,
+        pressed: bool,
+    },
+    /// Scroll the wheel of the virtual pointer along both axes in one frame
+    VirtualPointerAxis {
+        horizontal: f64,
+        vertical: f64,
+    },
     BlurOptionChange(BlurOption),
+    /// Keep the screen from idling while the window is visible
+    IdleInhibit(bool),
     // settings, info, single_tone
     NewLayerShell {
         settings: NewLayerShellSettings,
@@ -190,6 +238,18 @@ pub enum ExwlShellCustomAction {
     ForgetLastOutput,
     Lock,
     UnLock,
+    /// Ask for an xdg-activation token for the window, it is sent back as
+    /// `ShellEvent::ActivationToken`
+    RequestActivationToken,
+    /// Activate the window with a token from another client
+    ActivateWithToken {
+        token: String,
+    },
+    /// Start dragging `data` out of the window, must be sent while a mouse button is pressed
+    StartDrag {
+        mime_types: Vec<String>,
+        data: Vec<u8>,
+    },
 }
 
 /// Please do not use this struct directly
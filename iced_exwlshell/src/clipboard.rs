@@ -1,6 +1,7 @@
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use exwlshellev::WindowWrapper;
+use exwlshellev::WindowState;
+use exwlshellev::clipboard::SelectionKind;
 use iced_core::Clipboard;
 use iced_core::clipboard::Kind;
 
@@ -14,69 +15,48 @@ pub(crate) fn is_disabled() -> bool {
     DISABLED.load(Ordering::Relaxed)
 }
 
-pub struct ExwlShellClipboard {
-    state: State,
+fn selection_kind(kind: Kind) -> SelectionKind {
+    match kind {
+        Kind::Standard => SelectionKind::Clipboard,
+        Kind::Primary => SelectionKind::Primary,
+    }
 }
 
-enum State {
-    Connected(window_clipboard::Clipboard),
-    Unavailable,
+/// The selections of the shell connection, seen through iced's [`Clipboard`]
+pub struct ExwlShellClipboard<'a, T> {
+    ev: &'a mut WindowState<T>,
 }
 
-impl ExwlShellClipboard {
-    /// Creates a new [`Clipboard`] for the given window.
-    pub fn connect(window: &WindowWrapper) -> Self {
-        #[allow(unsafe_code)]
-        let state = unsafe { window_clipboard::Clipboard::connect(window) }
-            .ok()
-            .map(State::Connected)
-            .unwrap_or(State::Unavailable);
-
-        Self { state }
+impl<'a, T: 'static> ExwlShellClipboard<'a, T> {
+    pub fn new(ev: &'a mut WindowState<T>) -> Self {
+        Self { ev }
     }
 
-    /// Creates a new [`Clipboard`] that isn't associated with a window.
-    /// This clipboard will never contain a copied value.
-    #[allow(unused)]
-    pub fn unconnected() -> Self {
-        Self {
-            state: State::Unavailable,
-        }
-    }
-
-    /// Reads the current content of the [`Clipboard`] as text.
+    /// Reads the current content of the [`Clipboard`] as text. The selection of another client
+    /// is read ahead when it changes, so it may be missing right after.
     pub fn read(&self, kind: Kind) -> Option<String> {
-        match &self.state {
-            State::Connected(clipboard) => match kind {
-                Kind::Standard => clipboard.read().ok(),
-                Kind::Primary => clipboard.read_primary().and_then(Result::ok),
-            },
-            State::Unavailable => None,
+        if is_disabled() {
+            return None;
         }
+        self.ev.read_selection_text(selection_kind(kind))
     }
 
     /// Writes the given text contents to the [`Clipboard`].
     pub fn write(&mut self, kind: Kind, contents: String) {
-        match &mut self.state {
-            State::Connected(clipboard) => {
-                let result = match kind {
-                    Kind::Standard => clipboard.write(contents),
-                    Kind::Primary => clipboard.write_primary(contents).unwrap_or(Ok(())),
-                };
-
-                match result {
-                    Ok(()) => {}
-                    Err(error) => {
-                        log::warn!("error writing to clipboard: {error}");
-                    }
-                }
-            }
-            State::Unavailable => {}
+        if is_disabled() {
+            return;
+        }
+        if self
+            .ev
+            .set_selection_text(selection_kind(kind), contents)
+            .is_none()
+        {
+            log::warn!("error writing to clipboard: {kind:?} is not available");
         }
     }
 }
 
-impl Clipboard for ExwlShellClipboard {
+impl<T: 'static> Clipboard for ExwlShellClipboard<'_, T> {
     fn read(&self, kind: Kind) -> Option<String> {
         self.read(kind)
     }
@@ -4,12 +4,13 @@ use crate::event::IcedButtonState;
 use crate::event::WindowEvent as ExWlShellEvent;
 use exwlshellev::keyboard::KeyLocation;
 use exwlshellev::keyboard::ModifiersState;
+use exwlshellev::reexport::xdg_toplevel::ResizeEdge;
 use exwlshellev::xkb_keyboard::ElementState;
 use exwlshellev::xkb_keyboard::KeyEvent as ExWlShellKeyEvent;
 use iced_core::SmolStr;
 use iced_core::input_method;
 use iced_core::touch;
-use iced_core::{Event as IcedEvent, keyboard, mouse};
+use iced_core::{Event as IcedEvent, keyboard, mouse, window};
 use keymap::{key, physical_key};
 use std::ops::Mul;
 
@@ -41,11 +42,26 @@ pub fn window_event(
                 },
             }))
         }
-        ExWlShellEvent::CursorEnter { .. } => Some(IcedEvent::Mouse(mouse::Event::CursorEntered)),
-        ExWlShellEvent::MouseInput(state) => Some(IcedEvent::Mouse(match state {
-            IcedButtonState::Pressed(btn) => mouse::Event::ButtonPressed(*btn),
-            IcedButtonState::Released(btn) => mouse::Event::ButtonReleased(*btn),
-        })),
+        ExWlShellEvent::CursorEnter { .. } | ExWlShellEvent::TabletProximityIn => {
+            Some(IcedEvent::Mouse(mouse::Event::CursorEntered))
+        }
+        // tablet tools drive the mouse, so ordinary widgets work with a pen
+        ExWlShellEvent::TabletProximityOut => Some(IcedEvent::Mouse(mouse::Event::CursorLeft)),
+        ExWlShellEvent::TabletMotion { x, y } => {
+            let (x, y) = scale_down((*x, *y), application_scale_factor);
+            Some(IcedEvent::Mouse(mouse::Event::CursorMoved {
+                position: iced_core::Point {
+                    x: x as f32,
+                    y: y as f32,
+                },
+            }))
+        }
+        ExWlShellEvent::MouseInput(state) | ExWlShellEvent::TabletButton(state) => {
+            Some(IcedEvent::Mouse(match state {
+                IcedButtonState::Pressed(btn) => mouse::Event::ButtonPressed(*btn),
+                IcedButtonState::Released(btn) => mouse::Event::ButtonReleased(*btn),
+            }))
+        }
         ExWlShellEvent::Axis { x, y } => Some(IcedEvent::Mouse(mouse::Event::WheelScrolled {
             delta: mouse::ScrollDelta::Lines { x: *x, y: *y },
         })),
@@ -147,14 +163,18 @@ pub fn window_event(
         )),
         ExWlShellEvent::Unfocus => Some(IcedEvent::Window(iced_core::window::Event::Unfocused)),
         ExWlShellEvent::Focused => Some(IcedEvent::Window(iced_core::window::Event::Focused)),
-        ExWlShellEvent::Ime(event) => Some(IcedEvent::InputMethod(match event {
-            exwlshellev::Ime::Enabled => input_method::Event::Opened,
-            exwlshellev::Ime::Preedit(content, size) => {
-                input_method::Event::Preedit(content.clone(), size.map(|(start, end)| start..end))
-            }
-            exwlshellev::Ime::Commit(content) => input_method::Event::Commit(content.clone()),
-            exwlshellev::Ime::Disabled => input_method::Event::Closed,
-        })),
+        ExWlShellEvent::Ime(event) => match event {
+            exwlshellev::Ime::Enabled => Some(input_method::Event::Opened),
+            exwlshellev::Ime::Preedit(content, size) => Some(input_method::Event::Preedit(
+                content.clone(),
+                size.map(|(start, end)| start..end),
+            )),
+            exwlshellev::Ime::Commit(content) => Some(input_method::Event::Commit(content.clone())),
+            // iced has no such event, `multi_window` selects the text for the commit to replace
+            exwlshellev::Ime::DeleteSurrounding { .. } => None,
+            exwlshellev::Ime::Disabled => Some(input_method::Event::Closed),
+        }
+        .map(IcedEvent::InputMethod),
         _ => None,
     }
 }
@@ -197,3 +217,16 @@ pub(crate) fn mouse_interaction(interaction: mouse::Interaction) -> String {
 fn is_private_use(c: char) -> bool {
     ('\u{E000}'..='\u{F8FF}').contains(&c)
 }
+
+pub(crate) fn resize_edge(direction: window::Direction) -> ResizeEdge {
+    match direction {
+        window::Direction::North => ResizeEdge::Top,
+        window::Direction::South => ResizeEdge::Bottom,
+        window::Direction::East => ResizeEdge::Right,
+        window::Direction::West => ResizeEdge::Left,
+        window::Direction::NorthEast => ResizeEdge::TopRight,
+        window::Direction::NorthWest => ResizeEdge::TopLeft,
+        window::Direction::SouthEast => ResizeEdge::BottomRight,
+        window::Direction::SouthWest => ResizeEdge::BottomLeft,
+    }
+}
@@ -0,0 +1,625 @@
+//! Client-side decorations
+//!
+//! When the compositor leaves drawing the frame of an xdg toplevel to us, the view of the window
+//! is wrapped in a header bar with the title and close, maximize and minimize buttons, and a thin
+//! border. Dragging the header moves the window and dragging the border resizes it, the content
+//! keeps every click it gets. The colors follow the [`Palette`] of the application theme.
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use exwlshellev::reexport::xdg_toplevel::ResizeEdge;
+use exwlshellev::{ToplevelState, WindowState, WmCapabilities, id::Id as LayerShellId};
+use iced_core::layout::{self, Layout};
+use iced_core::mouse::{self, Click, click};
+use iced_core::renderer::{self, Quad};
+use iced_core::text::{self, Alignment, LineHeight, Shaping, Text, Wrapping};
+use iced_core::theme::{self, Palette};
+use iced_core::widget::{Tree, Widget, tree};
+use iced_core::{
+    Border, Clipboard, Color, Element, Event, Font, Length, Pixels, Point, Rectangle, Shell, Size,
+    Vector, alignment, overlay,
+};
+
+/// Height of the header bar in logical pixels
+pub(crate) const HEADER_HEIGHT: f32 = 32.;
+/// Width of the border around the content that starts a resize, the top one is part of the header
+const RESIZE_BORDER: f32 = 6.;
+const BUTTON_WIDTH: f32 = 40.;
+
+/// Colors of the frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Style {
+    pub header: Color,
+    /// header of a window that is not active
+    pub header_inactive: Color,
+    pub title: Color,
+    pub button_hover: Color,
+    pub close_hover: Color,
+}
+
+impl Style {
+    pub fn from_palette(palette: &Palette) -> Self {
+        let header = mix(palette.background, palette.text, 0.08);
+        Self {
+            header,
+            header_inactive: mix(palette.background, palette.text, 0.03),
+            title: palette.text,
+            button_hover: mix(header, palette.text, 0.12),
+            close_hover: palette.danger,
+        }
+    }
+
+    /// The frame for `theme`, or the light one if the theme has no palette
+    pub fn from_theme(theme: &impl theme::Base) -> Self {
+        Self::from_palette(&theme.palette().unwrap_or(Palette::LIGHT))
+    }
+}
+
+fn mix(base: Color, other: Color, amount: f32) -> Color {
+    Color {
+        r: base.r + (other.r - base.r) * amount,
+        g: base.g + (other.g - base.g) * amount,
+        b: base.b + (other.b - base.b) * amount,
+        a: base.a,
+    }
+}
+
+/// What the frame asks of the window, carried out after the events reached the view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Request {
+    Move,
+    Resize(ResizeEdge),
+    ShowMenu(Point),
+    ToggleMaximize,
+    Minimize,
+    Close,
+}
+
+/// The state of the toplevel the frame shows, shared with the frame rebuilt on every view
+#[derive(Debug, Default)]
+pub(crate) struct FrameState {
+    pub title: String,
+    pub toplevel: ToplevelState,
+    pub capabilities: Option<WmCapabilities>,
+    pub request: Option<Request>,
+}
+
+pub(crate) type Frame = Rc<RefCell<FrameState>>;
+
+/// Carry out a request of the frame of the toplevel `id`
+pub(crate) fn apply_request<T: 'static>(
+    ev: &mut WindowState<T>,
+    id: LayerShellId,
+    request: Request,
+) {
+    let done = match request {
+        Request::Move => ev.start_move(id),
+        Request::Resize(edge) => ev.start_resize(id, edge),
+        Request::ShowMenu(position) => {
+            ev.show_window_menu(id, position.x as i32, position.y as i32)
+        }
+        Request::ToggleMaximize => ev
+            .get_unit_with_id(id)
+            .and_then(|unit| unit.set_maximized(!unit.toplevel_state().maximized)),
+        Request::Minimize => ev
+            .get_unit_with_id(id)
+            .and_then(|unit| unit.set_minimized()),
+        Request::Close => {
+            ev.request_close(id);
+            Some(())
+        }
+    };
+    if done.is_none() {
+        tracing::warn!("client-side decorations could not {request:?} window {id:?}");
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Button {
+    Close,
+    Maximize,
+    Minimize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Hit {
+    Edge(ResizeEdge),
+    Button(Button),
+    Header,
+    Content,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    last_click: Option<Click>,
+    pressed: Option<Button>,
+}
+
+/// The view of a window inside its frame
+pub(crate) struct Decorated<'a, Message, Theme, Renderer> {
+    content: Element<'a, Message, Theme, Renderer>,
+    frame: Frame,
+}
+
+impl<'a, Message, Theme, Renderer> Decorated<'a, Message, Theme, Renderer> {
+    pub fn new(content: Element<'a, Message, Theme, Renderer>, frame: Frame) -> Self {
+        Self { content, frame }
+    }
+
+    fn header_height(&self) -> f32 {
+        if self.frame.borrow().toplevel.fullscreen {
+            0.
+        } else {
+            HEADER_HEIGHT
+        }
+    }
+
+    /// A tiled window cannot be resized from its edges, so it has no border
+    fn border(&self) -> f32 {
+        let toplevel = self.frame.borrow().toplevel;
+        if toplevel.maximized || toplevel.fullscreen {
+            0.
+        } else {
+            RESIZE_BORDER
+        }
+    }
+
+    /// The buttons shown from the right, skipping what the compositor cannot do
+    fn buttons(&self) -> Vec<Button> {
+        let capabilities = self.frame.borrow().capabilities;
+        let mut buttons = vec![Button::Close];
+        if capabilities.is_none_or(|capabilities| capabilities.maximize) {
+            buttons.push(Button::Maximize);
+        }
+        if capabilities.is_none_or(|capabilities| capabilities.minimize) {
+            buttons.push(Button::Minimize);
+        }
+        buttons
+    }
+
+    fn button_bounds(bounds: Rectangle, index: usize) -> Rectangle {
+        Rectangle {
+            x: bounds.x + bounds.width - BUTTON_WIDTH * (index + 1) as f32,
+            y: bounds.y,
+            width: BUTTON_WIDTH,
+            height: HEADER_HEIGHT,
+        }
+    }
+
+    /// The area left to the content by the header and the border
+    fn content_bounds(&self, bounds: Rectangle) -> Rectangle {
+        let header_height = self.header_height();
+        let border = self.border();
+        Rectangle {
+            x: bounds.x + border,
+            y: bounds.y + header_height,
+            width: (bounds.width - 2. * border).max(0.),
+            height: (bounds.height - header_height - border).max(0.),
+        }
+    }
+
+    fn hit(&self, bounds: Rectangle, position: Point) -> Hit {
+        if self.content_bounds(bounds).contains(position) {
+            return Hit::Content;
+        }
+        if self.border() > 0. {
+            let left = position.x < bounds.x + RESIZE_BORDER;
+            let right = position.x > bounds.x + bounds.width - RESIZE_BORDER;
+            let top = position.y < bounds.y + RESIZE_BORDER;
+            let bottom = position.y > bounds.y + bounds.height - RESIZE_BORDER;
+            let edge = match (top, bottom, left, right) {
+                (true, _, true, _) => Some(ResizeEdge::TopLeft),
+                (true, _, _, true) => Some(ResizeEdge::TopRight),
+                (_, true, true, _) => Some(ResizeEdge::BottomLeft),
+                (_, true, _, true) => Some(ResizeEdge::BottomRight),
+                (true, ..) => Some(ResizeEdge::Top),
+                (_, true, ..) => Some(ResizeEdge::Bottom),
+                (_, _, true, _) => Some(ResizeEdge::Left),
+                (_, _, _, true) => Some(ResizeEdge::Right),
+                _ => None,
+            };
+            if let Some(edge) = edge {
+                return Hit::Edge(edge);
+            }
+        }
+        self.buttons()
+            .into_iter()
+            .enumerate()
+            .find(|(index, _)| Self::button_bounds(bounds, *index).contains(position))
+            .map_or(Hit::Header, |(_, button)| Hit::Button(button))
+    }
+
+    fn request(&self, request: Request) {
+        self.frame.borrow_mut().request = Some(request);
+    }
+}
+
+impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer>
+    for Decorated<'_, Message, Theme, Renderer>
+where
+    Theme: theme::Base,
+    Renderer: text::Renderer<Font = Font>,
+{
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Fill, Length::Fill)
+    }
+
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    fn layout(
+        &mut self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let size = limits.max();
+        let content_bounds = self.content_bounds(Rectangle::with_size(size));
+        let content_limits = layout::Limits::new(Size::ZERO, content_bounds.size());
+        let content = self
+            .content
+            .as_widget_mut()
+            .layout(&mut tree.children[0], renderer, &content_limits)
+            .move_to(content_bounds.position());
+        layout::Node::with_children(size, vec![content])
+    }
+
+    fn operate(
+        &mut self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn iced_core::widget::Operation,
+    ) {
+        let content_layout = content_layout(layout);
+        self.content.as_widget_mut().operate(
+            &mut tree.children[0],
+            content_layout,
+            renderer,
+            operation,
+        );
+    }
+
+    fn update(
+        &mut self,
+        tree: &mut Tree,
+        event: &Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let hit = cursor
+            .position_over(bounds)
+            .map(|position| (position, self.hit(bounds, position)));
+        let state = tree.state.downcast_mut::<State>();
+        match (event, hit) {
+            (Event::Mouse(mouse::Event::ButtonPressed(button)), Some((_, Hit::Edge(edge)))) => {
+                if *button == mouse::Button::Left {
+                    self.request(Request::Resize(edge));
+                }
+                shell.capture_event();
+                return;
+            }
+            (Event::Mouse(mouse::Event::ButtonPressed(button)), Some((_, Hit::Button(hit)))) => {
+                if *button == mouse::Button::Left {
+                    state.pressed = Some(hit);
+                }
+                shell.capture_event();
+                return;
+            }
+            (Event::Mouse(mouse::Event::ButtonPressed(button)), Some((position, Hit::Header))) => {
+                match button {
+                    mouse::Button::Left => {
+                        let click = Click::new(position, *button, state.last_click);
+                        state.last_click = Some(click);
+                        if click.kind() == click::Kind::Double {
+                            self.request(Request::ToggleMaximize);
+                        } else {
+                            self.request(Request::Move);
+                        }
+                    }
+                    mouse::Button::Right => {
+                        self.request(Request::ShowMenu(Point::new(
+                            position.x - bounds.x,
+                            position.y - bounds.y,
+                        )));
+                    }
+                    _ => {}
+                }
+                shell.capture_event();
+                return;
+            }
+            (Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)), hit) => {
+                if let Some(pressed) = state.pressed.take() {
+                    if hit.is_some_and(|(_, hit)| hit == Hit::Button(pressed)) {
+                        self.request(match pressed {
+                            Button::Close => Request::Close,
+                            Button::Maximize => Request::ToggleMaximize,
+                            Button::Minimize => Request::Minimize,
+                        });
+                    }
+                    shell.request_redraw();
+                    shell.capture_event();
+                    return;
+                }
+            }
+            (Event::Mouse(mouse::Event::CursorMoved { .. }), _) => {
+                // button hover follows the cursor
+                shell.request_redraw();
+            }
+            _ => {}
+        }
+        let content_layout = content_layout(layout);
+        self.content.as_widget_mut().update(
+            &mut tree.children[0],
+            event,
+            content_layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        );
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let bounds = layout.bounds();
+        match cursor
+            .position_over(bounds)
+            .map(|position| self.hit(bounds, position))
+        {
+            Some(Hit::Edge(ResizeEdge::Left | ResizeEdge::Right)) => {
+                mouse::Interaction::ResizingHorizontally
+            }
+            Some(Hit::Edge(ResizeEdge::Top | ResizeEdge::Bottom)) => {
+                mouse::Interaction::ResizingVertically
+            }
+            Some(Hit::Edge(ResizeEdge::TopRight | ResizeEdge::BottomLeft)) => {
+                mouse::Interaction::ResizingDiagonallyUp
+            }
+            Some(Hit::Edge(_)) => mouse::Interaction::ResizingDiagonallyDown,
+            Some(Hit::Button(_)) => mouse::Interaction::Pointer,
+            Some(Hit::Header) => mouse::Interaction::Idle,
+            Some(Hit::Content) | None => {
+                let content_layout = content_layout(layout);
+                self.content.as_widget().mouse_interaction(
+                    &tree.children[0],
+                    content_layout,
+                    cursor,
+                    viewport,
+                    renderer,
+                )
+            }
+        }
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let content_layout = content_layout(layout);
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            content_layout,
+            cursor,
+            viewport,
+        );
+        let header_height = self.header_height();
+        if header_height == 0. {
+            return;
+        }
+        let bounds = layout.bounds();
+        let header = Rectangle {
+            height: header_height,
+            ..bounds
+        };
+        let frame_style = Style::from_theme(theme);
+        let frame = self.frame.borrow();
+        let frame_color = if frame.toplevel.activated {
+            frame_style.header
+        } else {
+            frame_style.header_inactive
+        };
+        let border = self.border();
+        let content = self.content_bounds(bounds);
+        let left = Rectangle {
+            x: bounds.x,
+            width: border,
+            ..bounds
+        };
+        let right = Rectangle {
+            x: bounds.x + bounds.width - border,
+            ..left
+        };
+        let bottom = Rectangle {
+            y: content.y + content.height,
+            height: bounds.y + bounds.height - content.y - content.height,
+            ..bounds
+        };
+        for bounds in [header, left, right, bottom] {
+            renderer.fill_quad(
+                Quad {
+                    bounds,
+                    ..Quad::default()
+                },
+                frame_color,
+            );
+        }
+        renderer.fill_text(
+            Text {
+                content: frame.title.clone(),
+                bounds: header.size(),
+                size: Pixels(14.),
+                line_height: LineHeight::default(),
+                font: Font::default(),
+                align_x: Alignment::Center,
+                align_y: alignment::Vertical::Center,
+                shaping: Shaping::Advanced,
+                wrapping: Wrapping::None,
+            },
+            header.center(),
+            frame_style.title,
+            header,
+        );
+        for (index, button) in self.buttons().into_iter().enumerate() {
+            let button_bounds = Self::button_bounds(bounds, index);
+            if cursor.is_over(button_bounds) {
+                renderer.fill_quad(
+                    Quad {
+                        bounds: button_bounds,
+                        ..Quad::default()
+                    },
+                    if button == Button::Close {
+                        frame_style.close_hover
+                    } else {
+                        frame_style.button_hover
+                    },
+                );
+            }
+            draw_icon(
+                renderer,
+                button,
+                frame.toplevel.maximized,
+                button_bounds.center(),
+                frame_style.title,
+            );
+        }
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'b>,
+        renderer: &Renderer,
+        viewport: &Rectangle,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        let content_layout = content_layout(layout);
+        self.content.as_widget_mut().overlay(
+            &mut tree.children[0],
+            content_layout,
+            renderer,
+            viewport,
+            translation,
+        )
+    }
+}
+
+/// The layout of the content inside the frame
+fn content_layout(layout: Layout<'_>) -> Layout<'_> {
+    layout.children().next().expect("the frame has its content")
+}
+
+fn draw_icon<Renderer: renderer::Renderer>(
+    renderer: &mut Renderer,
+    button: Button,
+    maximized: bool,
+    center: Point,
+    color: Color,
+) {
+    const SIZE: f32 = 10.;
+    let outline = |renderer: &mut Renderer, bounds: Rectangle| {
+        renderer.fill_quad(
+            Quad {
+                bounds,
+                border: Border {
+                    color,
+                    width: 1.,
+                    radius: 0.into(),
+                },
+                ..Quad::default()
+            },
+            Color::TRANSPARENT,
+        );
+    };
+    let square = |offset: Vector, size: f32| Rectangle {
+        x: center.x - size / 2. + offset.x,
+        y: center.y - size / 2. + offset.y,
+        width: size,
+        height: size,
+    };
+    match button {
+        Button::Minimize => renderer.fill_quad(
+            Quad {
+                bounds: Rectangle {
+                    x: center.x - SIZE / 2.,
+                    y: center.y + SIZE / 2. - 1.,
+                    width: SIZE,
+                    height: 1.,
+                },
+                ..Quad::default()
+            },
+            color,
+        ),
+        Button::Maximize if maximized => {
+            outline(renderer, square(Vector::new(2., -2.), SIZE - 2.));
+            outline(renderer, square(Vector::new(-1., 1.), SIZE - 2.));
+        }
+        Button::Maximize => outline(renderer, square(Vector::ZERO, SIZE)),
+        // a cross out of dots along both diagonals, quads cannot be rotated
+        Button::Close => {
+            for step in 0..=SIZE as i32 {
+                let offset = step as f32 - SIZE / 2.;
+                for (x, y) in [(offset, offset), (offset, -offset)] {
+                    renderer.fill_quad(
+                        Quad {
+                            bounds: Rectangle {
+                                x: center.x + x - 0.75,
+                                y: center.y + y - 0.75,
+                                width: 1.5,
+                                height: 1.5,
+                            },
+                            ..Quad::default()
+                        },
+                        color,
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<Decorated<'a, Message, Theme, Renderer>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Message: 'a,
+    Theme: theme::Base + 'a,
+    Renderer: text::Renderer<Font = Font> + 'a,
+{
+    fn from(decorated: Decorated<'a, Message, Theme, Renderer>) -> Self {
+        Element::new(decorated)
+    }
+}
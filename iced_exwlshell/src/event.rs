@@ -1,9 +1,14 @@
+use std::path::PathBuf;
+
+use exwlshellev::compose::ComposeState;
+use exwlshellev::dnd::{URI_LIST_MIME_TYPE, parse_uri_list};
 use exwlshellev::keyboard::ModifiersState;
 use exwlshellev::reexport::wayland_client::{ButtonState, KeyState, WEnum, WlRegion};
 use exwlshellev::xkb_keyboard::KeyEvent as LayerShellKeyEvent;
 use exwlshellev::{DispatchMessage, WindowState};
 use iced_core::mouse;
 use iced_runtime::Action;
+use iced_wayland_subscriber::shell::{Compose, Gesture, SeatId};
 
 use iced_core::keyboard::Modifiers as IcedModifiers;
 
@@ -11,9 +16,27 @@ use iced_wayland_subscriber::OutputInfo;
 
 fn from_u32_to_icedmouse(code: u32) -> mouse::Button {
     match code {
-        273 => mouse::Button::Right,
-        274 => mouse::Button::Middle,
-        _ => mouse::Button::Left,
+        // BTN_LEFT
+        0x110 => mouse::Button::Left,
+        // BTN_RIGHT
+        0x111 => mouse::Button::Right,
+        // BTN_MIDDLE
+        0x112 => mouse::Button::Middle,
+        // BTN_SIDE, BTN_BACK
+        0x113 | 0x116 => mouse::Button::Back,
+        // BTN_EXTRA, BTN_FORWARD
+        0x114 | 0x115 => mouse::Button::Forward,
+        code => mouse::Button::Other(code as u16),
+    }
+}
+
+fn from_stylus_to_icedmouse(code: u32) -> mouse::Button {
+    match code {
+        // BTN_STYLUS
+        0x14b => mouse::Button::Right,
+        // BTN_STYLUS2
+        0x14c => mouse::Button::Middle,
+        code => mouse::Button::Other(code as u16),
     }
 }
 
@@ -98,6 +121,11 @@ pub enum WindowEvent {
         y: f64,
     },
     Ime(exwlshellev::Ime),
+    /// bytes to delete before and after the cursor
+    ImeDeleteSurrounding {
+        before: usize,
+        after: usize,
+    },
     Refresh,
     Closed,
     ThemeChanged(iced_core::theme::Mode),
@@ -108,6 +136,34 @@ pub enum WindowEvent {
     Locked,
     LockDenied,
     LockFinished,
+    ActivationToken(String),
+    Gesture(Gesture),
+    Compose(Compose),
+    KeyboardLayoutChanged {
+        index: u32,
+        name: String,
+        layouts: Vec<String>,
+    },
+    RelativeMotion {
+        dx: f64,
+        dy: f64,
+        dx_unaccel: f64,
+        dy_unaccel: f64,
+    },
+    TabletProximityIn,
+    TabletProximityOut,
+    TabletMotion {
+        x: f64,
+        y: f64,
+    },
+    TabletButton(IcedButtonState),
+    FilesHovered(Vec<PathBuf>),
+    FilesHoveredLeft,
+    FilesDropped(Vec<PathBuf>),
+    SeatAdded(SeatId),
+    SeatRemoved(SeatId),
+    /// events iced has no counterpart for, like pointer constraints or tablet pressure
+    Ignored,
 }
 
 #[derive(Debug)]
@@ -133,7 +189,7 @@ impl WindowEvent {
                 surface_y: y,
                 ..
             } => WindowEvent::CursorMoved { x: *x, y: *y },
-            DispatchMessage::MouseLeave => WindowEvent::CursorLeft,
+            DispatchMessage::MouseLeave { .. } => WindowEvent::CursorLeft,
             DispatchMessage::MouseButton { state, button, .. } => {
                 let btn = from_u32_to_icedmouse(*button);
                 match state {
@@ -177,13 +233,14 @@ impl WindowEvent {
             DispatchMessage::KeyboardInput {
                 event,
                 is_synthetic,
+                ..
             } => WindowEvent::KeyBoardInput {
                 event: event.clone(),
                 is_synthetic: *is_synthetic,
             },
-            DispatchMessage::Unfocus => WindowEvent::Unfocus,
-            DispatchMessage::Focused(_) => WindowEvent::Focused,
-            DispatchMessage::ModifiersChanged(modifiers) => {
+            DispatchMessage::Unfocus { .. } => WindowEvent::Unfocus,
+            DispatchMessage::Focused { .. } => WindowEvent::Focused,
+            DispatchMessage::ModifiersChanged { modifiers, .. } => {
                 WindowEvent::ModifiersChanged(*modifiers)
             }
             DispatchMessage::Axis {
@@ -194,6 +251,12 @@ impl WindowEvent {
             } => {
                 if horizontal.stop && vertical.stop {
                     WindowEvent::ScrollStop
+                } else if vertical.value120 != 0 || horizontal.value120 != 0 {
+                    // high-resolution wheels scroll by fractions of a line
+                    WindowEvent::Axis {
+                        x: (-horizontal.value120 as f64 / 120. * scale) as f32,
+                        y: (-vertical.value120 as f64 / 120. * scale) as f32,
+                    }
                 } else if vertical.discrete != 0 || horizontal.discrete != 0 {
                     WindowEvent::Axis {
                         x: (-horizontal.discrete as f64 * scale) as f32,
@@ -206,7 +269,26 @@ impl WindowEvent {
                     }
                 }
             }
-            DispatchMessage::Ime(ime) => WindowEvent::Ime(ime.clone()),
+            DispatchMessage::Compose { state, .. } => WindowEvent::Compose(match state {
+                ComposeState::Composing => Compose::Composing,
+                ComposeState::Composed => Compose::Composed,
+                ComposeState::Cancelled => Compose::Cancelled,
+            }),
+            DispatchMessage::KeyboardLayoutChanged { index, name, seat } => {
+                WindowEvent::KeyboardLayoutChanged {
+                    index: *index,
+                    name: name.clone(),
+                    layouts: ev.keyboard_layouts(*seat),
+                }
+            }
+            DispatchMessage::Ime {
+                ime: exwlshellev::Ime::DeleteSurrounding { before, after },
+                ..
+            } => WindowEvent::ImeDeleteSurrounding {
+                before: *before,
+                after: *after,
+            },
+            DispatchMessage::Ime { ime, .. } => WindowEvent::Ime(ime.clone()),
             DispatchMessage::OutputAdded(info) => WindowEvent::OutputAdded(info.clone()),
             DispatchMessage::OutputUpdated(info) => WindowEvent::OutputUpdated(info.clone()),
             DispatchMessage::OutputRemoved(info) => WindowEvent::OutputRemoved(info.clone()),
@@ -218,6 +300,123 @@ impl WindowEvent {
             DispatchMessage::Locked => WindowEvent::Locked,
             DispatchMessage::LockDenied => WindowEvent::LockDenied,
             DispatchMessage::LockFinished => WindowEvent::LockFinished,
+            DispatchMessage::ActivationToken(token) => WindowEvent::ActivationToken(token.clone()),
+            DispatchMessage::SwipeBegin { fingers, .. } => {
+                WindowEvent::Gesture(Gesture::SwipeBegin { fingers: *fingers })
+            }
+            DispatchMessage::SwipeUpdate { dx, dy, .. } => {
+                WindowEvent::Gesture(Gesture::SwipeUpdate { dx: *dx, dy: *dy })
+            }
+            DispatchMessage::SwipeEnd { cancelled, .. } => {
+                WindowEvent::Gesture(Gesture::SwipeEnd {
+                    cancelled: *cancelled,
+                })
+            }
+            DispatchMessage::PinchBegin { fingers, .. } => {
+                WindowEvent::Gesture(Gesture::PinchBegin { fingers: *fingers })
+            }
+            DispatchMessage::PinchUpdate {
+                dx,
+                dy,
+                scale,
+                rotation,
+                ..
+            } => WindowEvent::Gesture(Gesture::PinchUpdate {
+                dx: *dx,
+                dy: *dy,
+                scale: *scale,
+                rotation: *rotation,
+            }),
+            DispatchMessage::PinchEnd { cancelled, .. } => {
+                WindowEvent::Gesture(Gesture::PinchEnd {
+                    cancelled: *cancelled,
+                })
+            }
+            DispatchMessage::HoldBegin { fingers, .. } => {
+                WindowEvent::Gesture(Gesture::HoldBegin { fingers: *fingers })
+            }
+            DispatchMessage::RelativeMotion {
+                dx,
+                dy,
+                dx_unaccel,
+                dy_unaccel,
+                ..
+            } => WindowEvent::RelativeMotion {
+                dx: *dx,
+                dy: *dy,
+                dx_unaccel: *dx_unaccel,
+                dy_unaccel: *dy_unaccel,
+            },
+            DispatchMessage::PointerLocked
+            | DispatchMessage::PointerUnlocked
+            | DispatchMessage::PointerConfined
+            | DispatchMessage::PointerUnconfined
+            | DispatchMessage::TabletToolPressure { .. }
+            | DispatchMessage::TabletToolTilt { .. }
+            | DispatchMessage::TabletToolRotation { .. }
+            | DispatchMessage::TabletPadButton { .. }
+            | DispatchMessage::TabletPadRing { .. }
+            | DispatchMessage::TabletPadStrip { .. } => WindowEvent::Ignored,
+            DispatchMessage::TabletToolProximityIn { .. } => WindowEvent::TabletProximityIn,
+            DispatchMessage::TabletToolProximityOut { .. } => WindowEvent::TabletProximityOut,
+            DispatchMessage::TabletToolMotion {
+                surface_x,
+                surface_y,
+                ..
+            } => WindowEvent::TabletMotion {
+                x: *surface_x,
+                y: *surface_y,
+            },
+            // the tip acts as the left button
+            DispatchMessage::TabletToolDown { .. } => {
+                WindowEvent::TabletButton(IcedButtonState::Pressed(mouse::Button::Left))
+            }
+            DispatchMessage::TabletToolUp { .. } => {
+                WindowEvent::TabletButton(IcedButtonState::Released(mouse::Button::Left))
+            }
+            DispatchMessage::TabletToolButton {
+                button, pressed, ..
+            } => {
+                let btn = from_stylus_to_icedmouse(*button);
+                if *pressed {
+                    WindowEvent::TabletButton(IcedButtonState::Pressed(btn))
+                } else {
+                    WindowEvent::TabletButton(IcedButtonState::Released(btn))
+                }
+            }
+            DispatchMessage::HoldEnd { cancelled, .. } => WindowEvent::Gesture(Gesture::HoldEnd {
+                cancelled: *cancelled,
+            }),
+            // the drag drives the cursor, so the widgets under it see it hover
+            DispatchMessage::DndEnter { x, y, .. } => WindowEvent::CursorEnter { x: *x, y: *y },
+            DispatchMessage::DndMotion { x, y, .. } => WindowEvent::CursorMoved { x: *x, y: *y },
+            DispatchMessage::DndLeave { .. } => WindowEvent::FilesHoveredLeft,
+            // the files are read when the drag enters and again once dropped, see `multi_window`
+            DispatchMessage::DndData {
+                mime_type,
+                data,
+                dropped,
+                ..
+            } if mime_type == URI_LIST_MIME_TYPE => {
+                let paths = parse_uri_list(data);
+                if *dropped {
+                    WindowEvent::FilesDropped(paths)
+                } else {
+                    WindowEvent::FilesHovered(paths)
+                }
+            }
+            DispatchMessage::DndDrop { .. }
+            | DispatchMessage::DndData { .. }
+            | DispatchMessage::SelectionData { .. }
+            | DispatchMessage::DragFinished { .. }
+            | DispatchMessage::ToplevelStateChanged(_)
+            | DispatchMessage::WmCapabilities(_)
+            | DispatchMessage::DecorationModeChanged { .. }
+            | DispatchMessage::InputMethodDone(_)
+            | DispatchMessage::InputMethodUnavailable
+            | DispatchMessage::InputPopupTextRectangle { .. } => WindowEvent::Ignored,
+            DispatchMessage::SeatAdded(seat) => WindowEvent::SeatAdded(SeatId((*seat).into())),
+            DispatchMessage::SeatRemoved(seat) => WindowEvent::SeatRemoved(SeatId((*seat).into())),
         }
     }
 }
@@ -1,5 +1,10 @@
+use iced_core::widget::{
+    Id, Operation,
+    operation::{Focusable, TextInput},
+};
 use iced_core::{
-    Color, Padding, Point, Rectangle, Size, Text, Vector, alignment, input_method, renderer, text,
+    Color, Padding, Pixels, Point, Rectangle, Size, Text, Vector, alignment, input_method,
+    renderer, text,
 };
 
 use enumflags2::bitflags;
@@ -143,3 +148,105 @@ where
         });
     }
 }
+
+/// The text input method state of iced leaves out the text and the cursor, this reads the text
+/// from the focused text input, with its bounds to find it again, and [`FocusedText::locate`]
+/// finds the cursor under the caret iced reports.
+#[derive(Default)]
+pub struct FocusedText {
+    /// the text input operated on last, `focusable` comes right after `text_input`
+    candidate: Option<(Rectangle, String)>,
+    found: Option<(Rectangle, String)>,
+}
+
+impl FocusedText {
+    /// The bounds and text of the focused text input, with the byte offset of the grapheme
+    /// boundary iced draws its `caret` at. The text is laid out the way a text input with the
+    /// default font, uniform padding and the default alignment lays it out, `None` if no single
+    /// boundary lies under the caret, as in scrolled text.
+    ///
+    /// An empty text input shows its placeholder with the cursor at the start, and iced does not
+    /// tell the two apart, so with the cursor at the start the text is reported as empty.
+    pub fn locate<Renderer: text::Renderer>(
+        self,
+        caret: Rectangle,
+        renderer: &Renderer,
+    ) -> Option<(Rectangle, String, usize)> {
+        use text::Paragraph as _;
+        use unicode_segmentation::UnicodeSegmentation;
+
+        let (bounds, text) = self.found?;
+        // the caret is one line high, and the padding on the left is the one on top
+        let line_height = text::LineHeight::default();
+        let origin = bounds.x + (caret.y - bounds.y);
+        let paragraph = Renderer::Paragraph::with_text(Text {
+            content: &text,
+            bounds: Size::INFINITE,
+            size: Pixels(caret.height / line_height.to_absolute(Pixels(1.0)).0),
+            line_height,
+            font: renderer.default_font(),
+            align_x: text::Alignment::Default,
+            align_y: alignment::Vertical::Top,
+            shaping: text::Shaping::Advanced,
+            wrapping: text::Wrapping::None,
+        });
+        let mut under_caret = text
+            .grapheme_indices(true)
+            .map(|(offset, _)| offset)
+            .chain([text.len()])
+            .enumerate()
+            .filter(|(index, _)| {
+                let position = paragraph
+                    .grapheme_position(0, *index)
+                    .unwrap_or(Point::ORIGIN);
+                (origin + position.x).floor() == caret.x
+            })
+            .map(|(_, offset)| offset);
+        let cursor = under_caret
+            .next()
+            .filter(|_| under_caret.next().is_none())?;
+        if cursor == 0 {
+            return Some((bounds, String::new(), 0));
+        }
+        Some((bounds, text, cursor))
+    }
+}
+
+impl Operation for FocusedText {
+    fn traverse(&mut self, operate: &mut dyn FnMut(&mut dyn Operation)) {
+        if self.found.is_none() {
+            operate(self);
+        }
+    }
+
+    fn text_input(&mut self, _id: Option<&Id>, bounds: Rectangle, state: &mut dyn TextInput) {
+        self.candidate = Some((bounds, state.text().to_owned()));
+    }
+
+    fn focusable(&mut self, _id: Option<&Id>, bounds: Rectangle, state: &mut dyn Focusable) {
+        let candidate = self.candidate.take();
+        if state.is_focused() {
+            self.found = candidate.filter(|(text_bounds, _)| *text_bounds == bounds);
+        }
+    }
+}
+
+/// Select the graphemes `start..end` of the text input at `bounds`, so the next commit of the
+/// input method replaces them.
+pub struct SelectRange {
+    pub bounds: Rectangle,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Operation for SelectRange {
+    fn traverse(&mut self, operate: &mut dyn FnMut(&mut dyn Operation)) {
+        operate(self);
+    }
+
+    fn text_input(&mut self, _id: Option<&Id>, bounds: Rectangle, state: &mut dyn TextInput) {
+        if bounds == self.bounds {
+            state.select_range(self.start, self.end);
+        }
+    }
+}
@@ -4,6 +4,7 @@ pub mod actions;
 pub mod build_pattern;
 mod clipboard;
 mod conversion;
+mod csd;
 mod error;
 mod event;
 mod multi_window;
@@ -47,9 +48,9 @@ pub use iced_wayland_subscriber::shell;
 /// Hook invoked for every surface the runtime materializes
 pub type NewShellHook<Message> = Box<dyn Fn(shell::ShellInfo) -> Option<Message>>;
 
-/// Opt-out for clipboard initialization. Call this before starting the
-/// runtime when your app has no text input and doesn't need paste/copy —
-/// this skips spawning the always-on smithay-clipboard worker thread.
+/// Opt-out for clipboard access. Call this before starting the runtime when
+/// your app should never read or write the clipboard. The clipboard shares the
+/// shell connection, so leaving it on costs no extra thread.
 pub fn disable_clipboard() {
     clipboard::set_disabled();
 }
@@ -2,22 +2,24 @@ use crate::reexport::{PopupAnchor, PopupConstraintAdjustment};
 use crate::{
     DefaultStyle,
     actions::{ExwlShellCustomActionWithId, IcedNewPopupSettings},
-    ime_preedit::ImeState,
+    ime_preedit::{FocusedText, ImeState, SelectRange},
     multi_window::window_manager::WindowManager,
     settings::VirtualKeyboardSettings,
     user_interface::UserInterfaces,
 };
 use crate::{
-    actions::ExwlShellCustomAction, clipboard::ExwlShellClipboard, conversion, error::Error,
+    actions::ExwlShellCustomAction, clipboard::ExwlShellClipboard, conversion, csd, error::Error,
 };
 use crate::{
     event::{IcedWlShellEvent, WindowEvent as ExwlShellWindowEvent},
     proxy::IcedProxy,
     settings::Settings,
 };
+use exwlshellev::dnd::URI_LIST_MIME_TYPE;
 use exwlshellev::{
-    DisplayWrapper, ExWlShellEvent, NewPopUpSettings, PopUpRepositionSettings, PopupPlacement,
-    RefreshRequest, ReturnData, WindowState, WindowWrapper,
+    DispatchMessage, DisplayWrapper, ExWlShellEvent, NewPopUpSettings, PixelSize,
+    PopUpRepositionSettings, PopupPlacement, RefreshRequest, ReturnData, WindowState,
+    WindowStateUnit, WindowWrapper, WlShellType,
     id::Id as LayerShellId,
     reexport::{
         wayland_client::{WlCompositor, WlRegion},
@@ -30,7 +32,7 @@ use futures::StreamExt;
 use iced_core::theme::Mode;
 use iced_core::{
     Event as IcedEvent, theme,
-    window::{Event as IcedWindowEvent, Id as IcedId, RedrawRequest},
+    window::{Event as IcedWindowEvent, Id as IcedId, Mode as WindowMode, RedrawRequest},
 };
 use iced_core::{Size, mouse::Cursor};
 use iced_futures::{Executor, Runtime};
@@ -45,7 +47,6 @@ use std::{
     borrow::Cow,
     collections::{HashMap, VecDeque},
     mem,
-    os::fd::AsFd,
     sync::Arc,
     time::Duration,
 };
@@ -188,6 +189,8 @@ where
     boot_span.finish();
 
     let mut waiting_layer_shell_events = VecDeque::new();
+    // handed over once the globals are bound
+    let mut virtual_keyboard_support = settings.virtual_keyboard_support;
 
     ev.running_with_proxy(message_receiver, move |event, ev, layer_shell_id| {
         let mut def_returndata = ReturnData::None;
@@ -204,7 +207,7 @@ where
                     IcedWlShellEvent::UpdateInputRegion(wl_compositor.create_region(qh, ())),
                 ));
 
-                if let Some(virtual_keyboard_setting) = settings.virtual_keyboard_support.as_ref() {
+                if let Some(virtual_keyboard_setting) = virtual_keyboard_support.take() {
                     let virtual_keyboard_manager = globals
                         .bind::<zwp_virtual_keyboard_v1::ZwpVirtualKeyboardManagerV1, _, _>(
                             qh,
@@ -220,11 +223,30 @@ where
                     let seat = ev.get_seat();
                     let virtual_keyboard_in =
                         virtual_keyboard_manager.create_virtual_keyboard(seat, qh, ());
-                    virtual_keyboard_in.keymap((*keymap_format).into(), file.as_fd(), *keymap_size);
                     ev.set_virtual_keyboard(virtual_keyboard_in);
+                    ev.set_virtual_keyboard_keymap(keymap_format, file, keymap_size);
                 }
             }
             ExWlShellEvent::RequestMessages(message) => {
+                // files are accepted and read while they hover, and read again once dropped
+                match message {
+                    DispatchMessage::DndEnter { mime_types, .. }
+                        if mime_types
+                            .iter()
+                            .any(|mime_type| mime_type == URI_LIST_MIME_TYPE) =>
+                    {
+                        ev.accept_dnd(Some(URI_LIST_MIME_TYPE));
+                        ev.receive_dnd(URI_LIST_MIME_TYPE);
+                    }
+                    DispatchMessage::DndDrop { mime_types, .. }
+                        if mime_types
+                            .iter()
+                            .any(|mime_type| mime_type == URI_LIST_MIME_TYPE) =>
+                    {
+                        ev.receive_dnd(URI_LIST_MIME_TYPE);
+                    }
+                    _ => {}
+                }
                 let window_event = ExwlShellWindowEvent::from_dispatch(message, ev);
                 waiting_layer_shell_events
                     .push_back((layer_shell_id, IcedWlShellEvent::Window(window_event)));
@@ -291,7 +313,6 @@ where
     compositor: Option<C>,
     window_manager: WindowManager<P, C>,
     cached_layer_dimensions: HashMap<IcedId, (Size<u32>, f32)>,
-    clipboard: ExwlShellClipboard,
     wl_input_region: Option<WlRegion>,
     user_interfaces: UserInterfaces<P>,
     waiting_layer_shell_actions: Vec<(Option<IcedId>, ExwlShellCustomAction)>,
@@ -333,7 +354,6 @@ where
             compositor: Default::default(),
             window_manager: WindowManager::new(),
             cached_layer_dimensions: HashMap::new(),
-            clipboard: ExwlShellClipboard::unconnected(),
             wl_input_region: Default::default(),
             user_interfaces: UserInterfaces::new(application),
             waiting_layer_shell_actions: Default::default(),
@@ -363,19 +383,10 @@ where
             new_compositor.load_font(font);
         }
         self.compositor = Some(new_compositor);
-        // Opt-out for clients that don't need clipboard access. Skipping
-        // connect() avoids spawning the smithay-clipboard worker thread,
-        // which otherwise runs an always-on wayland event loop (~0.4% CPU).
-        self.clipboard = if crate::clipboard::is_disabled() {
-            ExwlShellClipboard::unconnected()
-        } else {
-            ExwlShellClipboard::connect(&window)
-        };
     }
 
     fn remove_compositor(&mut self) {
         self.compositor = None;
-        self.clipboard = ExwlShellClipboard::unconnected();
     }
 
     fn handle_event(
@@ -444,6 +455,12 @@ where
         let unit_id = ex_wlshell_window.id();
         let (width, height) = ex_wlshell_window.get_size();
         let scale_float = ex_wlshell_window.scale_float();
+        let decorated_toplevel = ex_wlshell_window.is_client_side_decorated().then(|| {
+            (
+                ex_wlshell_window.toplevel_state(),
+                ex_wlshell_window.wm_capabilities(),
+            )
+        });
         // events may not be handled after RequestRefreshWithWrapper in the same
         // interaction, we dispatched them immediately.
         let mut events = Vec::new();
@@ -473,11 +490,12 @@ where
                     .copied()
                     .unwrap_or_else(IcedId::unique);
                 let shell_type = match ex_wlshell_window.wl_shell_type() {
-                    exwlshellev::WlShellType::LayerShell => shell::ShellType::LayerShell,
-                    exwlshellev::WlShellType::PopUp => shell::ShellType::PopUp,
-                    exwlshellev::WlShellType::XdgTopLevel => shell::ShellType::XdgTopLevel,
-                    exwlshellev::WlShellType::InputPanel => shell::ShellType::InputPanel,
-                    exwlshellev::WlShellType::SessionLock => shell::ShellType::SessionLock,
+                    WlShellType::LayerShell => shell::ShellType::LayerShell,
+                    WlShellType::PopUp => shell::ShellType::PopUp,
+                    WlShellType::XdgTopLevel => shell::ShellType::XdgTopLevel,
+                    WlShellType::InputPanel => shell::ShellType::InputPanel,
+                    WlShellType::InputMethodPopup => shell::ShellType::InputMethodPopup,
+                    WlShellType::SessionLock => shell::ShellType::SessionLock,
                 };
                 let info = shell::ShellInfo {
                     window: iced_id,
@@ -567,6 +585,17 @@ where
                 (iced_id, window)
             };
 
+        if self.user_interfaces.sync_frame(iced_id, decorated_toplevel)
+            && let Some(cache) = self.user_interfaces.remove(&iced_id)
+        {
+            self.user_interfaces.build(
+                iced_id,
+                cache,
+                &mut window.renderer,
+                window.state.viewport().logical_size(),
+            );
+        }
+
         let compositor = self
             .compositor
             .as_mut()
@@ -592,7 +621,7 @@ where
             &events,
             cursor,
             &mut window.renderer,
-            &mut self.clipboard,
+            &mut ExwlShellClipboard::new(ev),
             &mut self.messages,
         );
 
@@ -643,8 +672,28 @@ where
         // get layer_shell_id so that layer_shell_window can be drop, and ev can be borrow mut
         let layer_shell_id = unit_id;
 
+        // never hand the text of a password field to the input method
+        let focused_text = match &ui_state {
+            user_interface::State::Updated {
+                input_method:
+                    iced_core::InputMethod::Enabled {
+                        cursor, purpose, ..
+                    },
+                ..
+            } if *purpose != iced_core::input_method::Purpose::Secure => {
+                let mut operation = FocusedText::default();
+                ui.operate(&window.renderer, &mut operation);
+                operation.locate(*cursor, &window.renderer)
+            }
+            _ => None,
+        };
+
         Self::handle_ui_state(ev, window, ui_state, false, true);
 
+        if let Some((text, cursor)) = window.sync_surrounding_text(focused_text) {
+            ev.set_ime_surrounding_text(text, cursor, cursor, layer_shell_id);
+        }
+
         window.draw_preedit();
 
         let present_span = iced_debug::present(iced_id);
@@ -711,6 +760,7 @@ where
         self.cached_layer_dimensions.remove(&iced_id);
         self.window_manager.remove(iced_id);
         self.user_interfaces.remove(&iced_id);
+        self.user_interfaces.sync_frame(iced_id, None);
         self.iced_events.retain(|(id, _)| *id != iced_id);
         self.waiting_layer_shell_actions
             .retain(|(id, _)| *id != Some(iced_id));
@@ -761,6 +811,29 @@ where
                 self.shell_broadcast.send(shell::ShellEvent::LockedFinished);
                 return;
             }
+            ExwlShellWindowEvent::SeatAdded(seat) => {
+                self.shell_broadcast
+                    .send(shell::ShellEvent::SeatAdded(*seat));
+                return;
+            }
+            ExwlShellWindowEvent::SeatRemoved(seat) => {
+                self.shell_broadcast
+                    .send(shell::ShellEvent::SeatRemoved(*seat));
+                return;
+            }
+            ExwlShellWindowEvent::KeyboardLayoutChanged {
+                index,
+                name,
+                layouts,
+            } => {
+                self.shell_broadcast
+                    .send(shell::ShellEvent::KeyboardLayoutChanged {
+                        index: *index,
+                        name: name.clone(),
+                        layouts: layouts.clone(),
+                    });
+                return;
+            }
             _ => {}
         }
         let id_and_window = if let Some(layer_shell_id) = layer_shell_id {
@@ -771,6 +844,103 @@ where
         let Some((iced_id, window)) = id_and_window else {
             return;
         };
+        if let ExwlShellWindowEvent::ActivationToken(token) = &event {
+            self.shell_broadcast
+                .send(shell::ShellEvent::ActivationToken {
+                    window: iced_id,
+                    token: token.clone(),
+                });
+            return;
+        }
+        if let ExwlShellWindowEvent::Gesture(gesture) = &event {
+            self.shell_broadcast.send(shell::ShellEvent::Gesture {
+                window: iced_id,
+                gesture: *gesture,
+            });
+            return;
+        }
+        if let ExwlShellWindowEvent::Compose(state) = &event {
+            self.shell_broadcast.send(shell::ShellEvent::Compose {
+                window: iced_id,
+                state: *state,
+            });
+            return;
+        }
+        match event {
+            ExwlShellWindowEvent::RelativeMotion {
+                dx,
+                dy,
+                dx_unaccel,
+                dy_unaccel,
+            } => {
+                self.shell_broadcast
+                    .send(shell::ShellEvent::RelativeMotion {
+                        window: iced_id,
+                        dx,
+                        dy,
+                        dx_unaccel,
+                        dy_unaccel,
+                    });
+                return;
+            }
+            ExwlShellWindowEvent::FilesHovered(paths) => {
+                self.iced_events.extend(paths.into_iter().map(|path| {
+                    (
+                        iced_id,
+                        IcedEvent::Window(IcedWindowEvent::FileHovered(path)),
+                    )
+                }));
+                return;
+            }
+            ExwlShellWindowEvent::FilesHoveredLeft => {
+                // the cursor the drag drove leaves with it
+                window.state.update(
+                    &ExwlShellWindowEvent::CursorLeft,
+                    self.user_interfaces.application(),
+                );
+                self.iced_events.extend([
+                    (
+                        iced_id,
+                        IcedEvent::Window(IcedWindowEvent::FilesHoveredLeft),
+                    ),
+                    (
+                        iced_id,
+                        IcedEvent::Mouse(iced_core::mouse::Event::CursorLeft),
+                    ),
+                ]);
+                return;
+            }
+            ExwlShellWindowEvent::FilesDropped(paths) => {
+                self.iced_events.extend(paths.into_iter().map(|path| {
+                    (
+                        iced_id,
+                        IcedEvent::Window(IcedWindowEvent::FileDropped(path)),
+                    )
+                }));
+                return;
+            }
+            ExwlShellWindowEvent::ImeDeleteSurrounding { before, after } => {
+                let Some((bounds, start, end)) = window.ime_delete_surrounding(before, after)
+                else {
+                    return;
+                };
+                if let Some(mut ui) = self.user_interfaces.ui_mut(&iced_id) {
+                    ui.operate(&window.renderer, &mut SelectRange { bounds, start, end });
+                }
+                // an empty commit removes the selection, the commit of the input method that
+                // may follow is inserted in its place
+                self.iced_events.push((
+                    iced_id,
+                    IcedEvent::InputMethod(iced_core::input_method::Event::Commit(String::new())),
+                ));
+                return;
+            }
+            ExwlShellWindowEvent::Ignored => return,
+            _ => {}
+        }
+        if let ExwlShellWindowEvent::Ime(exwlshellev::Ime::Commit(text)) = &event {
+            window.ime_commit(text);
+        }
         if let ExwlShellWindowEvent::OutputChanged(output) = &event {
             self.shell_broadcast
                 .send(shell::ShellEvent::WindowOutputChanged {
@@ -800,7 +970,6 @@ where
             &mut self.compositor,
             action,
             &mut self.messages,
-            &mut self.clipboard,
             &mut self.waiting_layer_shell_actions,
             &mut should_exit,
             &mut self.window_manager,
@@ -858,6 +1027,15 @@ where
                 ref_mut_exshell_window!(ev, iced_id, ex_shell_id, layer_shell_window);
                 exshell_window.set_blur_option(blur_option);
             }
+            ExwlShellCustomAction::IdleInhibit(enabled) => {
+                ref_mut_exshell_window!(ev, iced_id, ex_shell_id, layer_shell_window);
+                if exshell_window.set_idle_inhibit(enabled).is_none() {
+                    tracing::warn!(
+                        "idle-inhibit-unstable-v1 is not supported, window_id: {:?}",
+                        iced_id
+                    );
+                }
+            }
             ExwlShellCustomAction::LayoutChange { anchor, size } => {
                 ref_mut_exshell_window!(ev, iced_id, ex_shell_id, layer_shell_window);
                 exshell_window.set_layout(anchor, size);
@@ -915,6 +1093,71 @@ where
                     key,
                 });
             }
+            ExwlShellCustomAction::VirtualKeyboardType(text) => {
+                if ev.virtual_keyboard_type(&text).is_none() {
+                    tracing::warn!("cannot type on the virtual keyboard");
+                }
+            }
+            ExwlShellCustomAction::VirtualKeyboardKey { key, pressed } => {
+                use exwlshellev::reexport::wayland_client::KeyState;
+                let state = if pressed {
+                    KeyState::Pressed
+                } else {
+                    KeyState::Released
+                };
+                if ev.virtual_keyboard_key(key, state).is_none() {
+                    tracing::warn!("cannot press key {key} on the virtual keyboard");
+                }
+            }
+            ExwlShellCustomAction::VirtualKeyboardModifiers {
+                depressed,
+                latched,
+                locked,
+            } => {
+                if ev
+                    .set_virtual_keyboard_modifiers(depressed, latched, locked)
+                    .is_none()
+                {
+                    tracing::warn!("cannot set the modifiers of the virtual keyboard");
+                }
+            }
+            ExwlShellCustomAction::VirtualPointerMotion { dx, dy } => {
+                if ev.virtual_pointer_motion(dx, dy).is_none() {
+                    tracing::warn!("cannot move the virtual pointer");
+                }
+            }
+            ExwlShellCustomAction::VirtualPointerMotionAbsolute {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                if ev
+                    .virtual_pointer_motion_absolute(x, y, width, height)
+                    .is_none()
+                {
+                    tracing::warn!("cannot move the virtual pointer");
+                }
+            }
+            ExwlShellCustomAction::VirtualPointerButton { button, pressed } => {
+                use exwlshellev::reexport::wayland_client::ButtonState;
+                let state = if pressed {
+                    ButtonState::Pressed
+                } else {
+                    ButtonState::Released
+                };
+                if ev.virtual_pointer_button(button, state).is_none() {
+                    tracing::warn!("cannot press the button {button} of the virtual pointer");
+                }
+            }
+            ExwlShellCustomAction::VirtualPointerAxis {
+                horizontal,
+                vertical,
+            } => {
+                if ev.virtual_pointer_scroll(horizontal, vertical).is_none() {
+                    tracing::warn!("cannot scroll the virtual pointer");
+                }
+            }
             ExwlShellCustomAction::NewLayerShell {
                 settings,
                 id: iced_id,
@@ -1059,6 +1302,33 @@ where
             ExwlShellCustomAction::ForgetLastOutput => {
                 ev.forget_last_output();
             }
+            ExwlShellCustomAction::RequestActivationToken => {
+                ref_mut_exshell_window!(ev, iced_id, ex_shell_id, layer_shell_window);
+                let id = exshell_window.id();
+                if ev.request_activation_token(id).is_none() {
+                    tracing::warn!(
+                        "xdg-activation-v1 is not supported, window_id: {:?}",
+                        iced_id
+                    );
+                }
+            }
+            ExwlShellCustomAction::ActivateWithToken { token } => {
+                ref_mut_exshell_window!(ev, iced_id, ex_shell_id, layer_shell_window);
+                let id = exshell_window.id();
+                if ev.activate_with_token(id, &token).is_none() {
+                    tracing::warn!(
+                        "xdg-activation-v1 is not supported, window_id: {:?}",
+                        iced_id
+                    );
+                }
+            }
+            ExwlShellCustomAction::StartDrag { mime_types, data } => {
+                ref_mut_exshell_window!(ev, iced_id, ex_shell_id, layer_shell_window);
+                let id = exshell_window.id();
+                if ev.start_drag(id, mime_types, data, None).is_none() {
+                    tracing::warn!("cannot start a drag, window_id: {:?}", iced_id);
+                }
+            }
         }
     }
 
@@ -1093,9 +1363,12 @@ where
                     &window_events,
                     window.state.cursor(),
                     &mut window.renderer,
-                    &mut self.clipboard,
+                    &mut ExwlShellClipboard::new(ev),
                     &mut self.messages,
                 );
+            if let Some(request) = self.user_interfaces.take_frame_request(&iced_id) {
+                csd::apply_request(ev, window.id, request);
+            }
 
             #[cfg(feature = "unconditional-rendering")]
             let unconditional_rendering = true;
@@ -1278,13 +1551,27 @@ pub(crate) fn update<P: IcedProgram, E: Executor>(
     runtime.track(recipes);
 }
 
+/// The exwlshellev unit of an iced window, to send xdg toplevel requests to
+fn toplevel_unit<'a, P, C>(
+    window_manager: &WindowManager<P, C>,
+    ev: &'a mut WindowState<IcedId>,
+    id: IcedId,
+) -> Option<&'a mut WindowStateUnit<IcedId>>
+where
+    P: IcedProgram,
+    C: Compositor<Renderer = P::Renderer>,
+    P::Theme: DefaultStyle,
+{
+    let window = window_manager.get(id)?;
+    ev.get_mut_unit_with_id(window.id)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn run_action<P, C, E: Executor>(
     user_interfaces: &mut UserInterfaces<P>,
     compositor: &mut Option<C>,
     event: Action<P::Message>,
     messages: &mut Vec<P::Message>,
-    clipboard: &mut ExwlShellClipboard,
     waiting_layer_shell_actions: &mut Vec<(Option<iced_core::window::Id>, ExwlShellCustomAction)>,
     should_exit: &mut bool,
     window_manager: &mut WindowManager<P, C>,
@@ -1326,10 +1613,10 @@ pub(crate) fn run_action<P, C, E: Executor>(
         },
         Action::Clipboard(action) => match action {
             clipboard::Action::Read { target, channel } => {
-                let _ = channel.send(clipboard.read(target));
+                let _ = channel.send(ExwlShellClipboard::new(ev).read(target));
             }
             clipboard::Action::Write { target, contents } => {
-                clipboard.write(target, contents);
+                ExwlShellClipboard::new(ev).write(target, contents);
             }
         },
         Action::Widget(action) => {
@@ -1393,6 +1680,95 @@ pub(crate) fn run_action<P, C, E: Executor>(
                     let _ = channel.send(window.state.wayland_scale_factor() as f32);
                 };
             }
+            WindowAction::GetMaximized(id, channel) => {
+                let maximized = window_manager
+                    .get(id)
+                    .and_then(|window| ev.get_unit_with_id(window.id))
+                    .is_some_and(|unit| unit.toplevel_state().maximized);
+                let _ = channel.send(maximized);
+            }
+            WindowAction::GetMode(id, channel) => {
+                let fullscreen = window_manager
+                    .get(id)
+                    .and_then(|window| ev.get_unit_with_id(window.id))
+                    .is_some_and(|unit| unit.toplevel_state().fullscreen);
+                let _ = channel.send(if fullscreen {
+                    WindowMode::Fullscreen
+                } else {
+                    WindowMode::Windowed
+                });
+            }
+            WindowAction::Maximize(id, maximized) => {
+                if let Some(unit) = toplevel_unit(window_manager, ev, id) {
+                    unit.set_maximized(maximized);
+                }
+            }
+            WindowAction::ToggleMaximize(id) => {
+                if let Some(unit) = toplevel_unit(window_manager, ev, id) {
+                    unit.set_maximized(!unit.toplevel_state().maximized);
+                }
+            }
+            WindowAction::Minimize(id, true) => {
+                if let Some(unit) = toplevel_unit(window_manager, ev, id) {
+                    unit.set_minimized();
+                }
+            }
+            WindowAction::SetMode(id, mode) => {
+                if let Some(unit) = toplevel_unit(window_manager, ev, id) {
+                    match mode {
+                        WindowMode::Fullscreen => unit.set_fullscreen(true, None),
+                        WindowMode::Windowed => unit.set_fullscreen(false, None),
+                        WindowMode::Hidden => unit.set_minimized(),
+                    };
+                }
+            }
+            WindowAction::Resize(id, size) => {
+                let size = PixelSize::try_px(size.width as u32, size.height as u32);
+                if let (Some(unit), Some(size)) = (toplevel_unit(window_manager, ev, id), size) {
+                    unit.resize_toplevel(size);
+                }
+            }
+            WindowAction::SetMinSize(id, size) => {
+                let size =
+                    size.and_then(|size| PixelSize::try_px(size.width as u32, size.height as u32));
+                if let Some(unit) = toplevel_unit(window_manager, ev, id) {
+                    unit.set_min_size(size);
+                }
+            }
+            WindowAction::SetMaxSize(id, size) => {
+                let size =
+                    size.and_then(|size| PixelSize::try_px(size.width as u32, size.height as u32));
+                if let Some(unit) = toplevel_unit(window_manager, ev, id) {
+                    unit.set_max_size(size);
+                }
+            }
+            WindowAction::Drag(id) => {
+                if let Some(window) = window_manager.get(id)
+                    && ev.start_move(window.id).is_none()
+                {
+                    tracing::warn!("cannot move window {id:?} without a pressed button");
+                }
+            }
+            WindowAction::DragResize(id, direction) => {
+                if let Some(window) = window_manager.get(id)
+                    && ev
+                        .start_resize(window.id, conversion::resize_edge(direction))
+                        .is_none()
+                {
+                    tracing::warn!("cannot resize window {id:?} without a pressed button");
+                }
+            }
+            WindowAction::ShowSystemMenu(id) => {
+                if let Some(window) = window_manager.get(id) {
+                    let (x, y) = window
+                        .state
+                        .mouse_position()
+                        .map_or((0, 0), |point| (point.x as i32, point.y as i32));
+                    if ev.show_window_menu(window.id, x, y).is_none() {
+                        tracing::warn!("cannot show the menu of window {id:?}");
+                    }
+                }
+            }
             _ => {}
         },
         Action::System(action) => match action {
@@ -157,10 +157,11 @@ where
 
     pub fn update(&mut self, event: &WindowEvent, application: &Instance<P>) {
         match event {
-            WindowEvent::CursorLeft => {
+            WindowEvent::CursorLeft | WindowEvent::TabletProximityOut => {
                 self.mouse_position = None;
             }
             WindowEvent::CursorMoved { x, y }
+            | WindowEvent::TabletMotion { x, y }
             | WindowEvent::CursorEnter { x, y }
             | WindowEvent::TouchMotion { x, y, .. }
             | WindowEvent::TouchDown { x, y, .. }
@@ -29,6 +29,17 @@ where
     pub mouse_interaction: mouse::Interaction,
     preedit: Option<Preedit<P::Renderer>>,
     ime_state: Option<(iced_core::Rectangle, input_method::Purpose)>,
+    surrounding_text: Option<SurroundingText>,
+}
+
+/// The text of the focused text input, as told to the input method.
+struct SurroundingText {
+    bounds: iced_core::Rectangle,
+    text: String,
+    /// byte offset of the cursor, found under the caret of the text input
+    cursor: usize,
+    /// changed by the input method and not told yet
+    changed: bool,
 }
 
 pub struct WindowManager<P: Program, C: Compositor>
@@ -107,6 +118,7 @@ where
                 mouse_interaction: mouse::Interaction::Idle,
                 preedit: None,
                 ime_state: None,
+                surrounding_text: None,
             },
         );
         self.entries
@@ -210,6 +222,67 @@ where
         }
     }
 
+    /// Follow the text and cursor of the focused text input, found with [`FocusedText`],
+    /// returns them to tell the input method when they changed.
+    ///
+    /// [`FocusedText`]: crate::ime_preedit::FocusedText
+    pub fn sync_surrounding_text(
+        &mut self,
+        focused: Option<(iced_core::Rectangle, String, usize)>,
+    ) -> Option<(&str, usize)> {
+        let Some((bounds, text, cursor)) = focused else {
+            self.surrounding_text = None;
+            return None;
+        };
+        let unchanged = self.surrounding_text.as_ref().is_some_and(|surrounding| {
+            !surrounding.changed
+                && surrounding.bounds == bounds
+                && surrounding.text == text
+                && surrounding.cursor == cursor
+        });
+        let surrounding = self.surrounding_text.insert(SurroundingText {
+            bounds,
+            text,
+            cursor,
+            changed: false,
+        });
+        (!unchanged).then_some((&surrounding.text, surrounding.cursor))
+    }
+
+    /// The input method inserts `text` at the cursor.
+    pub fn ime_commit(&mut self, text: &str) {
+        if let Some(surrounding) = &mut self.surrounding_text {
+            surrounding.text.insert_str(surrounding.cursor, text);
+            surrounding.cursor += text.len();
+            surrounding.changed = true;
+        }
+    }
+
+    /// The input method deletes `before` bytes before the cursor and `after` after it. Returns
+    /// the bounds of the text input and the graphemes to select, for the commit that follows to
+    /// replace them.
+    pub fn ime_delete_surrounding(
+        &mut self,
+        before: usize,
+        after: usize,
+    ) -> Option<(iced_core::Rectangle, usize, usize)> {
+        use unicode_segmentation::UnicodeSegmentation;
+
+        let surrounding = self.surrounding_text.as_mut()?;
+        let text = &surrounding.text;
+        let start = surrounding.cursor.saturating_sub(before);
+        let end = (surrounding.cursor + after).min(text.len());
+        if !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            return None;
+        }
+        let graphemes = |offset: usize| text[..offset].graphemes(true).count();
+        let selection = (surrounding.bounds, graphemes(start), graphemes(end));
+        surrounding.text.replace_range(start..end, "");
+        surrounding.cursor = start;
+        surrounding.changed = true;
+        Some(selection)
+    }
+
     fn update_ime(&mut self, cursor: iced_core::Rectangle, purpose: input_method::Purpose) {
         if self.ime_state != Some((cursor, purpose)) {
             self.ime_state = Some((cursor, purpose));
@@ -227,6 +300,7 @@ where
         }
 
         self.preedit = None;
+        self.surrounding_text = None;
         flags
     }
 }
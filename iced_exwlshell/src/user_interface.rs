@@ -5,7 +5,10 @@ use iced_runtime::{
     UserInterface as IcedUserInterface,
     user_interface::{Cache, State},
 };
-use std::{collections::HashMap, mem};
+use std::{cell::RefCell, collections::HashMap, mem, rc::Rc};
+
+use crate::csd;
+use exwlshellev::{ToplevelState, WmCapabilities};
 
 pub(crate) trait UserInterfaceReclaim<Message, Theme, Renderer> {
     fn reclaim(&mut self, ui: IcedUserInterface<'static, Message, Theme, Renderer>);
@@ -91,6 +94,8 @@ pub struct UserInterfaces<P: Program> {
     // reference to application won't be leaked to public.
     #[allow(clippy::type_complexity)]
     uis: HashMap<Id, IcedUserInterface<'static, P::Message, P::Theme, P::Renderer>>,
+    /// windows drawing their own frame
+    frames: HashMap<Id, csd::Frame>,
     application: Instance<P>,
 }
 
@@ -101,6 +106,7 @@ where
     pub fn new(application: Instance<P>) -> Self {
         Self {
             uis: HashMap::new(),
+            frames: HashMap::new(),
             application,
         }
     }
@@ -135,9 +141,45 @@ where
         })
     }
 
+    /// Keep the frame of `id` in line with its toplevel, `None` if it is not client-side
+    /// decorated. Returns whether the frame came or went, the ui then has to be rebuilt.
+    pub fn sync_frame(
+        &mut self,
+        id: Id,
+        toplevel: Option<(ToplevelState, Option<WmCapabilities>)>,
+    ) -> bool {
+        match (toplevel, self.frames.get(&id)) {
+            (Some((toplevel, capabilities)), Some(frame)) => {
+                let mut frame = frame.borrow_mut();
+                frame.toplevel = toplevel;
+                frame.capabilities = capabilities;
+                false
+            }
+            (Some((toplevel, capabilities)), None) => {
+                let frame = csd::FrameState {
+                    toplevel,
+                    capabilities,
+                    ..Default::default()
+                };
+                self.frames.insert(id, Rc::new(RefCell::new(frame)));
+                true
+            }
+            (None, _) => self.frames.remove(&id).is_some(),
+        }
+    }
+
+    /// What the frame of `id` asked for while handling the last events
+    pub fn take_frame_request(&self, id: &Id) -> Option<csd::Request> {
+        self.frames.get(id)?.borrow_mut().request.take()
+    }
+
     pub fn build(&mut self, id: Id, cache: Cache, renderer: &mut P::Renderer, size: Size) {
         let view_span = iced_debug::view(id);
-        let view = self.application.view(id);
+        let mut view = self.application.view(id);
+        if let Some(frame) = self.frames.get(&id) {
+            frame.borrow_mut().title = self.application.title(id);
+            view = csd::Decorated::new(view, frame.clone()).into();
+        }
         view_span.finish();
 
         let layout_span = iced_debug::layout(id);
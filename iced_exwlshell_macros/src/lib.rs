@@ -44,6 +44,8 @@ pub fn to_exwlshell_message(
             /// Action, margin change Margin: top, left, bottom, right
             MarginChange{id: iced_exwlshell::reexport::IcedId, margin: (i32, i32, i32, i32)},
             BlurOptionChange{id: iced_exwlshell::reexport::IcedId, option: iced_exwlshell::reexport::BlurOption},
+            /// Action, keep the screen from idling while the window is visible
+            IdleInhibit{id: iced_exwlshell::reexport::IcedId, enabled: bool},
             /// Action, ExclusiveZone Change
             ExclusiveZoneChange{id: iced_exwlshell::reexport::IcedId, zone_size: i32},
             /// Action, KeyboardInteractivity change
@@ -52,6 +54,20 @@ pub fn to_exwlshell_message(
             VirtualKeyboardPressed {
                 key: u32,
             },
+            /// Action, type text on the virtual keyboard
+            VirtualKeyboardType(String),
+            /// Action, press or release a key of the virtual keyboard
+            VirtualKeyboardKey { key: u32, pressed: bool },
+            /// Action, set the depressed, latched and locked modifiers of the virtual keyboard
+            VirtualKeyboardModifiers { depressed: u32, latched: u32, locked: u32 },
+            /// Action, move the virtual pointer by dx, dy
+            VirtualPointerMotion { dx: f64, dy: f64 },
+            /// Action, move the virtual pointer to x, y out of width, height
+            VirtualPointerMotionAbsolute { x: u32, y: u32, width: u32, height: u32 },
+            /// Action, press or release an evdev button of the virtual pointer
+            VirtualPointerButton { button: u32, pressed: bool },
+            /// Action, scroll the wheel of the virtual pointer
+            VirtualPointerAxis { horizontal: f64, vertical: f64 },
             /// Action request for new layershell
             NewLayerShell { settings: iced_exwlshell::reexport::NewLayerShellSettings, id: iced_exwlshell::reexport::IcedId },
             /// Action request for new base window
@@ -71,7 +87,13 @@ pub fn to_exwlshell_message(
             /// Action, Lock
             Lock,
             /// Action, UnLock
-            UnLock
+            UnLock,
+            /// Action, request an xdg-activation token for the window
+            RequestActivationToken(iced_exwlshell::reexport::IcedId),
+            /// Action, activate the window with an xdg-activation token
+            ActivateWithToken { id: iced_exwlshell::reexport::IcedId, token: String },
+            /// Action, start dragging data out of the window
+            StartDrag { id: iced_exwlshell::reexport::IcedId, mime_types: Vec<String>, data: Vec<u8> }
         };
 
         let impl_quote = quote! {
@@ -128,6 +150,13 @@ pub fn to_exwlshell_message(
                             None,
                             ExwlShellCustomAction::VirtualKeyboardPressed { key })
                         ),
+                        Self::VirtualKeyboardType(text) => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualKeyboardType(text))),
+                        Self::VirtualKeyboardKey { key, pressed } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualKeyboardKey { key, pressed })),
+                        Self::VirtualKeyboardModifiers { depressed, latched, locked } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualKeyboardModifiers { depressed, latched, locked })),
+                        Self::VirtualPointerMotion { dx, dy } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerMotion { dx, dy })),
+                        Self::VirtualPointerMotionAbsolute { x, y, width, height } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerMotionAbsolute { x, y, width, height })),
+                        Self::VirtualPointerButton { button, pressed } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerButton { button, pressed })),
+                        Self::VirtualPointerAxis { horizontal, vertical } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerAxis { horizontal, vertical })),
                         Self::NewLayerShell {settings, id } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::NewLayerShell { settings, id })),
                         Self::NewBaseWindow {settings, id } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::NewBaseWindow { settings, id })),
                         Self::NewPopUp { settings, id } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::NewPopUp { settings, id })),
@@ -137,8 +166,12 @@ pub fn to_exwlshell_message(
                         Self::RemoveWindow(id) => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::RemoveWindow)),
                         Self::ForgetLastOutput => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::ForgetLastOutput)),
                         Self::BlurOptionChange {id, option} => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::BlurOptionChange(option))),
+                        Self::IdleInhibit { id, enabled } => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::IdleInhibit(enabled))),
                         Self::Lock => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::Lock)),
                         Self::UnLock => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::UnLock)),
+                        Self::RequestActivationToken(id) => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::RequestActivationToken)),
+                        Self::ActivateWithToken { id, token } => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::ActivateWithToken { token })),
+                        Self::StartDrag { id, mime_types, data } => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::StartDrag { mime_types, data })),
                         _ => Err(self)
                     }
                 }
@@ -247,11 +280,19 @@ pub fn to_layer_message(attr: TokenStream2, input: TokenStream2) -> manyhow::Res
                 /// Margin: top, left, bottom, right
                 MarginChange{id: iced_exwlshell::reexport::IcedId, margin: (i32, i32, i32, i32)},
                 BlurOptionChange{id: iced_exwlshell::reexport::IcedId, option: iced_exwlshell::reexport::BlurOption},
+                IdleInhibit{id: iced_exwlshell::reexport::IcedId, enabled: bool},
                 ExclusiveZoneChange{id: iced_exwlshell::reexport::IcedId, zone_size: i32},
                 KeyboardInteractivityChange{id: iced_exwlshell::reexport::IcedId, keyboard_interactivity: iced_exwlshell::reexport::KeyboardInteractivity},
                 VirtualKeyboardPressed {
                     key: u32,
                 },
+                VirtualKeyboardType(String),
+                VirtualKeyboardKey { key: u32, pressed: bool },
+                VirtualKeyboardModifiers { depressed: u32, latched: u32, locked: u32 },
+                VirtualPointerMotion { dx: f64, dy: f64 },
+                VirtualPointerMotionAbsolute { x: u32, y: u32, width: u32, height: u32 },
+                VirtualPointerButton { button: u32, pressed: bool },
+                VirtualPointerAxis { horizontal: f64, vertical: f64 },
                 NewLayerShell { settings: iced_exwlshell::reexport::NewLayerShellSettings, id: iced_exwlshell::reexport::IcedId },
                 NewBaseWindow { settings: iced_exwlshell::actions::IcedXdgWindowSettings, id: iced_exwlshell::reexport::IcedId },
                 NewPopUp { settings: iced_exwlshell::actions::IcedNewPopupSettings, id: iced_exwlshell::reexport::IcedId },
@@ -261,6 +302,9 @@ pub fn to_layer_message(attr: TokenStream2, input: TokenStream2) -> manyhow::Res
                 NewInputPanel { settings: iced_exwlshell::reexport::NewInputPanelSettings, id: iced_exwlshell::reexport::IcedId },
                 RemoveWindow(iced_exwlshell::reexport::IcedId),
                 ForgetLastOutput,
+                RequestActivationToken(iced_exwlshell::reexport::IcedId),
+                ActivateWithToken { id: iced_exwlshell::reexport::IcedId, token: String },
+                StartDrag { id: iced_exwlshell::reexport::IcedId, mime_types: Vec<String>, data: Vec<u8> },
             };
 
             let impl_quote = quote! {
@@ -316,6 +360,13 @@ pub fn to_layer_message(attr: TokenStream2, input: TokenStream2) -> manyhow::Res
                                 None,
                                 ExwlShellCustomAction::VirtualKeyboardPressed { key })
                             ),
+                            Self::VirtualKeyboardType(text) => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualKeyboardType(text))),
+                            Self::VirtualKeyboardKey { key, pressed } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualKeyboardKey { key, pressed })),
+                            Self::VirtualKeyboardModifiers { depressed, latched, locked } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualKeyboardModifiers { depressed, latched, locked })),
+                            Self::VirtualPointerMotion { dx, dy } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerMotion { dx, dy })),
+                            Self::VirtualPointerMotionAbsolute { x, y, width, height } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerMotionAbsolute { x, y, width, height })),
+                            Self::VirtualPointerButton { button, pressed } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerButton { button, pressed })),
+                            Self::VirtualPointerAxis { horizontal, vertical } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerAxis { horizontal, vertical })),
                             Self::NewLayerShell {settings, id } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::NewLayerShell { settings, id })),
                             Self::NewBaseWindow {settings, id } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::NewBaseWindow { settings, id })),
                             Self::NewPopUp { settings, id } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::NewPopUp { settings, id })),
@@ -325,6 +376,10 @@ pub fn to_layer_message(attr: TokenStream2, input: TokenStream2) -> manyhow::Res
                             Self::RemoveWindow(id) => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::RemoveWindow)),
                             Self::ForgetLastOutput => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::ForgetLastOutput)),
                             Self::BlurOptionChange {id, option} => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::BlurOptionChange(option))),
+                            Self::IdleInhibit { id, enabled } => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::IdleInhibit(enabled))),
+                            Self::RequestActivationToken(id) => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::RequestActivationToken)),
+                            Self::ActivateWithToken { id, token } => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::ActivateWithToken { token })),
+                            Self::StartDrag { id, mime_types, data } => Ok(ExwlShellCustomActionWithId::new(Some(id), ExwlShellCustomAction::StartDrag { mime_types, data })),
                             _ => Err(self)
                         }
                     }
@@ -344,7 +399,18 @@ pub fn to_layer_message(attr: TokenStream2, input: TokenStream2) -> manyhow::Res
                 VirtualKeyboardPressed {
                     key: u32,
                 },
+                VirtualKeyboardType(String),
+                VirtualKeyboardKey { key: u32, pressed: bool },
+                VirtualKeyboardModifiers { depressed: u32, latched: u32, locked: u32 },
+                VirtualPointerMotion { dx: f64, dy: f64 },
+                VirtualPointerMotionAbsolute { x: u32, y: u32, width: u32, height: u32 },
+                VirtualPointerButton { button: u32, pressed: bool },
+                VirtualPointerAxis { horizontal: f64, vertical: f64 },
                 BlurOptionChange(iced_exwlshell::reexport::BlurOption),
+                IdleInhibit { enabled: bool },
+                RequestActivationToken,
+                ActivateWithToken { token: String },
+                StartDrag { mime_types: Vec<String>, data: Vec<u8> },
             };
             let impl_quote = quote! {
                 impl #impl_gen TryInto<#wlshell_action> for #ident #ty_gen #where_gen {
@@ -365,8 +431,19 @@ pub fn to_layer_message(attr: TokenStream2, input: TokenStream2) -> manyhow::Res
                             Self::VirtualKeyboardPressed { key } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualKeyboardPressed {
                                 key
                             })),
+                            Self::VirtualKeyboardType(text) => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualKeyboardType(text))),
+                            Self::VirtualKeyboardKey { key, pressed } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualKeyboardKey { key, pressed })),
+                            Self::VirtualKeyboardModifiers { depressed, latched, locked } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualKeyboardModifiers { depressed, latched, locked })),
+                            Self::VirtualPointerMotion { dx, dy } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerMotion { dx, dy })),
+                            Self::VirtualPointerMotionAbsolute { x, y, width, height } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerMotionAbsolute { x, y, width, height })),
+                            Self::VirtualPointerButton { button, pressed } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerButton { button, pressed })),
+                            Self::VirtualPointerAxis { horizontal, vertical } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::VirtualPointerAxis { horizontal, vertical })),
 
                             Self::BlurOptionChange(option) => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::BlurOptionChange(option))),
+                            Self::IdleInhibit { enabled } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::IdleInhibit(enabled))),
+                            Self::RequestActivationToken => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::RequestActivationToken)),
+                            Self::ActivateWithToken { token } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::ActivateWithToken { token })),
+                            Self::StartDrag { mime_types, data } => Ok(ExwlShellCustomActionWithId::new(None, ExwlShellCustomAction::StartDrag { mime_types, data })),
                             _ => Err(self)
                         }
                     }
@@ -0,0 +1,838 @@
+use std::time::{Duration, Instant};
+
+use iced_core::Rectangle;
+use iced_core::image::Handle;
+use sctk::{
+    delegate_dispatch2, delegate_registry,
+    output::{OutputHandler, OutputState},
+    registry::{ProvidesRegistryState, RegistryState, SimpleGlobal},
+    registry_handlers,
+    shm::raw::RawPool,
+};
+use wayland_client::{
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum, delegate_noop,
+    event_created_child,
+    globals::GlobalList,
+    protocol::{
+        wl_buffer::WlBuffer,
+        wl_callback::WlCallback,
+        wl_output::WlOutput,
+        wl_shm::{Format, WlShm},
+    },
+};
+use wayland_protocols::ext::foreign_toplevel_list::v1::client::{
+    ext_foreign_toplevel_handle_v1::{self, ExtForeignToplevelHandleV1},
+    ext_foreign_toplevel_list_v1::{self, ExtForeignToplevelListV1},
+};
+use wayland_protocols::ext::image_capture_source::v1::client::{
+    ext_foreign_toplevel_image_capture_source_manager_v1::ExtForeignToplevelImageCaptureSourceManagerV1,
+    ext_image_capture_source_v1::ExtImageCaptureSourceV1,
+    ext_output_image_capture_source_manager_v1::ExtOutputImageCaptureSourceManagerV1,
+};
+use wayland_protocols::ext::image_copy_capture::v1::client::{
+    ext_image_copy_capture_frame_v1::{self, ExtImageCopyCaptureFrameV1, FailureReason},
+    ext_image_copy_capture_manager_v1::{ExtImageCopyCaptureManagerV1, Options},
+    ext_image_copy_capture_session_v1::{self, ExtImageCopyCaptureSessionV1},
+};
+use wayland_protocols_wlr::screencopy::v1::client::{
+    zwlr_screencopy_frame_v1::{self, Flags, ZwlrScreencopyFrameV1},
+    zwlr_screencopy_manager_v1::ZwlrScreencopyManagerV1,
+};
+
+use crate::Error;
+use crate::info::OutputId;
+use crate::worker::{self, Disposition, Worker};
+
+/// The shm formats frames are converted from, by preference
+const FORMATS: [Format; 4] = [
+    Format::Xrgb8888,
+    Format::Argb8888,
+    Format::Xbgr8888,
+    Format::Abgr8888,
+];
+
+/// What to capture
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CaptureTarget {
+    Output(OutputId),
+    /// A window, by the `identifier` of `ext-foreign-toplevel-list-v1`, like
+    /// `toplevel::Toplevel::identifier` with the `toplevel` feature. Not available with
+    /// the wlr screencopy fallback.
+    Toplevel(String),
+}
+
+/// One capture, part of the subscription identity.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CaptureOptions {
+    pub target: CaptureTarget,
+    /// Frames are captured at most this often, 30 unless set.
+    pub max_fps: u32,
+    /// Paint the cursor onto the frames.
+    pub paint_cursors: bool,
+}
+
+impl CaptureOptions {
+    pub fn output(output: OutputId) -> Self {
+        Self::new(CaptureTarget::Output(output))
+    }
+
+    pub fn toplevel(identifier: impl Into<String>) -> Self {
+        Self::new(CaptureTarget::Toplevel(identifier.into()))
+    }
+
+    fn new(target: CaptureTarget) -> Self {
+        Self {
+            target,
+            max_fps: 30,
+            paint_cursors: false,
+        }
+    }
+
+    pub fn max_fps(mut self, max_fps: u32) -> Self {
+        self.max_fps = max_fps;
+        self
+    }
+
+    pub fn paint_cursors(mut self) -> Self {
+        self.paint_cursors = true;
+        self
+    }
+}
+
+/// A captured image
+#[derive(Debug, Clone)]
+pub struct CaptureFrame {
+    pub handle: Handle,
+    pub width: u32,
+    pub height: u32,
+    /// The regions that changed since the previous frame, in buffer pixels.
+    /// The whole frame for the first one.
+    pub damage: Vec<Rectangle<u32>>,
+}
+
+/// What the capture subscription hands to the application
+#[derive(Debug)]
+pub enum CaptureEvent {
+    /// A new image of the target, only emitted when something changed.
+    Frame(CaptureFrame),
+    /// The target is gone, like a closed window or an unplugged output. Nothing follows.
+    Stopped,
+    /// The compositor cannot capture this target, with neither
+    /// `ext-image-copy-capture-v1` nor, for outputs, wlr screencopy. Emitted once, at startup,
+    /// even if the worker restarts.
+    Unsupported,
+    Stop(Error),
+}
+
+/// Shared memory the compositor copies frames into, reused while the constraints hold.
+#[derive(Debug)]
+struct ShmBuffer {
+    pool: RawPool,
+    buffer: WlBuffer,
+    width: u32,
+    height: u32,
+    stride: u32,
+    format: Format,
+    /// Never captured into, so it has to be damaged whole.
+    fresh: bool,
+}
+
+impl ShmBuffer {
+    /// The frame as RGBA, which is what iced takes
+    fn read_rgba(&mut self, y_invert: bool) -> Vec<u8> {
+        let (width, height, stride) = (
+            self.width as usize,
+            self.height as usize,
+            self.stride as usize,
+        );
+        let format = self.format;
+        let memory = &self.pool.mmap()[..];
+        let mut rgba = Vec::with_capacity(width * height * 4);
+        for row in 0..height {
+            let row = if y_invert { height - 1 - row } else { row };
+            let Some(pixels) = memory.get(row * stride..row * stride + width * 4) else {
+                break;
+            };
+            for pixel in pixels.as_chunks::<4>().0 {
+                // the formats are little endian, `Xrgb8888` is B, G, R, X in memory
+                let [r, g, b, a] = match format {
+                    Format::Xrgb8888 => [pixel[2], pixel[1], pixel[0], u8::MAX],
+                    Format::Argb8888 => [pixel[2], pixel[1], pixel[0], pixel[3]],
+                    Format::Xbgr8888 => [pixel[0], pixel[1], pixel[2], u8::MAX],
+                    _ => *pixel,
+                };
+                rgba.extend_from_slice(&[r, g, b, a]);
+            }
+        }
+        rgba
+    }
+}
+
+#[derive(Debug)]
+struct ExtSession {
+    source: ExtImageCaptureSourceV1,
+    session: ExtImageCopyCaptureSessionV1,
+    frame: Option<ExtImageCopyCaptureFrameV1>,
+    /// The constraints being received, applied at `done`.
+    size: (u32, u32),
+    formats: Vec<Format>,
+}
+
+#[derive(Debug)]
+struct WlrCapture {
+    output: WlOutput,
+    frame: Option<ZwlrScreencopyFrameV1>,
+    /// The first buffer the compositor offered in a format we convert from:
+    /// format, width, height and stride.
+    offer: Option<(Format, u32, u32, u32)>,
+    y_invert: bool,
+}
+
+/// Dispatch state for the capture worker.
+#[derive(Debug)]
+pub(crate) struct Capture {
+    registry_state: RegistryState,
+    /// Finds the output to capture.
+    output_state: OutputState,
+    shm: Option<SimpleGlobal<WlShm, 1>>,
+    options: CaptureOptions,
+    manager: Option<ExtImageCopyCaptureManagerV1>,
+    output_sources: Option<ExtOutputImageCaptureSourceManagerV1>,
+    toplevel_sources: Option<ExtForeignToplevelImageCaptureSourceManagerV1>,
+    /// Finds the window to capture, only bound for a toplevel target.
+    toplevel_list: Option<ExtForeignToplevelListV1>,
+    toplevels: Vec<(ExtForeignToplevelHandleV1, Option<String>)>,
+    screencopy: Option<ZwlrScreencopyManagerV1>,
+    ext_session: Option<ExtSession>,
+    wlr_capture: Option<WlrCapture>,
+    buffer: Option<ShmBuffer>,
+    /// Damage of the frame in flight.
+    damage: Vec<Rectangle<u32>>,
+    last_capture: Option<Instant>,
+    /// When the frame held back by `max_fps` is captured.
+    next_capture: Option<Instant>,
+    /// Captures the held back frame from [`Worker::tick`].
+    qh: QueueHandle<Self>,
+    /// The target went away, nothing more is captured.
+    stopped: bool,
+    events: Vec<CaptureEvent>,
+}
+
+impl Capture {
+    fn target_output(&self, id: OutputId) -> Option<WlOutput> {
+        self.output_state.outputs().find(|output| {
+            self.output_state
+                .info(output)
+                .is_some_and(|info| OutputId::from(&info) == id)
+        })
+    }
+
+    /// Start capturing once the target is known.
+    fn start(&mut self, qh: &QueueHandle<Self>) {
+        if self.stopped || self.ext_session.is_some() || self.wlr_capture.is_some() {
+            return;
+        }
+        let source = match &self.options.target {
+            CaptureTarget::Output(id) => {
+                let Some(output) = self.target_output(*id) else {
+                    return;
+                };
+                match (&self.output_sources, &self.screencopy) {
+                    (Some(sources), _) if self.manager.is_some() => {
+                        sources.create_source(&output, qh, ())
+                    }
+                    (_, Some(_)) => {
+                        self.wlr_capture = Some(WlrCapture {
+                            output,
+                            frame: None,
+                            offer: None,
+                            y_invert: false,
+                        });
+                        self.capture_wlr(qh);
+                        return;
+                    }
+                    _ => return,
+                }
+            }
+            CaptureTarget::Toplevel(identifier) => {
+                let Some((handle, _)) = self
+                    .toplevels
+                    .iter()
+                    .find(|(_, known)| known.as_ref() == Some(identifier))
+                else {
+                    return;
+                };
+                let Some(sources) = &self.toplevel_sources else {
+                    return;
+                };
+                sources.create_source(handle, qh, ())
+            }
+        };
+        let Some(manager) = &self.manager else {
+            source.destroy();
+            return;
+        };
+        let options = if self.options.paint_cursors {
+            Options::PaintCursors
+        } else {
+            Options::empty()
+        };
+        let session = manager.create_session(&source, options, qh, ());
+        self.ext_session = Some(ExtSession {
+            source,
+            session,
+            frame: None,
+            size: (0, 0),
+            formats: Vec::new(),
+        });
+    }
+
+    /// Drop the capture, the target is gone.
+    fn stop(&mut self) {
+        if let Some(ext) = self.ext_session.take() {
+            if let Some(frame) = ext.frame {
+                frame.destroy();
+            }
+            ext.session.destroy();
+            ext.source.destroy();
+        }
+        if let Some(wlr) = self.wlr_capture.take()
+            && let Some(frame) = wlr.frame
+        {
+            frame.destroy();
+        }
+        if let Some(buffer) = self.buffer.take() {
+            buffer.buffer.destroy();
+        }
+        self.next_capture = None;
+        if !self.stopped {
+            self.stopped = true;
+            self.events.push(CaptureEvent::Stopped);
+        }
+    }
+
+    /// Hold the frame rate under `max_fps`. When it is too early, the frame
+    /// is taken by [`Worker::tick`] instead.
+    fn frame_due(&mut self) -> bool {
+        let interval = Duration::from_secs(1) / self.options.max_fps.max(1);
+        let now = Instant::now();
+        if let Some(due) = self.last_capture.map(|last| last + interval)
+            && due > now
+        {
+            self.next_capture = Some(due);
+            return false;
+        }
+        self.next_capture = None;
+        self.last_capture = Some(now);
+        self.damage.clear();
+        true
+    }
+
+    /// Reuse the buffer if it still fits, allocate one otherwise.
+    fn ensure_buffer(
+        &mut self,
+        qh: &QueueHandle<Self>,
+        (format, width, height, stride): (Format, u32, u32, u32),
+    ) -> bool {
+        if self.buffer.as_ref().is_some_and(|buffer| {
+            (buffer.format, buffer.width, buffer.height, buffer.stride)
+                == (format, width, height, stride)
+        }) {
+            return true;
+        }
+        if let Some(buffer) = self.buffer.take() {
+            buffer.buffer.destroy();
+        }
+        let Some(Ok(mut pool)) = self
+            .shm
+            .as_ref()
+            .map(|shm| RawPool::new((stride * height) as usize, shm))
+        else {
+            return false;
+        };
+        let buffer = pool.create_buffer(
+            0,
+            width as i32,
+            height as i32,
+            stride as i32,
+            format,
+            (),
+            qh,
+        );
+        self.buffer = Some(ShmBuffer {
+            pool,
+            buffer,
+            width,
+            height,
+            stride,
+            format,
+            fresh: true,
+        });
+        true
+    }
+
+    fn capture_ext(&mut self, qh: &QueueHandle<Self>) {
+        if self.buffer.is_none()
+            || self
+                .ext_session
+                .as_ref()
+                .is_none_or(|ext| ext.frame.is_some())
+        {
+            return;
+        }
+        if !self.frame_due() {
+            return;
+        }
+        let (Some(ext), Some(buffer)) = (&mut self.ext_session, &mut self.buffer) else {
+            return;
+        };
+        let frame = ext.session.create_frame(qh, ());
+        frame.attach_buffer(&buffer.buffer);
+        if std::mem::take(&mut buffer.fresh) {
+            frame.damage_buffer(0, 0, buffer.width as i32, buffer.height as i32);
+        }
+        frame.capture();
+        ext.frame = Some(frame);
+    }
+
+    fn capture_wlr(&mut self, qh: &QueueHandle<Self>) {
+        if self
+            .wlr_capture
+            .as_ref()
+            .is_none_or(|wlr| wlr.frame.is_some())
+        {
+            return;
+        }
+        if !self.frame_due() {
+            return;
+        }
+        let (Some(wlr), Some(screencopy)) = (&mut self.wlr_capture, &self.screencopy) else {
+            return;
+        };
+        wlr.offer = None;
+        wlr.y_invert = false;
+        wlr.frame =
+            Some(screencopy.capture_output(self.options.paint_cursors.into(), &wlr.output, qh, ()));
+    }
+
+    /// Copy into a buffer matching the offer of the compositor.
+    fn copy_wlr(&mut self, qh: &QueueHandle<Self>) {
+        let Some(offer) = self.wlr_capture.as_ref().and_then(|wlr| wlr.offer) else {
+            // nothing we can convert, no frame can be taken
+            self.stop();
+            return;
+        };
+        if !self.ensure_buffer(qh, offer) {
+            self.stop();
+            return;
+        }
+        let (Some(wlr), Some(buffer)) = (&self.wlr_capture, &self.buffer) else {
+            return;
+        };
+        let Some(frame) = &wlr.frame else {
+            return;
+        };
+        // version 2 waits for damage, like ext-image-copy-capture does
+        if frame.version() >= 2 {
+            frame.copy_with_damage(&buffer.buffer);
+        } else {
+            frame.copy(&buffer.buffer);
+        }
+    }
+
+    /// Hand the frame that just landed in the buffer to the application, if it changed.
+    fn publish(&mut self, y_invert: bool, whole: bool) {
+        let Some(buffer) = &mut self.buffer else {
+            return;
+        };
+        let mut damage = std::mem::take(&mut self.damage);
+        if whole {
+            damage = vec![Rectangle {
+                x: 0,
+                y: 0,
+                width: buffer.width,
+                height: buffer.height,
+            }];
+        }
+        if damage.is_empty() {
+            return;
+        }
+        let pixels = buffer.read_rgba(y_invert);
+        self.events.push(CaptureEvent::Frame(CaptureFrame {
+            handle: Handle::from_rgba(buffer.width, buffer.height, pixels),
+            width: buffer.width,
+            height: buffer.height,
+            damage,
+        }));
+    }
+
+    fn add_damage(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        let [x, y, width, height] = [x, y, width, height].map(|value| value.max(0) as u32);
+        self.damage.push(Rectangle {
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureSessionV1, ()> for Capture {
+    fn event(
+        state: &mut Self,
+        _: &ExtImageCopyCaptureSessionV1,
+        event: ext_image_copy_capture_session_v1::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        let Some(ext) = &mut state.ext_session else {
+            return;
+        };
+        match event {
+            ext_image_copy_capture_session_v1::Event::BufferSize { width, height } => {
+                ext.size = (width, height);
+            }
+            ext_image_copy_capture_session_v1::Event::ShmFormat {
+                format: WEnum::Value(format),
+            } => ext.formats.push(format),
+            ext_image_copy_capture_session_v1::Event::Done => {
+                let formats = std::mem::take(&mut ext.formats);
+                let (width, height) = ext.size;
+                let Some(format) = FORMATS.into_iter().find(|format| formats.contains(format))
+                else {
+                    // nothing we can convert, no frame can be taken
+                    state.stop();
+                    return;
+                };
+                if state.ensure_buffer(qh, (format, width, height, width * 4)) {
+                    state.capture_ext(qh);
+                } else {
+                    state.stop();
+                }
+            }
+            ext_image_copy_capture_session_v1::Event::Stopped => state.stop(),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureFrameV1, ()> for Capture {
+    fn event(
+        state: &mut Self,
+        frame: &ExtImageCopyCaptureFrameV1,
+        event: ext_image_copy_capture_frame_v1::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_image_copy_capture_frame_v1::Event::Damage {
+                x,
+                y,
+                width,
+                height,
+            } => state.add_damage(x, y, width, height),
+            ext_image_copy_capture_frame_v1::Event::Ready => {
+                frame.destroy();
+                if let Some(ext) = &mut state.ext_session {
+                    ext.frame = None;
+                }
+                state.publish(false, false);
+                state.capture_ext(qh);
+            }
+            ext_image_copy_capture_frame_v1::Event::Failed { reason } => {
+                frame.destroy();
+                if let Some(ext) = &mut state.ext_session {
+                    ext.frame = None;
+                }
+                match reason {
+                    // the session sends the new constraints, then `done` captures again
+                    WEnum::Value(FailureReason::BufferConstraints) => {}
+                    WEnum::Value(FailureReason::Stopped) => state.stop(),
+                    _ => state.capture_ext(qh),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwlrScreencopyFrameV1, ()> for Capture {
+    fn event(
+        state: &mut Self,
+        frame: &ZwlrScreencopyFrameV1,
+        event: zwlr_screencopy_frame_v1::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        let Some(wlr) = &mut state.wlr_capture else {
+            return;
+        };
+        match event {
+            zwlr_screencopy_frame_v1::Event::Buffer {
+                format: WEnum::Value(format),
+                width,
+                height,
+                stride,
+            } => {
+                if wlr.offer.is_none() && FORMATS.contains(&format) {
+                    wlr.offer = Some((format, width, height, stride));
+                }
+                // before version 3 there is only one offer and no `buffer_done`
+                if frame.version() < 3 {
+                    state.copy_wlr(qh);
+                }
+            }
+            zwlr_screencopy_frame_v1::Event::BufferDone => state.copy_wlr(qh),
+            zwlr_screencopy_frame_v1::Event::Flags { flags } => {
+                wlr.y_invert =
+                    matches!(flags, WEnum::Value(flags) if flags.contains(Flags::YInvert));
+            }
+            zwlr_screencopy_frame_v1::Event::Damage {
+                x,
+                y,
+                width,
+                height,
+            } => state.add_damage(x as i32, y as i32, width as i32, height as i32),
+            zwlr_screencopy_frame_v1::Event::Ready { .. } => {
+                frame.destroy();
+                wlr.frame = None;
+                let y_invert = wlr.y_invert;
+                state.publish(y_invert, frame.version() < 2);
+                state.capture_wlr(qh);
+            }
+            zwlr_screencopy_frame_v1::Event::Failed => {
+                frame.destroy();
+                wlr.frame = None;
+                state.stop();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtForeignToplevelListV1, ()> for Capture {
+    fn event(
+        state: &mut Self,
+        list: &ExtForeignToplevelListV1,
+        event: ext_foreign_toplevel_list_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_foreign_toplevel_list_v1::Event::Toplevel { toplevel } => {
+                state.toplevels.push((toplevel, None));
+            }
+            ext_foreign_toplevel_list_v1::Event::Finished => {
+                list.destroy();
+                state.toplevel_list = None;
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(Capture, ExtForeignToplevelListV1, [
+        ext_foreign_toplevel_list_v1::EVT_TOPLEVEL_OPCODE => (ExtForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ExtForeignToplevelHandleV1, ()> for Capture {
+    fn event(
+        state: &mut Self,
+        handle: &ExtForeignToplevelHandleV1,
+        event: ext_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_foreign_toplevel_handle_v1::Event::Identifier { identifier } => {
+                if let Some((_, known)) = state
+                    .toplevels
+                    .iter_mut()
+                    .find(|(known, _)| known == handle)
+                {
+                    *known = Some(identifier);
+                }
+            }
+            ext_foreign_toplevel_handle_v1::Event::Done => state.start(qh),
+            ext_foreign_toplevel_handle_v1::Event::Closed => {
+                handle.destroy();
+                state.toplevels.retain(|(known, _)| known != handle);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Worker for Capture {
+    type Event = CaptureEvent;
+    type Config = CaptureOptions;
+
+    fn disposition(event: &CaptureEvent) -> Disposition {
+        match event {
+            // Only the newest image is worth showing.
+            CaptureEvent::Frame(_) => Disposition::Supersedes,
+            CaptureEvent::Stopped | CaptureEvent::Stop(_) => Disposition::Terminal,
+            CaptureEvent::Unsupported => Disposition::Once,
+        }
+    }
+
+    fn init(
+        _: &Connection,
+        globals: &GlobalList,
+        qh: &QueueHandle<Self>,
+        options: &CaptureOptions,
+    ) -> Result<Self, Error> {
+        let manager = globals
+            .bind::<ExtImageCopyCaptureManagerV1, _, _>(qh, 1..=1, ())
+            .ok();
+        let output_sources = globals
+            .bind::<ExtOutputImageCaptureSourceManagerV1, _, _>(qh, 1..=1, ())
+            .ok();
+        let toplevel_sources = globals
+            .bind::<ExtForeignToplevelImageCaptureSourceManagerV1, _, _>(qh, 1..=1, ())
+            .ok();
+        let screencopy = globals
+            .bind::<ZwlrScreencopyManagerV1, _, _>(qh, 1..=3, ())
+            .ok();
+        let shm = SimpleGlobal::bind(globals, qh).ok();
+        let supported = shm.is_some()
+            && match options.target {
+                CaptureTarget::Output(_) => {
+                    (manager.is_some() && output_sources.is_some()) || screencopy.is_some()
+                }
+                CaptureTarget::Toplevel(_) => manager.is_some() && toplevel_sources.is_some(),
+            };
+        let toplevel_list = match options.target {
+            CaptureTarget::Toplevel(_) if supported => globals
+                .bind::<ExtForeignToplevelListV1, _, _>(qh, 1..=1, ())
+                .ok(),
+            _ => None,
+        };
+        let mut capture = Self {
+            registry_state: RegistryState::new(globals),
+            output_state: OutputState::new(globals, qh),
+            shm,
+            options: options.clone(),
+            manager,
+            output_sources,
+            toplevel_sources,
+            toplevel_list,
+            toplevels: Vec::new(),
+            screencopy,
+            ext_session: None,
+            wlr_capture: None,
+            buffer: None,
+            damage: Vec::new(),
+            last_capture: None,
+            next_capture: None,
+            qh: qh.clone(),
+            stopped: false,
+            events: Vec::new(),
+        };
+        if supported {
+            capture.start(qh);
+        } else {
+            capture.events.push(CaptureEvent::Unsupported);
+        }
+        Ok(capture)
+    }
+
+    fn take_events(&mut self) -> Vec<CaptureEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn stop_event(error: Error) -> CaptureEvent {
+        CaptureEvent::Stop(error)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.next_capture
+    }
+
+    fn tick(&mut self) {
+        let qh = self.qh.clone();
+        self.capture_ext(&qh);
+        self.capture_wlr(&qh);
+    }
+
+    fn teardown(&mut self, queue: &mut EventQueue<Self>) {
+        crate::output::release_outputs(&self.output_state);
+        // no `Stopped` for the application, it is the one going away
+        self.stopped = true;
+        self.stop();
+        for (handle, _) in self.toplevels.drain(..) {
+            handle.destroy();
+        }
+        if let Some(list) = self.toplevel_list.take() {
+            list.stop();
+            let _ = queue.roundtrip(self);
+            // `finished` destroys it if it arrived
+            if list.is_alive() {
+                list.destroy();
+            }
+        }
+        if let Some(manager) = self.manager.take() {
+            manager.destroy();
+        }
+        if let Some(sources) = self.output_sources.take() {
+            sources.destroy();
+        }
+        if let Some(sources) = self.toplevel_sources.take() {
+            sources.destroy();
+        }
+        if let Some(screencopy) = self.screencopy.take() {
+            screencopy.destroy();
+        }
+    }
+}
+
+impl OutputHandler for Capture {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+    fn new_output(&mut self, _: &Connection, qh: &QueueHandle<Self>, _: WlOutput) {
+        self.start(qh);
+    }
+    fn update_output(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlOutput) {}
+    fn output_destroyed(&mut self, _: &Connection, _: &QueueHandle<Self>, output: WlOutput) {
+        // ext sessions get `stopped`, screencopy frames would only fail
+        if self
+            .wlr_capture
+            .as_ref()
+            .is_some_and(|wlr| wlr.output == output)
+        {
+            self.stop();
+        }
+    }
+}
+
+impl ProvidesRegistryState for Capture {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+    registry_handlers![OutputState];
+}
+
+delegate_registry!(Capture);
+delegate_dispatch2!(Capture);
+delegate_noop!(Capture: ignore WlCallback);
+delegate_noop!(Capture: ignore WlBuffer);
+delegate_noop!(Capture: ignore WlShm);
+delegate_noop!(Capture: ExtImageCopyCaptureManagerV1);
+delegate_noop!(Capture: ExtOutputImageCaptureSourceManagerV1);
+delegate_noop!(Capture: ExtForeignToplevelImageCaptureSourceManagerV1);
+delegate_noop!(Capture: ExtImageCaptureSourceV1);
+delegate_noop!(Capture: ZwlrScreencopyManagerV1);
+
+/// Capture an output or a window, for thumbnails and previews.
+pub fn listen(
+    connection: Connection,
+    options: CaptureOptions,
+) -> iced_futures::Subscription<CaptureEvent> {
+    worker::listen::<Capture>(connection, options)
+}
@@ -0,0 +1,722 @@
+use std::fs::File;
+use std::io::{ErrorKind, Seek, Write};
+use std::os::fd::{AsFd, OwnedFd};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rustix::fs::MemfdFlags;
+use sctk::{
+    delegate_dispatch2, delegate_registry,
+    output::{OutputHandler, OutputState},
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+};
+use wayland_client::{
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle,
+    backend::WaylandError,
+    delegate_noop,
+    globals::GlobalList,
+    protocol::{wl_callback::WlCallback, wl_output::WlOutput},
+};
+use wayland_protocols_wlr::gamma_control::v1::client::{
+    zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1,
+    zwlr_gamma_control_v1::{self, ZwlrGammaControlV1},
+};
+
+use crate::Error;
+use crate::info::OutputId;
+use crate::worker::{self, Disposition, Worker};
+
+/// How often the gamma moves during a scheduled transition
+const TRANSITION_STEP: Duration = Duration::from_secs(10);
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// A color temperature and brightness to set the gamma ramps to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Gamma {
+    /// In Kelvin, 6500 leaves the colors unchanged and lower is warmer.
+    pub temperature: u32,
+    /// In percent, 100 leaves the brightness unchanged.
+    pub brightness: u8,
+}
+
+impl Gamma {
+    /// The ramps of an uncorrected output
+    pub const NEUTRAL: Self = Self {
+        temperature: 6500,
+        brightness: 100,
+    };
+
+    pub fn new(temperature: u32) -> Self {
+        Self {
+            temperature,
+            brightness: 100,
+        }
+    }
+
+    pub fn brightness(mut self, percent: u8) -> Self {
+        self.brightness = percent.min(100);
+        self
+    }
+
+    /// `self` moved `progress` of the way, from 0 to 1, towards `other`
+    fn blend(self, other: Self, progress: f64) -> Self {
+        let lerp = |from: f64, to: f64| from + (to - from) * progress.clamp(0.0, 1.0);
+        Self {
+            temperature: lerp(self.temperature.into(), other.temperature.into()).round() as u32,
+            brightness: lerp(self.brightness.into(), other.brightness.into()).round() as u8,
+        }
+    }
+}
+
+/// Fade between a day and a night gamma at fixed times of the day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Schedule {
+    /// Hour and minute the night gamma starts fading into the day one
+    pub sunrise: (u8, u8),
+    /// Hour and minute the day gamma starts fading into the night one
+    pub sunset: (u8, u8),
+    /// How long a fade lasts
+    pub transition: Duration,
+    /// Minutes local time is ahead of UTC, the times above are local.
+    pub utc_offset: i32,
+    pub day: Gamma,
+    pub night: Gamma,
+}
+
+impl Schedule {
+    /// Fade over half an hour into a 4000K night, times are UTC until
+    /// [`Schedule::utc_offset`] is set.
+    pub fn new(sunrise: (u8, u8), sunset: (u8, u8)) -> Self {
+        Self {
+            sunrise,
+            sunset,
+            transition: Duration::from_secs(30 * 60),
+            utc_offset: 0,
+            day: Gamma::NEUTRAL,
+            night: Gamma::new(4000),
+        }
+    }
+
+    pub fn transition(mut self, transition: Duration) -> Self {
+        self.transition = transition;
+        self
+    }
+
+    /// The standard library has no time zone database, so the offset is the application's to find.
+    pub fn utc_offset(mut self, minutes: i32) -> Self {
+        self.utc_offset = minutes;
+        self
+    }
+
+    pub fn day(mut self, gamma: Gamma) -> Self {
+        self.day = gamma;
+        self
+    }
+
+    pub fn night(mut self, gamma: Gamma) -> Self {
+        self.night = gamma;
+        self
+    }
+
+    /// The gamma at `now`, and how long until it changes
+    fn at(&self, now: SystemTime) -> (Gamma, Duration) {
+        let utc = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let local = (utc as i64 + i64::from(self.utc_offset) * 60).rem_euclid(DAY as i64) as u64;
+        self.at_second(local)
+    }
+
+    /// The gamma `second` seconds after local midnight, and how long until it changes
+    fn at_second(&self, second: u64) -> (Gamma, Duration) {
+        let of_day =
+            |(hour, minute): (u8, u8)| (u64::from(hour) * 3600 + u64::from(minute) * 60) % DAY;
+        let (sunrise, sunset) = (of_day(self.sunrise), of_day(self.sunset));
+        let since = |start: u64| (second + DAY - start) % DAY;
+        let until = |start: u64| {
+            Duration::from_secs((start + DAY - second) % DAY).max(Duration::from_secs(1))
+        };
+        let transition = self.transition.as_secs().min(DAY / 2);
+        let step = TRANSITION_STEP;
+
+        if since(sunrise) < transition {
+            let progress = since(sunrise) as f64 / transition as f64;
+            (self.night.blend(self.day, progress), step)
+        } else if since(sunset) < transition {
+            let progress = since(sunset) as f64 / transition as f64;
+            (self.day.blend(self.night, progress), step)
+        } else if since(sunrise) < since(sunset) {
+            (self.day, until(sunset))
+        } else {
+            (self.night, until(sunrise))
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RequestError {
+    /// The output is gone, or its gamma can no longer be controlled.
+    #[error("no such output")]
+    Gone,
+    /// The compositor has not told the gamma size of the output yet.
+    #[error("the gamma size of the output is not known yet")]
+    NotReady,
+    #[error("failed to write the gamma ramps: {0}")]
+    Ramps(#[from] std::io::Error),
+    #[error("failed to flush the connection")]
+    Io(#[from] wayland_client::backend::WaylandError),
+}
+
+/// Whitepoint of a color temperature, as red, green and blue factors. Tanner
+/// Helland's fit of the blackbody colors, scaled so 6500K is white.
+fn whitepoint(temperature: u32) -> [f64; 3] {
+    fn fit(temperature: u32) -> [f64; 3] {
+        let t = f64::from(temperature.clamp(1000, 40000)) / 100.0;
+        let red = if t <= 66.0 {
+            255.0
+        } else {
+            329.698727446 * (t - 60.0).powf(-0.1332047592)
+        };
+        let green = if t <= 66.0 {
+            99.4708025861 * t.ln() - 161.1195681661
+        } else {
+            288.1221695283 * (t - 60.0).powf(-0.0755148492)
+        };
+        let blue = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.5177312231 * (t - 10.0).ln() - 305.0447927307
+        };
+        [red, green, blue].map(|channel| channel.clamp(0.0, 255.0) / 255.0)
+    }
+    let white = fit(Gamma::NEUTRAL.temperature);
+    let color = fit(temperature);
+    std::array::from_fn(|channel| (color[channel] / white[channel]).min(1.0))
+}
+
+/// The red, then green, then blue ramps of `size` entries, as the protocol lays them out
+fn ramps(size: u32, gamma: Gamma) -> Vec<u8> {
+    let brightness = f64::from(gamma.brightness.min(100)) / 100.0;
+    let last = f64::from(size.max(2) - 1);
+    whitepoint(gamma.temperature)
+        .into_iter()
+        .flat_map(|channel| {
+            (0..size).flat_map(move |index| {
+                let value = f64::from(index) / last * channel * brightness;
+                ((value * f64::from(u16::MAX)).round() as u16).to_ne_bytes()
+            })
+        })
+        .collect()
+}
+
+fn ramps_fd(size: u32, gamma: Gamma) -> std::io::Result<OwnedFd> {
+    let mut file = File::from(rustix::fs::memfd_create(
+        "iced-wayland-gamma",
+        MemfdFlags::CLOEXEC,
+    )?);
+    file.write_all(&ramps(size, gamma))?;
+    file.rewind()?;
+    Ok(file.into())
+}
+
+#[derive(Debug)]
+struct OutputGamma {
+    id: OutputId,
+    output: WlOutput,
+    control: ZwlrGammaControlV1,
+    /// Entries per ramp, `None` until the compositor tells
+    size: Option<u32>,
+    /// Whether the schedule sets the gamma, until the output is restored
+    follows_schedule: bool,
+}
+
+impl OutputGamma {
+    fn set(&self, gamma: Gamma) -> Result<(), RequestError> {
+        let size = self.size.ok_or(RequestError::NotReady)?;
+        self.control.set_gamma(ramps_fd(size, gamma)?.as_fd());
+        Ok(())
+    }
+}
+
+/// What the worker and [`GammaControl`] share.
+#[derive(Debug)]
+struct Controls {
+    manager: Option<ZwlrGammaControlManagerV1>,
+    outputs: Vec<OutputGamma>,
+    /// The gamma the schedule set last
+    scheduled: Option<Gamma>,
+    /// Set once the subscription stops.
+    dead: bool,
+}
+
+/// Sets and restores the gamma of outputs, handed out with [`GammaEvent::Ready`].
+#[derive(Debug, Clone)]
+pub struct GammaControl {
+    conn: Connection,
+    qh: QueueHandle<NightLight>,
+    controls: Arc<Mutex<Controls>>,
+}
+
+impl GammaControl {
+    fn lock(&self) -> MutexGuard<'_, Controls> {
+        self.controls.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn flush(&self) -> Result<(), RequestError> {
+        match self.conn.flush() {
+            Err(WaylandError::Io(error)) if error.kind() == ErrorKind::WouldBlock => Ok(()),
+            Err(error) => Err(error.into()),
+            Ok(()) => Ok(()),
+        }
+    }
+
+    /// The outputs whose gamma can be controlled
+    pub fn outputs(&self) -> Vec<OutputId> {
+        self.lock().outputs.iter().map(|gamma| gamma.id).collect()
+    }
+
+    /// Set the gamma of `output`. With a schedule, it is set again when the schedule moves on,
+    /// unless the output was restored.
+    pub fn set(&self, output: OutputId, gamma: Gamma) -> Result<(), RequestError> {
+        let controls = self.lock();
+        if controls.dead {
+            return Err(RequestError::Gone);
+        }
+        controls
+            .outputs
+            .iter()
+            .find(|known| known.id == output && known.control.is_alive())
+            .ok_or(RequestError::Gone)?
+            .set(gamma)?;
+        self.flush()
+    }
+
+    /// Set the gamma of every output whose gamma size is known
+    pub fn set_all(&self, gamma: Gamma) -> Result<(), RequestError> {
+        let controls = self.lock();
+        if controls.dead {
+            return Err(RequestError::Gone);
+        }
+        for known in &controls.outputs {
+            if known.size.is_some() {
+                known.set(gamma)?;
+            }
+        }
+        self.flush()
+    }
+
+    /// Give `output` back the ramps it had before this subscription touched them. The schedule
+    /// leaves it alone from then on, until [`GammaControl::follow_schedule`].
+    pub fn restore(&self, output: OutputId) -> Result<(), RequestError> {
+        let mut controls = self.lock();
+        if controls.dead {
+            return Err(RequestError::Gone);
+        }
+        let Controls {
+            manager, outputs, ..
+        } = &mut *controls;
+        let manager = manager.as_ref().ok_or(RequestError::Gone)?;
+        let known = outputs
+            .iter_mut()
+            .find(|known| known.id == output)
+            .ok_or(RequestError::Gone)?;
+        restore(manager, &self.qh, known);
+        self.flush()
+    }
+
+    /// Restore every output, see [`GammaControl::restore`]
+    pub fn restore_all(&self) -> Result<(), RequestError> {
+        let mut controls = self.lock();
+        if controls.dead {
+            return Err(RequestError::Gone);
+        }
+        let Controls {
+            manager, outputs, ..
+        } = &mut *controls;
+        let manager = manager.as_ref().ok_or(RequestError::Gone)?;
+        for known in outputs {
+            restore(manager, &self.qh, known);
+        }
+        self.flush()
+    }
+
+    /// Let the schedule set the gamma of `output` again after a restore, starting with the
+    /// gamma it set last
+    pub fn follow_schedule(&self, output: OutputId) -> Result<(), RequestError> {
+        let mut controls = self.lock();
+        if controls.dead {
+            return Err(RequestError::Gone);
+        }
+        let scheduled = controls.scheduled;
+        let known = controls
+            .outputs
+            .iter_mut()
+            .find(|known| known.id == output && known.control.is_alive())
+            .ok_or(RequestError::Gone)?;
+        known.follows_schedule = true;
+        // Otherwise set once the compositor tells the gamma size
+        if let Some(gamma) = scheduled
+            && known.size.is_some()
+        {
+            known.set(gamma)?;
+        }
+        self.flush()
+    }
+}
+
+/// The compositor restores the ramps of a destroyed control, take a fresh one to keep control.
+fn restore(
+    manager: &ZwlrGammaControlManagerV1,
+    qh: &QueueHandle<NightLight>,
+    known: &mut OutputGamma,
+) {
+    known.control.destroy();
+    known.control = manager.get_gamma_control(&known.output, qh, known.id);
+    known.size = None;
+    known.follows_schedule = false;
+}
+
+/// What the gamma subscription hands to the application
+#[derive(Debug)]
+pub enum GammaEvent {
+    /// The compositor supports gamma control. Emitted once, before any `Output`.
+    Ready(GammaControl),
+    /// The gamma of `output` can be set, with ramps of `size` entries. Emitted
+    /// again after a restore.
+    Output {
+        output: OutputId,
+        size: u32,
+    },
+    /// The schedule moved on, every output that was not restored is set to this gamma.
+    Scheduled(Gamma),
+    /// The gamma of `output` can no longer be controlled, because the output
+    /// went away or another client controls it.
+    Removed(OutputId),
+    /// The compositor does not implement `wlr-gamma-control`. Emitted once, at startup,
+    /// even if the worker restarts.
+    Unsupported,
+    Stop(Error),
+}
+
+/// Dispatch state for the gamma worker.
+#[derive(Debug)]
+pub(crate) struct NightLight {
+    registry_state: RegistryState,
+    output_state: OutputState,
+    control: GammaControl,
+    schedule: Option<Schedule>,
+    next_tick: Option<Instant>,
+    events: Vec<GammaEvent>,
+}
+
+impl NightLight {
+    fn control_output(&mut self, qh: &QueueHandle<Self>, output: &WlOutput) {
+        let Some(info) = self.output_state.info(output) else {
+            return;
+        };
+        let id = OutputId::from(&info);
+        let mut controls = self.control.lock();
+        let Some(manager) = &controls.manager else {
+            return;
+        };
+        if controls.outputs.iter().any(|known| known.id == id) {
+            return;
+        }
+        let control = manager.get_gamma_control(output, qh, id);
+        controls.outputs.push(OutputGamma {
+            id,
+            output: output.clone(),
+            control,
+            size: None,
+            follows_schedule: true,
+        });
+    }
+
+    /// Destroy the control of `output`, returns whether there was one.
+    fn drop_control(&mut self, output: OutputId) -> bool {
+        let mut controls = self.control.lock();
+        let Some(index) = controls.outputs.iter().position(|known| known.id == output) else {
+            return false;
+        };
+        controls.outputs.remove(index).control.destroy();
+        true
+    }
+}
+
+impl Dispatch<ZwlrGammaControlV1, OutputId> for NightLight {
+    fn event(
+        state: &mut Self,
+        control: &ZwlrGammaControlV1,
+        event: zwlr_gamma_control_v1::Event,
+        output: &OutputId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_gamma_control_v1::Event::GammaSize { size } => {
+                let mut controls = state.control.lock();
+                let scheduled = controls.scheduled;
+                // A control replaced by a restore may still be answering.
+                let Some(known) = controls
+                    .outputs
+                    .iter_mut()
+                    .find(|known| known.id == *output && &known.control == control)
+                else {
+                    return;
+                };
+                known.size = Some(size);
+                if let Some(gamma) = scheduled
+                    && known.follows_schedule
+                {
+                    let _ = known.set(gamma);
+                }
+                drop(controls);
+                state.events.push(GammaEvent::Output {
+                    output: *output,
+                    size,
+                });
+            }
+            // Also sent for an output that went away, already reported by `output_destroyed`
+            zwlr_gamma_control_v1::Event::Failed => {
+                let current = state
+                    .control
+                    .lock()
+                    .outputs
+                    .iter()
+                    .any(|known| &known.control == control);
+                if current && state.drop_control(*output) {
+                    state.events.push(GammaEvent::Removed(*output));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Worker for NightLight {
+    type Event = GammaEvent;
+    type Config = Option<Schedule>;
+
+    fn disposition(event: &GammaEvent) -> Disposition {
+        match event {
+            GammaEvent::Unsupported => Disposition::Once,
+            _ => Disposition::Incremental,
+        }
+    }
+
+    fn init(
+        conn: &Connection,
+        globals: &GlobalList,
+        qh: &QueueHandle<Self>,
+        schedule: &Option<Schedule>,
+    ) -> Result<Self, Error> {
+        // Absent is not final, compositor may register the global after connect
+        let manager = globals
+            .bind::<ZwlrGammaControlManagerV1, _, _>(qh, 1..=1, ())
+            .ok();
+        let supported = manager.is_some();
+        let control = GammaControl {
+            conn: conn.clone(),
+            qh: qh.clone(),
+            controls: Arc::new(Mutex::new(Controls {
+                manager,
+                outputs: Vec::new(),
+                scheduled: None,
+                dead: false,
+            })),
+        };
+        let events = vec![if supported {
+            GammaEvent::Ready(control.clone())
+        } else {
+            GammaEvent::Unsupported
+        }];
+        Ok(Self {
+            registry_state: RegistryState::new(globals),
+            output_state: OutputState::new(globals, qh),
+            control,
+            schedule: *schedule,
+            next_tick: schedule.map(|_| Instant::now()),
+            events,
+        })
+    }
+
+    fn take_events(&mut self) -> Vec<GammaEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn reset_events(&mut self) -> Vec<GammaEvent> {
+        self.control
+            .outputs()
+            .into_iter()
+            .map(GammaEvent::Removed)
+            .collect()
+    }
+
+    fn stop_event(error: Error) -> GammaEvent {
+        GammaEvent::Stop(error)
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        self.next_tick
+    }
+
+    fn tick(&mut self) {
+        let Some(schedule) = self.schedule else {
+            return;
+        };
+        let (gamma, next) = schedule.at(SystemTime::now());
+        self.next_tick = Some(Instant::now() + next);
+        let mut controls = self.control.lock();
+        if controls.scheduled == Some(gamma) {
+            return;
+        }
+        controls.scheduled = Some(gamma);
+        for known in controls
+            .outputs
+            .iter()
+            .filter(|known| known.follows_schedule && known.size.is_some())
+        {
+            let _ = known.set(gamma);
+        }
+        drop(controls);
+        self.events.push(GammaEvent::Scheduled(gamma));
+    }
+
+    fn teardown(&mut self, _: &mut EventQueue<Self>) {
+        // Destroying the controls is what restores the ramps.
+        let mut controls = self.control.lock();
+        controls.dead = true;
+        for known in controls.outputs.drain(..) {
+            known.control.destroy();
+        }
+        if let Some(manager) = controls.manager.take() {
+            manager.destroy();
+        }
+        drop(controls);
+        crate::output::release_outputs(&self.output_state);
+    }
+}
+
+impl OutputHandler for NightLight {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+    fn new_output(&mut self, _: &Connection, qh: &QueueHandle<Self>, output: WlOutput) {
+        self.control_output(qh, &output);
+    }
+    fn update_output(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlOutput) {}
+    fn output_destroyed(&mut self, _: &Connection, _: &QueueHandle<Self>, output: WlOutput) {
+        if let Some(info) = self.output_state.info(&output)
+            && self.drop_control(OutputId::from(&info))
+        {
+            self.events.push(GammaEvent::Removed(OutputId::from(&info)));
+        }
+    }
+}
+
+impl sctk::registry::RegistryHandler<NightLight> for NightLight {
+    /// The compositor registered the manager after we connected.
+    fn new_global(
+        state: &mut NightLight,
+        _conn: &Connection,
+        qh: &QueueHandle<NightLight>,
+        _name: u32,
+        interface: &str,
+        _version: u32,
+    ) {
+        if interface != ZwlrGammaControlManagerV1::interface().name
+            || state.control.lock().manager.is_some()
+        {
+            return;
+        }
+        let Ok(manager) = state.registry_state.bind_one(qh, 1..=1, ()) else {
+            return;
+        };
+        state.control.lock().manager = Some(manager);
+        state.events.push(GammaEvent::Ready(state.control.clone()));
+        for output in state.output_state.outputs().collect::<Vec<_>>() {
+            state.control_output(qh, &output);
+        }
+    }
+}
+
+impl ProvidesRegistryState for NightLight {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+    registry_handlers![OutputState, NightLight];
+}
+
+delegate_registry!(NightLight);
+delegate_dispatch2!(NightLight);
+delegate_noop!(NightLight: ignore WlCallback);
+delegate_noop!(NightLight: ignore ZwlrGammaControlManagerV1);
+
+/// Control the gamma of every output, following `schedule` if there is one.
+/// Every ramp is restored when the subscription is dropped.
+pub fn listen(
+    connection: Connection,
+    schedule: Option<Schedule>,
+) -> iced_futures::Subscription<GammaEvent> {
+    worker::listen::<NightLight>(connection, schedule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(ramps: &[u8], size: usize) -> Vec<Vec<u16>> {
+        ramps
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|value| u16::from_ne_bytes(*value))
+            .collect::<Vec<_>>()
+            .chunks(size)
+            .map(<[u16]>::to_vec)
+            .collect()
+    }
+
+    #[test]
+    fn ramps_follow_temperature_and_brightness() {
+        let neutral = channels(&ramps(256, Gamma::NEUTRAL), 256);
+        assert_eq!(neutral.len(), 3);
+        for channel in &neutral {
+            assert_eq!((channel[0], channel[255]), (0, u16::MAX));
+        }
+
+        let warm = channels(&ramps(256, Gamma::new(3000).brightness(50)), 256);
+        let [red, green, blue] = [&warm[0], &warm[1], &warm[2]].map(|channel| channel[255]);
+        assert_eq!(red, u16::MAX / 2 + 1);
+        assert!(red > green && green > blue);
+    }
+
+    #[test]
+    fn schedule_fades_across_sunrise_and_sunset() {
+        let schedule = Schedule::new((7, 0), (19, 0))
+            .transition(Duration::from_secs(3600))
+            .night(Gamma::new(3500).brightness(80));
+        let hour = |hours: f64| (hours * 3600.0) as u64;
+
+        assert_eq!(
+            schedule.at_second(hour(3.0)),
+            (schedule.night, Duration::from_secs(4 * 3600))
+        );
+        assert_eq!(
+            schedule.at_second(hour(12.0)),
+            (schedule.day, Duration::from_secs(7 * 3600))
+        );
+        let (halfway, step) = schedule.at_second(hour(19.5));
+        assert_eq!(halfway, Gamma::new(5000).brightness(90));
+        assert_eq!(step, TRANSITION_STEP);
+        assert_eq!(schedule.at_second(hour(7.5)).0, halfway);
+        assert_eq!(schedule.at_second(hour(20.0)).0, schedule.night);
+
+        // 23:30 UTC is 01:30 two hours east
+        let late = UNIX_EPOCH + Duration::from_secs(hour(23.5));
+        assert_eq!(schedule.utc_offset(120).at(late).0, schedule.night);
+        assert_eq!(schedule.utc_offset(-12 * 60).at(late).0, schedule.day);
+    }
+}
@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use wayland_client::{
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, delegate_noop,
+    globals::{GlobalList, GlobalListContents},
+    protocol::{
+        wl_callback::WlCallback,
+        wl_registry::{self, WlRegistry},
+        wl_seat::WlSeat,
+    },
+};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+    ext_idle_notifier_v1::ExtIdleNotifierV1,
+};
+
+use crate::Error;
+use crate::worker::{self, Disposition, Worker};
+
+/// One inactivity timeout to watch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdleTimeout {
+    pub timeout: Duration,
+    /// Report idle even while an inhibitor, like a playing video, is active.
+    /// Needs ext-idle-notifier-v1 version 2, older compositors honor
+    /// inhibitors anyway.
+    pub ignore_inhibitors: bool,
+}
+
+impl IdleTimeout {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            ignore_inhibitors: false,
+        }
+    }
+
+    pub fn ignore_inhibitors(mut self) -> Self {
+        self.ignore_inhibitors = true;
+        self
+    }
+}
+
+/// What the idle subscription hands to the application
+#[derive(Debug)]
+pub enum IdleEvent {
+    /// The user was inactive for `timeout`. A timeout asked for twice is reported twice.
+    Idled(IdleTimeout),
+    /// The user is active again after `Idled` for the same timeout.
+    Resumed(IdleTimeout),
+    /// The compositor does not implement `ext-idle-notify-v1`, or there is no
+    /// seat. Emitted once, at startup, even if the worker restarts.
+    Unsupported,
+    Stop(Error),
+}
+
+/// Dispatch state for the idle worker.
+#[derive(Debug)]
+pub(crate) struct Idle {
+    notifier: Option<ExtIdleNotifierV1>,
+    seat: Option<WlSeat>,
+    /// One per requested timeout, in the order of the request, which is their user data
+    notifications: Vec<(ExtIdleNotificationV1, IdleTimeout)>,
+    /// Requests reported `Idled` and not yet `Resumed`.
+    idled: Vec<usize>,
+    events: Vec<IdleEvent>,
+}
+
+impl Worker for Idle {
+    type Event = IdleEvent;
+    type Config = Vec<IdleTimeout>;
+
+    fn disposition(event: &IdleEvent) -> Disposition {
+        match event {
+            IdleEvent::Unsupported => Disposition::Once,
+            _ => Disposition::Incremental,
+        }
+    }
+
+    fn init(
+        _: &Connection,
+        globals: &GlobalList,
+        qh: &QueueHandle<Self>,
+        timeouts: &Vec<IdleTimeout>,
+    ) -> Result<Self, Error> {
+        let notifier = globals.bind::<ExtIdleNotifierV1, _, _>(qh, 1..=2, ()).ok();
+        let seat = globals.bind::<WlSeat, _, _>(qh, 1..=5, ()).ok();
+        let mut idle = Self {
+            notifier,
+            seat,
+            notifications: Vec::new(),
+            idled: Vec::new(),
+            events: Vec::new(),
+        };
+        let (Some(notifier), Some(seat)) = (&idle.notifier, &idle.seat) else {
+            idle.events.push(IdleEvent::Unsupported);
+            return Ok(idle);
+        };
+        for (request, timeout) in timeouts.iter().enumerate() {
+            // the protocol counts in milliseconds and has no room for more
+            let millis = u32::try_from(timeout.timeout.as_millis()).unwrap_or(u32::MAX);
+            let notification = if timeout.ignore_inhibitors && notifier.version() >= 2 {
+                notifier.get_input_idle_notification(millis, seat, qh, request)
+            } else {
+                notifier.get_idle_notification(millis, seat, qh, request)
+            };
+            idle.notifications.push((notification, *timeout));
+        }
+        Ok(idle)
+    }
+
+    fn take_events(&mut self) -> Vec<IdleEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn reset_events(&mut self) -> Vec<IdleEvent> {
+        let notifications = &self.notifications;
+        self.idled
+            .drain(..)
+            .map(|request| IdleEvent::Resumed(notifications[request].1))
+            .collect()
+    }
+
+    fn stop_event(error: Error) -> IdleEvent {
+        IdleEvent::Stop(error)
+    }
+
+    fn teardown(&mut self, _: &mut EventQueue<Self>) {
+        for (notification, _) in self.notifications.drain(..) {
+            notification.destroy();
+        }
+        if let Some(notifier) = self.notifier.take() {
+            notifier.destroy();
+        }
+        if let Some(seat) = self.seat.take()
+            && seat.version() >= 5
+        {
+            seat.release();
+        }
+    }
+}
+
+impl Dispatch<ExtIdleNotificationV1, usize> for Idle {
+    fn event(
+        state: &mut Self,
+        _: &ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        request: &usize,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let Some(&(_, timeout)) = state.notifications.get(*request) else {
+            return;
+        };
+        match event {
+            ext_idle_notification_v1::Event::Idled if !state.idled.contains(request) => {
+                state.idled.push(*request);
+                state.events.push(IdleEvent::Idled(timeout));
+            }
+            ext_idle_notification_v1::Event::Resumed if state.idled.contains(request) => {
+                state.idled.retain(|idled| idled != request);
+                state.events.push(IdleEvent::Resumed(timeout));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for Idle {
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(Idle: ignore WlCallback);
+delegate_noop!(Idle: ignore WlSeat);
+delegate_noop!(Idle: ignore ExtIdleNotifierV1);
+
+/// Watch for user inactivity, one `Idled`/`Resumed` pair per requested timeout.
+pub fn listen(
+    connection: Connection,
+    timeouts: Vec<IdleTimeout>,
+) -> iced_futures::Subscription<IdleEvent> {
+    worker::listen::<Idle>(connection, timeouts)
+}
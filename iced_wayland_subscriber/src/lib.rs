@@ -7,6 +7,24 @@ pub mod shell;
 #[cfg(feature = "workspace")]
 pub mod workspace;
 
+#[cfg(feature = "idle")]
+pub mod idle;
+
+#[cfg(feature = "toplevel")]
+pub mod toplevel;
+
+#[cfg(feature = "capture")]
+pub mod capture;
+
+#[cfg(feature = "output_management")]
+pub mod output_management;
+
+#[cfg(feature = "power")]
+pub mod power;
+
+#[cfg(feature = "gamma")]
+pub mod gamma;
+
 pub use info::{OutputId, OutputInfo, pixel_size};
 pub use worker::Error;
 
@@ -48,8 +48,14 @@ pub(crate) fn release_outputs(output_state: &OutputState) {
 
 impl Worker for Outputs {
     type Event = OutputEvent;
-
-    fn init(_: &Connection, globals: &GlobalList, qh: &QueueHandle<Self>) -> Result<Self, Error> {
+    type Config = ();
+
+    fn init(
+        _: &Connection,
+        globals: &GlobalList,
+        qh: &QueueHandle<Self>,
+        _: &(),
+    ) -> Result<Self, Error> {
         Ok(Self {
             registry_state: RegistryState::new(globals),
             output_state: OutputState::new(globals, qh),
@@ -108,5 +114,5 @@ delegate_noop!(Outputs: ignore WlCallback);
 
 /// Watch the compositor's outputs
 pub fn listen(connection: Connection) -> iced_futures::Subscription<OutputEvent> {
-    worker::listen::<Outputs>(connection)
+    worker::listen::<Outputs>(connection, ())
 }
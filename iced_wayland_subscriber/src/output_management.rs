@@ -0,0 +1,1046 @@
+use std::future::Future;
+use std::io::ErrorKind;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+
+use futures::channel::oneshot;
+use sctk::{
+    delegate_dispatch2, delegate_registry,
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+};
+use wayland_client::{
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum,
+    backend::{ObjectId, WaylandError},
+    delegate_noop, event_created_child,
+    globals::GlobalList,
+    protocol::wl_callback::WlCallback,
+};
+use wayland_protocols_wlr::output_management::v1::client::{
+    zwlr_output_configuration_head_v1::ZwlrOutputConfigurationHeadV1,
+    zwlr_output_configuration_v1::{self, ZwlrOutputConfigurationV1},
+    zwlr_output_head_v1::{self, ZwlrOutputHeadV1},
+    zwlr_output_manager_v1::{self, ZwlrOutputManagerV1},
+    zwlr_output_mode_v1::{self, ZwlrOutputModeV1},
+};
+
+use crate::Error;
+use crate::worker::{self, Disposition, Worker};
+
+pub use wayland_client::protocol::wl_output::Transform;
+
+/// Identity of a head, stable for the lifetime of the protocol object
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HeadId(ObjectId);
+
+/// Identity of a mode of a head.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModeId(ObjectId);
+
+#[derive(Debug, Clone)]
+pub struct Mode {
+    pub id: ModeId,
+    pub width: i32,
+    pub height: i32,
+    /// Vertical refresh rate in mHz, 0 if unknown
+    pub refresh: i32,
+    pub preferred: bool,
+    handle: ZwlrOutputModeV1,
+}
+
+/// A display the compositor can drive, enabled or not.
+#[derive(Debug, Clone)]
+pub struct Head {
+    pub id: HeadId,
+    /// The connector name, like `DP-1`
+    pub name: String,
+    pub description: String,
+    pub make: String,
+    pub model: String,
+    pub serial_number: String,
+    /// Physical size in millimeters, 0 if unknown
+    pub physical_size: (i32, i32),
+    pub enabled: bool,
+    pub modes: Vec<Mode>,
+    pub current_mode: Option<ModeId>,
+    pub position: (i32, i32),
+    pub transform: Transform,
+    pub scale: f64,
+    /// `None` before version 4 of the protocol
+    pub adaptive_sync: Option<bool>,
+    handle: ZwlrOutputHeadV1,
+}
+
+impl Head {
+    /// The mode the head is currently using
+    pub fn mode(&self) -> Option<&Mode> {
+        let current = self.current_mode.as_ref()?;
+        self.modes.iter().find(|mode| &mode.id == current)
+    }
+
+    fn new(handle: ZwlrOutputHeadV1) -> Self {
+        Self {
+            id: HeadId(handle.id()),
+            name: String::new(),
+            description: String::new(),
+            make: String::new(),
+            model: String::new(),
+            serial_number: String::new(),
+            physical_size: (0, 0),
+            enabled: false,
+            modes: Vec::new(),
+            current_mode: None,
+            position: (0, 0),
+            transform: Transform::Normal,
+            scale: 1.0,
+            adaptive_sync: None,
+            handle,
+        }
+    }
+}
+
+/// The heads as of the last manager `done` event.
+#[derive(Debug, Clone)]
+pub struct OutputManagementSnapshot {
+    pub heads: Vec<Head>,
+    /// Configurations made against an older serial are cancelled.
+    serial: u32,
+    manager: Option<ZwlrOutputManagerV1>,
+    qh: QueueHandle<OutputManagement>,
+    conn: Connection,
+    /// Blocks application requests once the protocol is over
+    stopped: Arc<Mutex<bool>>,
+    /// Set the instant the protocol ends, ahead of `stopped`.
+    dead: Arc<AtomicBool>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RequestError {
+    /// The compositor is too old for a setting of the configuration
+    #[error("the compositor does not support this request")]
+    Unsupported,
+    /// A head or mode of the configuration is gone, or the manager is.
+    #[error("no such head or mode")]
+    Gone,
+    /// A scale or custom mode of the configuration is not positive
+    #[error("invalid scale or mode")]
+    Invalid,
+    #[error("failed to flush the connection")]
+    Io(#[from] wayland_client::backend::WaylandError),
+}
+
+/// How a head should be driven. `None` keeps the current value.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HeadConfiguration {
+    pub enabled: bool,
+    pub mode: Option<ModeSetting>,
+    pub position: Option<(i32, i32)>,
+    pub transform: Option<Transform>,
+    pub scale: Option<f64>,
+    /// Needs version 4 of the protocol
+    pub adaptive_sync: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModeSetting {
+    /// One of the modes the head advertised
+    Mode(ModeId),
+    /// Size in pixels and refresh rate in mHz, all positive
+    Custom {
+        width: i32,
+        height: i32,
+        refresh: i32,
+    },
+}
+
+/// A change to the output layout, made from a snapshot with
+/// [`OutputManagementSnapshot::configuration`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputConfiguration {
+    serial: u32,
+    heads: Vec<(HeadId, HeadConfiguration)>,
+}
+
+impl OutputConfiguration {
+    pub fn head(&self, id: &HeadId) -> Option<&HeadConfiguration> {
+        self.heads
+            .iter()
+            .find(|(head, _)| head == id)
+            .map(|(_, config)| config)
+    }
+
+    pub fn head_mut(&mut self, id: &HeadId) -> Option<&mut HeadConfiguration> {
+        self.heads
+            .iter_mut()
+            .find(|(head, _)| head == id)
+            .map(|(_, config)| config)
+    }
+}
+
+/// How the compositor answered a configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigurationResult {
+    Succeeded,
+    Failed,
+    /// The heads changed since the snapshot, or the protocol ended.
+    Cancelled,
+}
+
+/// Resolves once the compositor answers a configuration.
+#[derive(Debug)]
+#[must_use = "the answer of the compositor is only known by awaiting this"]
+pub struct PendingConfiguration(oneshot::Receiver<ConfigurationResult>);
+
+impl Future for PendingConfiguration {
+    type Output = ConfigurationResult;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<ConfigurationResult> {
+        // The sender is dropped unanswered when the worker stops.
+        Pin::new(&mut self.0)
+            .poll(cx)
+            .map(|result| result.unwrap_or(ConfigurationResult::Cancelled))
+    }
+}
+
+/// Where the answer of a configuration goes.
+#[derive(Debug)]
+struct ConfigurationData(Mutex<Option<oneshot::Sender<ConfigurationResult>>>);
+
+impl OutputManagementSnapshot {
+    pub fn head(&self, id: &HeadId) -> Option<&Head> {
+        self.heads.iter().find(|head| &head.id == id)
+    }
+
+    /// A configuration keeping every head as it is
+    pub fn configuration(&self) -> OutputConfiguration {
+        OutputConfiguration {
+            serial: self.serial,
+            heads: self
+                .heads
+                .iter()
+                .map(|head| {
+                    let config = HeadConfiguration {
+                        enabled: head.enabled,
+                        ..HeadConfiguration::default()
+                    };
+                    (head.id.clone(), config)
+                })
+                .collect(),
+        }
+    }
+
+    /// Ask the compositor whether `config` would work, without applying it
+    pub fn test(&self, config: &OutputConfiguration) -> Result<PendingConfiguration, RequestError> {
+        self.send(config, false)
+    }
+
+    pub fn apply(
+        &self,
+        config: &OutputConfiguration,
+    ) -> Result<PendingConfiguration, RequestError> {
+        self.send(config, true)
+    }
+
+    /// Apply `config` only if the compositor accepts it in a test first
+    pub async fn test_then_apply(
+        &self,
+        config: &OutputConfiguration,
+    ) -> Result<ConfigurationResult, RequestError> {
+        match self.test(config)?.await {
+            ConfigurationResult::Succeeded => Ok(self.apply(config)?.await),
+            result => Ok(result),
+        }
+    }
+
+    /// Check every setting of `config` before anything goes out, a bad one is a protocol error.
+    fn validate(
+        &self,
+        manager: &ZwlrOutputManagerV1,
+        config: &OutputConfiguration,
+    ) -> Result<(), RequestError> {
+        for (id, head_config) in &config.heads {
+            let head = self
+                .head(id)
+                .filter(|head| head.handle.is_alive())
+                .ok_or(RequestError::Gone)?;
+            if !head_config.enabled {
+                continue;
+            }
+            match &head_config.mode {
+                Some(ModeSetting::Mode(mode))
+                    if !head.modes.iter().any(|known| &known.id == mode) =>
+                {
+                    return Err(RequestError::Gone);
+                }
+                Some(ModeSetting::Custom {
+                    width,
+                    height,
+                    refresh,
+                }) if *width <= 0 || *height <= 0 || *refresh <= 0 => {
+                    return Err(RequestError::Invalid);
+                }
+                _ => {}
+            }
+            if let Some(scale) = head_config.scale
+                && !(scale.is_finite() && scale > 0.0)
+            {
+                return Err(RequestError::Invalid);
+            }
+            if head_config.adaptive_sync.is_some() && manager.version() < 4 {
+                return Err(RequestError::Unsupported);
+            }
+        }
+        Ok(())
+    }
+
+    fn send(
+        &self,
+        config: &OutputConfiguration,
+        apply: bool,
+    ) -> Result<PendingConfiguration, RequestError> {
+        if self.dead.load(Ordering::Acquire) {
+            return Err(RequestError::Gone);
+        }
+        let stopped = self.stopped.lock().unwrap_or_else(PoisonError::into_inner);
+        if *stopped || self.dead.load(Ordering::Acquire) {
+            return Err(RequestError::Gone);
+        }
+        let manager = self
+            .manager
+            .as_ref()
+            .filter(|manager| manager.is_alive())
+            .ok_or(RequestError::Gone)?;
+        self.validate(manager, config)?;
+
+        let (sender, receiver) = oneshot::channel();
+        let configuration = manager.create_configuration(
+            config.serial,
+            &self.qh,
+            ConfigurationData(Mutex::new(Some(sender))),
+        );
+        // The protocol wants every head configured, those left out keep their state.
+        for head in &self.heads {
+            let default = HeadConfiguration {
+                enabled: head.enabled,
+                ..HeadConfiguration::default()
+            };
+            let head_config = config.head(&head.id).unwrap_or(&default);
+            if !head_config.enabled {
+                configuration.disable_head(&head.handle);
+                continue;
+            }
+            let head_configuration = configuration.enable_head(&head.handle, &self.qh, ());
+            match &head_config.mode {
+                Some(ModeSetting::Mode(id)) => {
+                    if let Some(mode) = head.modes.iter().find(|mode| &mode.id == id) {
+                        head_configuration.set_mode(&mode.handle);
+                    }
+                }
+                Some(ModeSetting::Custom {
+                    width,
+                    height,
+                    refresh,
+                }) => head_configuration.set_custom_mode(*width, *height, *refresh),
+                None => {}
+            }
+            if let Some((x, y)) = head_config.position {
+                head_configuration.set_position(x, y);
+            }
+            if let Some(transform) = head_config.transform {
+                head_configuration.set_transform(transform);
+            }
+            if let Some(scale) = head_config.scale {
+                head_configuration.set_scale(scale);
+            }
+            if let Some(enabled) = head_config.adaptive_sync {
+                head_configuration.set_adaptive_sync(if enabled {
+                    zwlr_output_head_v1::AdaptiveSyncState::Enabled
+                } else {
+                    zwlr_output_head_v1::AdaptiveSyncState::Disabled
+                });
+            }
+        }
+        if apply {
+            configuration.apply();
+        } else {
+            configuration.test();
+        }
+
+        match self.conn.flush() {
+            Err(WaylandError::Io(error)) if error.kind() == ErrorKind::WouldBlock => {}
+            Err(error) => return Err(error.into()),
+            Ok(()) => {}
+        }
+        Ok(PendingConfiguration(receiver))
+    }
+}
+
+impl Dispatch<ZwlrOutputManagerV1, ()> for OutputManagement {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrOutputManagerV1,
+        event: zwlr_output_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_output_manager_v1::Event::Head { head } => {
+                state.snapshot.heads.push(Head::new(head));
+            }
+            zwlr_output_manager_v1::Event::Done { serial } => {
+                state.snapshot.serial = serial;
+                state.published = Arc::new(state.snapshot.clone());
+                state.saw_done = true;
+            }
+            zwlr_output_manager_v1::Event::Finished => {
+                state.finished = true;
+                state.snapshot.dead.store(true, Ordering::Release);
+                state.snapshot.manager = None;
+                let published = Arc::make_mut(&mut state.published);
+                published.manager = None;
+                published.heads.clear();
+                state.saw_finished = true;
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(OutputManagement, ZwlrOutputManagerV1, [
+        zwlr_output_manager_v1::EVT_HEAD_OPCODE => (ZwlrOutputHeadV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrOutputHeadV1, ()> for OutputManagement {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrOutputHeadV1,
+        event: zwlr_output_head_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwlr_output_head_v1::Event::Finished = event {
+            let head_id = HeadId(handle.id());
+            if !state.stopping && handle.version() >= 3 {
+                let _lifetime = state
+                    .snapshot
+                    .stopped
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                handle.release();
+            }
+            state.snapshot.heads.retain(|head| head.id != head_id);
+            return;
+        }
+        let Some(head) = state.head_mut(handle) else {
+            return;
+        };
+        match event {
+            zwlr_output_head_v1::Event::Name { name } => head.name = name,
+            zwlr_output_head_v1::Event::Description { description } => {
+                head.description = description;
+            }
+            zwlr_output_head_v1::Event::PhysicalSize { width, height } => {
+                head.physical_size = (width, height);
+            }
+            zwlr_output_head_v1::Event::Mode { mode } => {
+                head.modes.push(Mode {
+                    id: ModeId(mode.id()),
+                    width: 0,
+                    height: 0,
+                    refresh: 0,
+                    preferred: false,
+                    handle: mode,
+                });
+            }
+            zwlr_output_head_v1::Event::Enabled { enabled } => {
+                head.enabled = enabled != 0;
+                if !head.enabled {
+                    head.current_mode = None;
+                }
+            }
+            zwlr_output_head_v1::Event::CurrentMode { mode } => {
+                head.current_mode = Some(ModeId(mode.id()));
+            }
+            zwlr_output_head_v1::Event::Position { x, y } => head.position = (x, y),
+            zwlr_output_head_v1::Event::Transform {
+                transform: WEnum::Value(transform),
+            } => head.transform = transform,
+            zwlr_output_head_v1::Event::Scale { scale } => head.scale = scale,
+            zwlr_output_head_v1::Event::Make { make } => head.make = make,
+            zwlr_output_head_v1::Event::Model { model } => head.model = model,
+            zwlr_output_head_v1::Event::SerialNumber { serial_number } => {
+                head.serial_number = serial_number;
+            }
+            zwlr_output_head_v1::Event::AdaptiveSync { state } => {
+                head.adaptive_sync =
+                    Some(state == WEnum::Value(zwlr_output_head_v1::AdaptiveSyncState::Enabled));
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(OutputManagement, ZwlrOutputHeadV1, [
+        zwlr_output_head_v1::EVT_MODE_OPCODE => (ZwlrOutputModeV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrOutputModeV1, ()> for OutputManagement {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrOutputModeV1,
+        event: zwlr_output_mode_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let mode_id = ModeId(handle.id());
+        if let zwlr_output_mode_v1::Event::Finished = event {
+            if !state.stopping && handle.version() >= 3 {
+                let _lifetime = state
+                    .snapshot
+                    .stopped
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                handle.release();
+            }
+            for head in &mut state.snapshot.heads {
+                head.modes.retain(|mode| mode.id != mode_id);
+                if head.current_mode.as_ref() == Some(&mode_id) {
+                    head.current_mode = None;
+                }
+            }
+            return;
+        }
+        let Some(mode) = state
+            .snapshot
+            .heads
+            .iter_mut()
+            .flat_map(|head| &mut head.modes)
+            .find(|mode| mode.id == mode_id)
+        else {
+            return;
+        };
+        match event {
+            zwlr_output_mode_v1::Event::Size { width, height } => {
+                mode.width = width;
+                mode.height = height;
+            }
+            zwlr_output_mode_v1::Event::Refresh { refresh } => mode.refresh = refresh,
+            zwlr_output_mode_v1::Event::Preferred => mode.preferred = true,
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwlrOutputConfigurationV1, ConfigurationData> for OutputManagement {
+    fn event(
+        _: &mut Self,
+        configuration: &ZwlrOutputConfigurationV1,
+        event: zwlr_output_configuration_v1::Event,
+        data: &ConfigurationData,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let result = match event {
+            zwlr_output_configuration_v1::Event::Succeeded => ConfigurationResult::Succeeded,
+            zwlr_output_configuration_v1::Event::Failed => ConfigurationResult::Failed,
+            zwlr_output_configuration_v1::Event::Cancelled => ConfigurationResult::Cancelled,
+            _ => return,
+        };
+        configuration.destroy();
+        if let Some(sender) = data.0.lock().unwrap_or_else(PoisonError::into_inner).take() {
+            let _ = sender.send(result);
+        }
+    }
+}
+
+/// What the output management subscription hands to the application
+#[derive(Debug)]
+pub enum OutputManagementEvent {
+    /// The heads, emitted once per manager `done` event.
+    Updated(Arc<OutputManagementSnapshot>),
+    /// The compositor does not implement `wlr-output-management`. Emitted once, at startup,
+    /// even if the worker restarts.
+    Unsupported,
+    /// The compositor ended the protocol.
+    Finished,
+    Stop(Error),
+}
+
+/// Dispatch state for the output management worker.
+#[derive(Debug)]
+pub(crate) struct OutputManagement {
+    registry_state: RegistryState,
+    /// Working copy that protocol events mutate as they arrive.
+    snapshot: OutputManagementSnapshot,
+    /// The snapshot as of the last manager `done`.
+    published: Arc<OutputManagementSnapshot>,
+    /// Report `Unsupported` once, before any snapshot.
+    announce_unsupported: bool,
+    /// `published` changed since the last drain.
+    saw_done: bool,
+    /// Compositor answered `stop` or ended the protocol.
+    finished: bool,
+    /// `stop` has been sent, so no further request may go out.
+    stopping: bool,
+    /// The protocol ended and the application has not been told yet.
+    saw_finished: bool,
+}
+
+impl OutputManagement {
+    fn head_mut(&mut self, handle: &ZwlrOutputHeadV1) -> Option<&mut Head> {
+        let id = handle.id();
+        self.snapshot.heads.iter_mut().find(|head| head.id.0 == id)
+    }
+}
+
+impl Worker for OutputManagement {
+    type Event = OutputManagementEvent;
+    type Config = ();
+
+    fn disposition(event: &OutputManagementEvent) -> Disposition {
+        match event {
+            OutputManagementEvent::Updated(_) => Disposition::Supersedes,
+            OutputManagementEvent::Finished | OutputManagementEvent::Stop(_) => {
+                Disposition::Terminal
+            }
+            OutputManagementEvent::Unsupported => Disposition::Once,
+        }
+    }
+
+    fn init(
+        conn: &Connection,
+        globals: &GlobalList,
+        qh: &QueueHandle<Self>,
+        _: &(),
+    ) -> Result<Self, Error> {
+        let manager = globals
+            .bind::<ZwlrOutputManagerV1, _, _>(qh, 1..=4, ())
+            .ok();
+        let unsupported = manager.is_none();
+        let snapshot = OutputManagementSnapshot {
+            heads: Vec::new(),
+            serial: 0,
+            manager,
+            qh: qh.clone(),
+            conn: conn.clone(),
+            stopped: Arc::new(Mutex::new(false)),
+            dead: Arc::new(AtomicBool::new(false)),
+        };
+        Ok(Self {
+            registry_state: RegistryState::new(globals),
+            published: Arc::new(snapshot.clone()),
+            snapshot,
+            announce_unsupported: unsupported,
+            saw_done: false,
+            finished: false,
+            stopping: false,
+            saw_finished: false,
+        })
+    }
+
+    fn take_events(&mut self) -> Vec<OutputManagementEvent> {
+        if std::mem::take(&mut self.announce_unsupported) {
+            return vec![OutputManagementEvent::Unsupported];
+        }
+        if std::mem::take(&mut self.saw_finished) {
+            self.saw_done = false;
+            return vec![OutputManagementEvent::Finished];
+        }
+        if std::mem::take(&mut self.saw_done) {
+            vec![OutputManagementEvent::Updated(self.published.clone())]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn reset_events(&mut self) -> Vec<OutputManagementEvent> {
+        if self.published.heads.is_empty() {
+            return Vec::new();
+        }
+        Arc::make_mut(&mut self.published).heads.clear();
+        vec![OutputManagementEvent::Updated(self.published.clone())]
+    }
+
+    fn stop_event(error: Error) -> OutputManagementEvent {
+        OutputManagementEvent::Stop(error)
+    }
+
+    fn teardown(&mut self, queue: &mut EventQueue<Self>) {
+        let mut stopped = self
+            .snapshot
+            .stopped
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        *stopped = true;
+        self.snapshot.dead.store(true, Ordering::Release);
+
+        for head in self.snapshot.heads.drain(..) {
+            if head.handle.version() < 3 {
+                continue;
+            }
+            for mode in &head.modes {
+                mode.handle.release();
+            }
+            head.handle.release();
+        }
+
+        if let Some(manager) = self.snapshot.manager.take() {
+            self.stopping = true;
+            manager.stop();
+            drop(stopped);
+            if queue.roundtrip(self).is_ok() && !self.finished {
+                let _ = queue.roundtrip(self);
+            }
+        }
+    }
+}
+
+impl sctk::registry::RegistryHandler<OutputManagement> for OutputManagement {
+    /// The compositor registered the manager after we connected.
+    fn new_global(
+        state: &mut OutputManagement,
+        _conn: &Connection,
+        qh: &QueueHandle<OutputManagement>,
+        _name: u32,
+        interface: &str,
+        _version: u32,
+    ) {
+        if state.snapshot.manager.is_some() || interface != ZwlrOutputManagerV1::interface().name {
+            return;
+        }
+        if let Ok(manager) = state.registry_state.bind_one(qh, 1..=4, ()) {
+            state.snapshot.manager = Some(manager);
+        }
+    }
+}
+
+impl ProvidesRegistryState for OutputManagement {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+    registry_handlers![OutputManagement];
+}
+
+delegate_registry!(OutputManagement);
+delegate_dispatch2!(OutputManagement);
+delegate_noop!(OutputManagement: ignore WlCallback);
+delegate_noop!(OutputManagement: ignore ZwlrOutputConfigurationHeadV1);
+
+/// Watch the heads of the compositor and configure them.
+pub fn listen(connection: Connection) -> iced_futures::Subscription<OutputManagementEvent> {
+    worker::listen::<OutputManagement>(connection, ())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::os::unix::net::UnixStream;
+    use std::thread::JoinHandle;
+
+    use futures::FutureExt;
+    use rustix::event::{PollFd, PollFlags, Timespec};
+    use wayland_client::globals::registry_queue_init;
+    use wayland_protocols_wlr::output_management::v1::server::{
+        zwlr_output_configuration_head_v1::{self as config_head, ZwlrOutputConfigurationHeadV1},
+        zwlr_output_configuration_v1::{self as config, ZwlrOutputConfigurationV1},
+        zwlr_output_head_v1::ZwlrOutputHeadV1,
+        zwlr_output_manager_v1::{self as manager, ZwlrOutputManagerV1},
+        zwlr_output_mode_v1::ZwlrOutputModeV1,
+    };
+    use wayland_server::{
+        Client, DataInit, Display, DisplayHandle, GlobalDispatch, New, Resource,
+        backend::{ClientData, ClientId, DisconnectReason},
+    };
+
+    use super::*;
+
+    /// Set once the client hangs up
+    #[derive(Default)]
+    struct Hangup(AtomicBool);
+
+    impl ClientData for Hangup {
+        fn disconnected(&self, _: ClientId, _: DisconnectReason) {
+            self.0.store(true, Ordering::Release);
+        }
+    }
+
+    /// A manager answering `test` and `apply` from a script, with one head of two modes
+    struct FakeManager {
+        script: VecDeque<ConfigurationResult>,
+        serial: u32,
+        manager: Option<ZwlrOutputManagerV1>,
+        head: Option<ZwlrOutputHeadV1>,
+        /// The position of the configuration being built
+        position: Option<(i32, i32)>,
+    }
+
+    impl FakeManager {
+        fn answer(&mut self, configuration: &ZwlrOutputConfigurationV1, serial: u32, apply: bool) {
+            if serial != self.serial {
+                configuration.cancelled();
+                return;
+            }
+            match self.script.pop_front().expect("the script ran out") {
+                ConfigurationResult::Succeeded => configuration.succeeded(),
+                ConfigurationResult::Failed => configuration.failed(),
+                ConfigurationResult::Cancelled => configuration.cancelled(),
+            }
+            if let (true, Some((x, y)), Some(head), Some(manager)) =
+                (apply, self.position, &self.head, &self.manager)
+            {
+                head.position(x, y);
+                self.serial += 1;
+                manager.done(self.serial);
+            }
+        }
+    }
+
+    impl GlobalDispatch<ZwlrOutputManagerV1, ()> for FakeManager {
+        fn bind(
+            state: &mut Self,
+            handle: &DisplayHandle,
+            client: &Client,
+            resource: New<ZwlrOutputManagerV1>,
+            _: &(),
+            data_init: &mut DataInit<'_, Self>,
+        ) {
+            let manager = data_init.init(resource, ());
+            let head = client
+                .create_resource::<ZwlrOutputHeadV1, (), Self>(handle, manager.version(), ())
+                .unwrap();
+            manager.head(&head);
+            head.name("DP-1".into());
+            head.description("Fake monitor".into());
+            let mut modes = Vec::new();
+            for (width, height) in [(2560, 1440), (1920, 1080)] {
+                let mode = client
+                    .create_resource::<ZwlrOutputModeV1, (), Self>(handle, manager.version(), ())
+                    .unwrap();
+                head.mode(&mode);
+                mode.size(width, height);
+                mode.refresh(60_000);
+                modes.push(mode);
+            }
+            modes[0].preferred();
+            head.enabled(1);
+            head.current_mode(&modes[0]);
+            head.position(0, 0);
+            head.scale(1.0);
+            manager.done(state.serial);
+            state.manager = Some(manager);
+            state.head = Some(head);
+        }
+    }
+
+    impl wayland_server::Dispatch<ZwlrOutputManagerV1, ()> for FakeManager {
+        fn request(
+            state: &mut Self,
+            _: &Client,
+            resource: &ZwlrOutputManagerV1,
+            request: manager::Request,
+            _: &(),
+            _: &DisplayHandle,
+            data_init: &mut DataInit<'_, Self>,
+        ) {
+            match request {
+                manager::Request::CreateConfiguration { id, serial } => {
+                    state.position = None;
+                    data_init.init(id, serial);
+                }
+                manager::Request::Stop => resource.finished(),
+                _ => {}
+            }
+        }
+    }
+
+    impl wayland_server::Dispatch<ZwlrOutputConfigurationV1, u32> for FakeManager {
+        fn request(
+            state: &mut Self,
+            _: &Client,
+            resource: &ZwlrOutputConfigurationV1,
+            request: config::Request,
+            serial: &u32,
+            _: &DisplayHandle,
+            data_init: &mut DataInit<'_, Self>,
+        ) {
+            match request {
+                config::Request::EnableHead { id, .. } => {
+                    data_init.init(id, ());
+                }
+                config::Request::Test => state.answer(resource, *serial, false),
+                config::Request::Apply => state.answer(resource, *serial, true),
+                _ => {}
+            }
+        }
+    }
+
+    impl wayland_server::Dispatch<ZwlrOutputConfigurationHeadV1, ()> for FakeManager {
+        fn request(
+            state: &mut Self,
+            _: &Client,
+            _: &ZwlrOutputConfigurationHeadV1,
+            request: config_head::Request,
+            _: &(),
+            _: &DisplayHandle,
+            _: &mut DataInit<'_, Self>,
+        ) {
+            if let config_head::Request::SetPosition { x, y } = request {
+                state.position = Some((x, y));
+            }
+        }
+    }
+
+    impl wayland_server::Dispatch<ZwlrOutputHeadV1, ()> for FakeManager {
+        fn request(
+            _: &mut Self,
+            _: &Client,
+            _: &ZwlrOutputHeadV1,
+            _: <ZwlrOutputHeadV1 as Resource>::Request,
+            _: &(),
+            _: &DisplayHandle,
+            _: &mut DataInit<'_, Self>,
+        ) {
+        }
+    }
+
+    impl wayland_server::Dispatch<ZwlrOutputModeV1, ()> for FakeManager {
+        fn request(
+            _: &mut Self,
+            _: &Client,
+            _: &ZwlrOutputModeV1,
+            _: <ZwlrOutputModeV1 as Resource>::Request,
+            _: &(),
+            _: &DisplayHandle,
+            _: &mut DataInit<'_, Self>,
+        ) {
+        }
+    }
+
+    /// Serve `script` on a thread until the client hangs up
+    fn serve(script: Vec<ConfigurationResult>) -> (Connection, JoinHandle<()>) {
+        let (client, server) = UnixStream::pair().unwrap();
+        let thread = std::thread::spawn(move || {
+            let mut display = Display::<FakeManager>::new().unwrap();
+            let mut handle = display.handle();
+            handle.create_global::<FakeManager, ZwlrOutputManagerV1, ()>(4, ());
+            let hangup = Arc::new(Hangup::default());
+            handle.insert_client(server, hangup.clone()).unwrap();
+            let mut state = FakeManager {
+                script: script.into(),
+                serial: 1,
+                manager: None,
+                head: None,
+                position: None,
+            };
+            let timeout = Timespec {
+                tv_sec: 0,
+                tv_nsec: 10_000_000,
+            };
+            while !hangup.0.load(Ordering::Acquire) {
+                let fd = display.backend().poll_fd();
+                let mut fds = [PollFd::new(&fd, PollFlags::IN)];
+                let _ = rustix::event::poll(&mut fds, Some(&timeout));
+                if display.dispatch_clients(&mut state).is_err() {
+                    break;
+                }
+                let _ = display.flush_clients();
+            }
+        });
+        (Connection::from_socket(client).unwrap(), thread)
+    }
+
+    /// Roundtrip until `future` resolves
+    fn resolve<F: Future + Unpin>(
+        queue: &mut EventQueue<OutputManagement>,
+        state: &mut OutputManagement,
+        mut future: F,
+    ) -> F::Output {
+        for _ in 0..10 {
+            queue.roundtrip(state).unwrap();
+            if let Some(output) = (&mut future).now_or_never() {
+                return output;
+            }
+        }
+        panic!("the fake manager never answered");
+    }
+
+    fn updated(state: &mut OutputManagement) -> Arc<OutputManagementSnapshot> {
+        match state.take_events().pop() {
+            Some(OutputManagementEvent::Updated(snapshot)) => snapshot,
+            other => panic!("expected a snapshot, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn configurations_follow_the_script() {
+        let (conn, server) = serve(vec![
+            ConfigurationResult::Failed,
+            ConfigurationResult::Succeeded,
+            ConfigurationResult::Succeeded,
+        ]);
+        let (globals, mut queue) = registry_queue_init::<OutputManagement>(&conn).unwrap();
+        let qh = queue.handle();
+        let mut state = OutputManagement::init(&conn, &globals, &qh, &()).unwrap();
+        queue.roundtrip(&mut state).unwrap();
+
+        let snapshot = updated(&mut state);
+        let [head] = snapshot.heads.as_slice() else {
+            panic!("expected one head");
+        };
+        assert_eq!(head.name, "DP-1");
+        assert_eq!(head.modes.len(), 2);
+        let mode = head.mode().unwrap();
+        assert_eq!(
+            (mode.width, mode.height, mode.preferred),
+            (2560, 1440, true)
+        );
+
+        let mut config = snapshot.configuration();
+        // Rejected before reaching the compositor
+        config.head_mut(&head.id).unwrap().scale = Some(0.0);
+        assert!(matches!(snapshot.test(&config), Err(RequestError::Invalid)));
+        config.head_mut(&head.id).unwrap().scale = None;
+        for (width, height, refresh) in [(0, 1080, 60000), (1920, -1, 60000), (1920, 1080, 0)] {
+            config.head_mut(&head.id).unwrap().mode = Some(ModeSetting::Custom {
+                width,
+                height,
+                refresh,
+            });
+            assert!(matches!(snapshot.test(&config), Err(RequestError::Invalid)));
+        }
+        config.head_mut(&head.id).unwrap().mode = None;
+
+        config.head_mut(&head.id).unwrap().position = Some((1920, 0));
+
+        // Failed in the test, so never applied
+        let result = resolve(
+            &mut queue,
+            &mut state,
+            Box::pin(snapshot.test_then_apply(&config)),
+        );
+        assert_eq!(result.unwrap(), ConfigurationResult::Failed);
+        assert!(state.take_events().is_empty());
+
+        let result = resolve(
+            &mut queue,
+            &mut state,
+            Box::pin(snapshot.test_then_apply(&config)),
+        );
+        assert_eq!(result.unwrap(), ConfigurationResult::Succeeded);
+        assert_eq!(updated(&mut state).heads[0].position, (1920, 0));
+
+        // The serial moved on, so the old configuration is stale
+        let pending = snapshot.test(&config).unwrap();
+        let result = resolve(&mut queue, &mut state, pending);
+        assert_eq!(result, ConfigurationResult::Cancelled);
+
+        state.teardown(&mut queue);
+        drop((snapshot, state, queue, conn));
+        server.join().unwrap();
+    }
+}
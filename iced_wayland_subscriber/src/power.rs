@@ -0,0 +1,285 @@
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use sctk::{
+    delegate_dispatch2, delegate_registry,
+    output::{OutputHandler, OutputState},
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+};
+use wayland_client::{
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum,
+    backend::WaylandError,
+    delegate_noop,
+    globals::GlobalList,
+    protocol::{wl_callback::WlCallback, wl_output::WlOutput},
+};
+use wayland_protocols_wlr::output_power_management::v1::client::{
+    zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1,
+    zwlr_output_power_v1::{self, ZwlrOutputPowerV1},
+};
+
+use crate::Error;
+use crate::info::OutputId;
+use crate::worker::{self, Disposition, Worker};
+
+pub use zwlr_output_power_v1::Mode as PowerMode;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RequestError {
+    /// The output is gone, its power can no longer be controlled, or the subscription stopped.
+    #[error("no such output")]
+    Gone,
+    #[error("failed to flush the connection")]
+    Io(#[from] wayland_client::backend::WaylandError),
+}
+
+/// Turns outputs on and off, handed out with [`PowerEvent::Ready`].
+#[derive(Debug, Clone)]
+pub struct PowerControl {
+    conn: Connection,
+    /// The worker destroys controls under this lock, so a request never hits a dead one.
+    controls: Arc<Mutex<Vec<(OutputId, ZwlrOutputPowerV1)>>>,
+    /// Set once the subscription stops.
+    dead: Arc<AtomicBool>,
+}
+
+impl PowerControl {
+    fn lock(&self) -> MutexGuard<'_, Vec<(OutputId, ZwlrOutputPowerV1)>> {
+        self.controls.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The outputs whose power can be controlled
+    pub fn outputs(&self) -> Vec<OutputId> {
+        self.lock().iter().map(|(output, _)| *output).collect()
+    }
+
+    /// Turn `output` on or off. The change is confirmed by a [`PowerEvent::Mode`].
+    pub fn set_power(&self, output: OutputId, mode: PowerMode) -> Result<(), RequestError> {
+        if self.dead.load(Ordering::Acquire) {
+            return Err(RequestError::Gone);
+        }
+        let controls = self.lock();
+        if self.dead.load(Ordering::Acquire) {
+            return Err(RequestError::Gone);
+        }
+        let control = controls
+            .iter()
+            .find(|(id, control)| *id == output && control.is_alive())
+            .map(|(_, control)| control)
+            .ok_or(RequestError::Gone)?;
+        control.set_mode(mode);
+
+        match self.conn.flush() {
+            Err(WaylandError::Io(error)) if error.kind() == ErrorKind::WouldBlock => Ok(()),
+            Err(error) => Err(error.into()),
+            Ok(()) => Ok(()),
+        }
+    }
+}
+
+/// What the output power subscription hands to the application
+#[derive(Debug)]
+pub enum PowerEvent {
+    /// The compositor supports output power management. Emitted once, before any `Mode`.
+    Ready(PowerControl),
+    /// The power mode of an output, when it is first known and whenever it changes.
+    Mode {
+        output: OutputId,
+        mode: PowerMode,
+    },
+    /// The power of `output` can no longer be controlled, because the output
+    /// went away or another client controls it.
+    Removed(OutputId),
+    /// The compositor does not implement `wlr-output-power-management`. Emitted once, at startup,
+    /// even if the worker restarts.
+    Unsupported,
+    Stop(Error),
+}
+
+/// Dispatch state for the output power worker.
+#[derive(Debug)]
+pub(crate) struct Power {
+    registry_state: RegistryState,
+    output_state: OutputState,
+    manager: Option<ZwlrOutputPowerManagerV1>,
+    control: PowerControl,
+    events: Vec<PowerEvent>,
+}
+
+impl Power {
+    fn control_output(&mut self, qh: &QueueHandle<Self>, output: &WlOutput) {
+        let (Some(manager), Some(info)) = (&self.manager, self.output_state.info(output)) else {
+            return;
+        };
+        let id = OutputId::from(&info);
+        let mut controls = self.control.lock();
+        if controls.iter().all(|(known, _)| *known != id) {
+            controls.push((id, manager.get_output_power(output, qh, id)));
+        }
+    }
+
+    /// Destroy the control of `output`, returns whether there was one.
+    fn drop_control(&mut self, output: OutputId) -> bool {
+        let mut controls = self.control.lock();
+        let Some(index) = controls.iter().position(|(id, _)| *id == output) else {
+            return false;
+        };
+        controls.remove(index).1.destroy();
+        true
+    }
+}
+
+impl Dispatch<ZwlrOutputPowerV1, OutputId> for Power {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrOutputPowerV1,
+        event: zwlr_output_power_v1::Event,
+        output: &OutputId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_output_power_v1::Event::Mode {
+                mode: WEnum::Value(mode),
+            } => state.events.push(PowerEvent::Mode {
+                output: *output,
+                mode,
+            }),
+            // Also sent for an output that went away, already reported by `output_destroyed`
+            zwlr_output_power_v1::Event::Failed if state.drop_control(*output) => {
+                state.events.push(PowerEvent::Removed(*output));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Worker for Power {
+    type Event = PowerEvent;
+    type Config = ();
+
+    fn disposition(event: &PowerEvent) -> Disposition {
+        match event {
+            PowerEvent::Unsupported => Disposition::Once,
+            _ => Disposition::Incremental,
+        }
+    }
+
+    fn init(
+        conn: &Connection,
+        globals: &GlobalList,
+        qh: &QueueHandle<Self>,
+        _: &(),
+    ) -> Result<Self, Error> {
+        // Absent is not final, compositor may register the global after connect
+        let manager = globals
+            .bind::<ZwlrOutputPowerManagerV1, _, _>(qh, 1..=1, ())
+            .ok();
+        let control = PowerControl {
+            conn: conn.clone(),
+            controls: Arc::new(Mutex::new(Vec::new())),
+            dead: Arc::new(AtomicBool::new(false)),
+        };
+        let events = vec![if manager.is_some() {
+            PowerEvent::Ready(control.clone())
+        } else {
+            PowerEvent::Unsupported
+        }];
+        Ok(Self {
+            registry_state: RegistryState::new(globals),
+            output_state: OutputState::new(globals, qh),
+            manager,
+            control,
+            events,
+        })
+    }
+
+    fn take_events(&mut self) -> Vec<PowerEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn reset_events(&mut self) -> Vec<PowerEvent> {
+        self.control
+            .outputs()
+            .into_iter()
+            .map(PowerEvent::Removed)
+            .collect()
+    }
+
+    fn stop_event(error: Error) -> PowerEvent {
+        PowerEvent::Stop(error)
+    }
+
+    fn teardown(&mut self, _: &mut EventQueue<Self>) {
+        let mut controls = self.control.lock();
+        self.control.dead.store(true, Ordering::Release);
+        for (_, control) in controls.drain(..) {
+            control.destroy();
+        }
+        drop(controls);
+        if let Some(manager) = self.manager.take() {
+            manager.destroy();
+        }
+        crate::output::release_outputs(&self.output_state);
+    }
+}
+
+impl OutputHandler for Power {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+    fn new_output(&mut self, _: &Connection, qh: &QueueHandle<Self>, output: WlOutput) {
+        self.control_output(qh, &output);
+    }
+    fn update_output(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlOutput) {}
+    fn output_destroyed(&mut self, _: &Connection, _: &QueueHandle<Self>, output: WlOutput) {
+        if let Some(info) = self.output_state.info(&output)
+            && self.drop_control(OutputId::from(&info))
+        {
+            self.events.push(PowerEvent::Removed(OutputId::from(&info)));
+        }
+    }
+}
+
+impl sctk::registry::RegistryHandler<Power> for Power {
+    /// The compositor registered the manager after we connected.
+    fn new_global(
+        state: &mut Power,
+        _conn: &Connection,
+        qh: &QueueHandle<Power>,
+        _name: u32,
+        interface: &str,
+        _version: u32,
+    ) {
+        if state.manager.is_some() || interface != ZwlrOutputPowerManagerV1::interface().name {
+            return;
+        }
+        let Ok(manager) = state.registry_state.bind_one(qh, 1..=1, ()) else {
+            return;
+        };
+        state.manager = Some(manager);
+        state.events.push(PowerEvent::Ready(state.control.clone()));
+        for output in state.output_state.outputs().collect::<Vec<_>>() {
+            state.control_output(qh, &output);
+        }
+    }
+}
+
+impl ProvidesRegistryState for Power {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+    registry_handlers![OutputState, Power];
+}
+
+delegate_registry!(Power);
+delegate_dispatch2!(Power);
+delegate_noop!(Power: ignore WlCallback);
+delegate_noop!(Power: ignore ZwlrOutputPowerManagerV1);
+
+/// Watch the power mode of every output, and control it.
+pub fn listen(connection: Connection) -> iced_futures::Subscription<PowerEvent> {
+    worker::listen::<Power>(connection, ())
+}
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::hash::{Hash, Hasher};
 use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 
@@ -15,6 +15,7 @@ pub enum ShellType {
     PopUp,
     XdgTopLevel,
     InputPanel,
+    InputMethodPopup,
     SessionLock,
 }
 
@@ -25,6 +26,56 @@ pub struct ShellInfo {
     pub shell: ShellType,
 }
 
+/// A touchpad gesture, deltas are in surface coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    SwipeBegin {
+        fingers: u32,
+    },
+    SwipeUpdate {
+        dx: f64,
+        dy: f64,
+    },
+    SwipeEnd {
+        cancelled: bool,
+    },
+    PinchBegin {
+        fingers: u32,
+    },
+    /// `scale` is relative to the begin of the pinch, `rotation` is the
+    /// change in degrees since the last update.
+    PinchUpdate {
+        dx: f64,
+        dy: f64,
+        scale: f64,
+        rotation: f64,
+    },
+    PinchEnd {
+        cancelled: bool,
+    },
+    HoldBegin {
+        fingers: u32,
+    },
+    HoldEnd {
+        cancelled: bool,
+    },
+}
+
+/// Where a compose or dead key sequence is after a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compose {
+    /// A sequence started or continued, show an indicator.
+    Composing,
+    /// The sequence produced its text.
+    Composed,
+    /// The sequence was dropped.
+    Cancelled,
+}
+
+/// Identity of a seat, a group of input devices such as a keyboard and a pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SeatId(pub u64);
+
 #[derive(Debug, Clone)]
 #[allow(clippy::large_enum_variant)]
 pub enum ShellEvent {
@@ -49,6 +100,32 @@ pub enum ShellEvent {
     LockDenied,
     /// The lock is finished
     LockedFinished,
+    /// The xdg-activation token requested for window, pass it to a spawned
+    /// process as `XDG_ACTIVATION_TOKEN`
+    ActivationToken { window: Id, token: String },
+    /// A touchpad gesture over window
+    Gesture { window: Id, gesture: Gesture },
+    /// A key pressed in window took part in a compose sequence
+    Compose { window: Id, state: Compose },
+    /// The keyboard switched to the layout `index` of `layouts`, names are empty when the
+    /// keymap does not set them
+    KeyboardLayoutChanged {
+        index: u32,
+        name: String,
+        layouts: Vec<String>,
+    },
+    /// Raw pointer motion over window, `dx_unaccel`/`dy_unaccel` are before acceleration
+    RelativeMotion {
+        window: Id,
+        dx: f64,
+        dy: f64,
+        dx_unaccel: f64,
+        dy_unaccel: f64,
+    },
+    /// A seat appeared, the seats present at start are announced too.
+    SeatAdded(SeatId),
+    /// A seat went away.
+    SeatRemoved(SeatId),
 }
 
 #[derive(Default)]
@@ -61,6 +138,8 @@ struct Registry {
     /// monitors still connected, keyed by `wl_registry` global name and
     /// replayed to new subscribers
     monitors: BTreeMap<u32, OutputInfo>,
+    /// seats still present, replayed to new subscribers
+    seats: BTreeSet<SeatId>,
 }
 
 /// Create the two ends of a shell broadcast.
@@ -132,6 +211,9 @@ impl ShellReceiver {
             for monitor in registry.monitors.values() {
                 let _ = sender.unbounded_send(ShellEvent::OutputAdded(monitor.clone()));
             }
+            for seat in &registry.seats {
+                let _ = sender.unbounded_send(ShellEvent::SeatAdded(*seat));
+            }
             for (window, shell) in &registry.shells {
                 let _ = sender.unbounded_send(ShellEvent::NewShell(ShellInfo {
                     window: *window,
@@ -163,10 +245,10 @@ fn publish(registry: &mut Registry, event: ShellEvent) {
             registry.outputs.remove(window);
             registry.shells.remove(window);
         }
-        ShellEvent::WindowOutputChanged { window, output } => {
-            if registry.shells.contains_key(window) {
-                registry.outputs.insert(*window, output.clone());
-            }
+        ShellEvent::WindowOutputChanged { window, output }
+            if registry.shells.contains_key(window) =>
+        {
+            registry.outputs.insert(*window, output.clone());
         }
         ShellEvent::OutputAdded(info) | ShellEvent::OutputUpdated(info) => {
             registry.monitors.insert(info.id, info.clone());
@@ -179,6 +261,12 @@ fn publish(registry: &mut Registry, event: ShellEvent) {
         ShellEvent::OutputRemoved(info) => {
             registry.monitors.remove(&info.id);
         }
+        ShellEvent::SeatAdded(seat) => {
+            registry.seats.insert(*seat);
+        }
+        ShellEvent::SeatRemoved(seat) => {
+            registry.seats.remove(seat);
+        }
         _ => {}
     }
     registry
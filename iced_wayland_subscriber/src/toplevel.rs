@@ -0,0 +1,724 @@
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use sctk::{
+    delegate_dispatch2, delegate_registry,
+    output::{OutputHandler, OutputState},
+    registry::{ProvidesRegistryState, RegistryState},
+    registry_handlers,
+};
+use wayland_client::{
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle,
+    backend::{ObjectId, WaylandError},
+    delegate_noop, event_created_child,
+    globals::GlobalList,
+    protocol::{wl_callback::WlCallback, wl_output::WlOutput, wl_seat::WlSeat},
+};
+use wayland_protocols::ext::foreign_toplevel_list::v1::client::{
+    ext_foreign_toplevel_handle_v1::{self, ExtForeignToplevelHandleV1},
+    ext_foreign_toplevel_list_v1::{self, ExtForeignToplevelListV1},
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+
+use crate::Error;
+use crate::info::OutputInfo;
+use crate::worker::{self, Disposition, Worker};
+
+pub use zwlr_foreign_toplevel_handle_v1::State;
+
+/// Identity of a toplevel, stable for the lifetime of the protocol object
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ToplevelId(ObjectId);
+
+#[derive(Debug, Clone)]
+enum Handle {
+    Ext(ExtForeignToplevelHandleV1),
+    Wlr(ZwlrForeignToplevelHandleV1),
+}
+
+impl Handle {
+    fn id(&self) -> ObjectId {
+        match self {
+            Self::Ext(handle) => handle.id(),
+            Self::Wlr(handle) => handle.id(),
+        }
+    }
+
+    fn is_alive(&self) -> bool {
+        match self {
+            Self::Ext(handle) => handle.is_alive(),
+            Self::Wlr(handle) => handle.is_alive(),
+        }
+    }
+
+    fn destroy(&self) {
+        match self {
+            Self::Ext(handle) => handle.destroy(),
+            Self::Wlr(handle) => handle.destroy(),
+        }
+    }
+}
+
+/// An open window of any client
+#[derive(Debug, Clone)]
+pub struct Toplevel {
+    pub id: ToplevelId,
+    pub title: String,
+    pub app_id: String,
+    /// The stable identifier of `ext-foreign-toplevel-list-v1`, `None` with the wlr protocol
+    pub identifier: Option<String>,
+    /// Always empty with `ext-foreign-toplevel-list-v1`, which does not report states
+    pub states: Vec<State>,
+    /// The outputs the toplevel is shown on, resolved when published so carry full info
+    pub outputs: Vec<OutputInfo>,
+    /// The toplevel this one is a dialog or child window of
+    pub parent: Option<ToplevelId>,
+    handle: Handle,
+    /// Every `wl_output` the compositor named for this toplevel.
+    bound_outputs: Vec<WlOutput>,
+}
+
+impl Toplevel {
+    fn new(handle: Handle) -> Self {
+        Self {
+            id: ToplevelId(handle.id()),
+            title: String::new(),
+            app_id: String::new(),
+            identifier: None,
+            states: Vec::new(),
+            outputs: Vec::new(),
+            parent: None,
+            handle,
+            bound_outputs: Vec::new(),
+        }
+    }
+
+    pub fn is(&self, state: State) -> bool {
+        self.states.contains(&state)
+    }
+}
+
+/// The open toplevels, each as of its last `done` event.
+#[derive(Debug, Clone)]
+pub struct ToplevelSnapshot {
+    pub toplevels: Vec<Toplevel>,
+    /// The seat `activate` is sent for
+    seat: Option<WlSeat>,
+    conn: Connection,
+    /// Blocks application requests once the protocol is over
+    stopped: Arc<Mutex<bool>>,
+    /// Set the instant the protocol ends, ahead of `stopped`, so a request
+    /// waiting on that lock cannot fire at an already-destroyed handle.
+    dead: Arc<AtomicBool>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RequestError {
+    /// The compositor only implements `ext-foreign-toplevel-list-v1`, which has
+    /// no requests, or a version too old for this one
+    #[error("the compositor does not support this request")]
+    Unsupported,
+    /// No such toplevel in this snapshot, or it was closed.
+    #[error("no such toplevel")]
+    Gone,
+    #[error("failed to flush the connection")]
+    Io(#[from] wayland_client::backend::WaylandError),
+}
+
+impl ToplevelSnapshot {
+    pub fn toplevel(&self, id: &ToplevelId) -> Option<&Toplevel> {
+        self.toplevels.iter().find(|toplevel| &toplevel.id == id)
+    }
+
+    /// The toplevel with the keyboard focus
+    pub fn activated(&self) -> Option<&Toplevel> {
+        self.toplevels
+            .iter()
+            .find(|toplevel| toplevel.is(State::Activated))
+    }
+
+    /// Dialogs and child windows of `parent`
+    pub fn children_of(&self, parent: &ToplevelId) -> impl Iterator<Item = &Toplevel> {
+        self.toplevels
+            .iter()
+            .filter(move |toplevel| toplevel.parent.as_ref() == Some(parent))
+    }
+
+    fn live_handle(&self, id: &ToplevelId) -> Result<&ZwlrForeignToplevelHandleV1, RequestError> {
+        let toplevel = self
+            .toplevel(id)
+            .filter(|toplevel| toplevel.handle.is_alive())
+            .ok_or(RequestError::Gone)?;
+        match &toplevel.handle {
+            Handle::Wlr(handle) => Ok(handle),
+            Handle::Ext(_) => Err(RequestError::Unsupported),
+        }
+    }
+
+    fn send(
+        &self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        request: impl FnOnce(),
+    ) -> Result<(), RequestError> {
+        if self.dead.load(Ordering::Acquire) {
+            return Err(RequestError::Gone);
+        }
+        let stopped = self.stopped.lock().unwrap_or_else(PoisonError::into_inner);
+        if *stopped || self.dead.load(Ordering::Acquire) || !handle.is_alive() {
+            return Err(RequestError::Gone);
+        }
+
+        request();
+
+        match self.conn.flush() {
+            Err(WaylandError::Io(error)) if error.kind() == ErrorKind::WouldBlock => Ok(()),
+            Err(error) => Err(error.into()),
+            Ok(()) => Ok(()),
+        }
+    }
+
+    /// Focus `id`, raising and unminimizing it as the compositor sees fit
+    pub fn activate(&self, id: &ToplevelId) -> Result<(), RequestError> {
+        let handle = self.live_handle(id)?;
+        let seat = self
+            .seat
+            .as_ref()
+            .filter(|seat| seat.is_alive())
+            .ok_or(RequestError::Unsupported)?;
+        self.send(handle, || handle.activate(seat))
+    }
+
+    /// Ask the client of `id` to close it, it may refuse or ask the user first
+    pub fn close(&self, id: &ToplevelId) -> Result<(), RequestError> {
+        let handle = self.live_handle(id)?;
+        self.send(handle, || handle.close())
+    }
+
+    pub fn set_minimized(&self, id: &ToplevelId, minimized: bool) -> Result<(), RequestError> {
+        let handle = self.live_handle(id)?;
+        self.send(handle, || {
+            if minimized {
+                handle.set_minimized();
+            } else {
+                handle.unset_minimized();
+            }
+        })
+    }
+
+    pub fn set_maximized(&self, id: &ToplevelId, maximized: bool) -> Result<(), RequestError> {
+        let handle = self.live_handle(id)?;
+        self.send(handle, || {
+            if maximized {
+                handle.set_maximized();
+            } else {
+                handle.unset_maximized();
+            }
+        })
+    }
+
+    /// Fullscreen `id` on the output of the compositor's choice. Needs version 2 of
+    /// `zwlr_foreign_toplevel_manager_v1`.
+    pub fn set_fullscreen(&self, id: &ToplevelId, fullscreen: bool) -> Result<(), RequestError> {
+        let handle = self.live_handle(id)?;
+        if handle.version() < 2 {
+            return Err(RequestError::Unsupported);
+        }
+        self.send(handle, || {
+            if fullscreen {
+                handle.set_fullscreen(None);
+            } else {
+                handle.unset_fullscreen();
+            }
+        })
+    }
+}
+
+/// The wlr protocol sends states as a raw array of native-endian `u32`s
+fn decode_states(bytes: &[u8]) -> Vec<State> {
+    bytes
+        .as_chunks::<4>()
+        .0
+        .iter()
+        .filter_map(|chunk| State::try_from(u32::from_ne_bytes(*chunk)).ok())
+        .collect()
+}
+
+impl Dispatch<ExtForeignToplevelListV1, ()> for Toplevels {
+    fn event(
+        state: &mut Self,
+        _: &ExtForeignToplevelListV1,
+        event: ext_foreign_toplevel_list_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_foreign_toplevel_list_v1::Event::Toplevel { toplevel } => {
+                state.pending.push(Toplevel::new(Handle::Ext(toplevel)));
+            }
+            ext_foreign_toplevel_list_v1::Event::Finished => state.finish(),
+            _ => {}
+        }
+    }
+
+    event_created_child!(Toplevels, ExtForeignToplevelListV1, [
+        ext_foreign_toplevel_list_v1::EVT_TOPLEVEL_OPCODE => (ExtForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ExtForeignToplevelHandleV1, ()> for Toplevels {
+    fn event(
+        state: &mut Self,
+        handle: &ExtForeignToplevelHandleV1,
+        event: ext_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let id = handle.id();
+        match event {
+            ext_foreign_toplevel_handle_v1::Event::Title { title } => {
+                if let Some(toplevel) = state.pending_mut(&id) {
+                    toplevel.title = title;
+                }
+            }
+            ext_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
+                if let Some(toplevel) = state.pending_mut(&id) {
+                    toplevel.app_id = app_id;
+                }
+            }
+            ext_foreign_toplevel_handle_v1::Event::Identifier { identifier } => {
+                if let Some(toplevel) = state.pending_mut(&id) {
+                    toplevel.identifier = Some(identifier);
+                }
+            }
+            ext_foreign_toplevel_handle_v1::Event::Done => state.commit(&id),
+            ext_foreign_toplevel_handle_v1::Event::Closed => {
+                state.closed(&id, || handle.destroy());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for Toplevels {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } => {
+                state.pending.push(Toplevel::new(Handle::Wlr(toplevel)));
+            }
+            zwlr_foreign_toplevel_manager_v1::Event::Finished => state.finish(),
+            _ => {}
+        }
+    }
+
+    event_created_child!(Toplevels, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for Toplevels {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let id = handle.id();
+        match event {
+            zwlr_foreign_toplevel_handle_v1::Event::Title { title } => {
+                if let Some(toplevel) = state.pending_mut(&id) {
+                    toplevel.title = title;
+                }
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
+                if let Some(toplevel) = state.pending_mut(&id) {
+                    toplevel.app_id = app_id;
+                }
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::OutputEnter { output } => {
+                if let Some(toplevel) = state.pending_mut(&id)
+                    && !toplevel.bound_outputs.contains(&output)
+                {
+                    toplevel.bound_outputs.push(output);
+                }
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::OutputLeave { output } => {
+                if let Some(toplevel) = state.pending_mut(&id) {
+                    toplevel
+                        .bound_outputs
+                        .retain(|existing| existing != &output);
+                }
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::State { state: states } => {
+                if let Some(toplevel) = state.pending_mut(&id) {
+                    toplevel.states = decode_states(&states);
+                }
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Parent { parent } => {
+                if let Some(toplevel) = state.pending_mut(&id) {
+                    toplevel.parent = parent.map(|parent| ToplevelId(parent.id()));
+                }
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Done => state.commit(&id),
+            zwlr_foreign_toplevel_handle_v1::Event::Closed => {
+                state.closed(&id, || handle.destroy());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// What the toplevel subscription hands to the application
+#[derive(Debug)]
+pub enum ToplevelEvent {
+    /// The open toplevels, emitted once per toplevel `done` or `closed` event.
+    Updated(Arc<ToplevelSnapshot>),
+    /// The compositor implements neither `ext-foreign-toplevel-list-v1` nor
+    /// `wlr-foreign-toplevel-management-unstable-v1`. Emitted once, at startup,
+    /// even if the worker restarts.
+    Unsupported,
+    /// The compositor ended the protocol.
+    Finished,
+    Stop(Error),
+}
+
+/// Dispatch state for the toplevel worker.
+#[derive(Debug)]
+pub(crate) struct Toplevels {
+    registry_state: RegistryState,
+    /// Resolves toplevel outputs on publish.
+    output_state: OutputState,
+    /// Preferred, it has states, outputs, parents and requests.
+    wlr_manager: Option<ZwlrForeignToplevelManagerV1>,
+    /// Bound only without the wlr manager.
+    ext_list: Option<ExtForeignToplevelListV1>,
+    /// Toplevels as of their last `done`.
+    snapshot: ToplevelSnapshot,
+    /// Changes that wait for the `done` of their toplevel.
+    pending: Vec<Toplevel>,
+    /// The snapshot as of the last `done` or `closed`.
+    published: Arc<ToplevelSnapshot>,
+    /// Report `Unsupported` once, before any snapshot.
+    announce_unsupported: bool,
+    /// `published` changed since the last drain.
+    saw_done: bool,
+    /// Compositor answered `stop` or ended the protocol.
+    finished: bool,
+    /// `stop` has been sent, so no further request may go out.
+    stopping: bool,
+    /// The protocol ended and the application has not been told yet.
+    saw_finished: bool,
+}
+
+impl Toplevels {
+    /// The working copy of a toplevel, until its `done`
+    fn pending_mut(&mut self, id: &ObjectId) -> Option<&mut Toplevel> {
+        if let Some(index) = self
+            .pending
+            .iter()
+            .position(|toplevel| &toplevel.id.0 == id)
+        {
+            return self.pending.get_mut(index);
+        }
+        let committed = self
+            .snapshot
+            .toplevels
+            .iter()
+            .find(|toplevel| &toplevel.id.0 == id)?
+            .clone();
+        self.pending.push(committed);
+        self.pending.last_mut()
+    }
+
+    /// Apply the changes of a toplevel at its `done` and publish them.
+    fn commit(&mut self, id: &ObjectId) {
+        let Some(index) = self
+            .pending
+            .iter()
+            .position(|toplevel| &toplevel.id.0 == id)
+        else {
+            return;
+        };
+        let toplevel = self.pending.swap_remove(index);
+        match self
+            .snapshot
+            .toplevels
+            .iter_mut()
+            .find(|known| known.id == toplevel.id)
+        {
+            Some(known) => *known = toplevel,
+            None => self.snapshot.toplevels.push(toplevel),
+        }
+        self.publish();
+    }
+
+    fn closed(&mut self, id: &ObjectId, destroy: impl FnOnce()) {
+        if !self.stopping {
+            let _lifetime = self
+                .snapshot
+                .stopped
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            destroy();
+        }
+        self.pending.retain(|toplevel| &toplevel.id.0 != id);
+        let before = self.snapshot.toplevels.len();
+        self.snapshot
+            .toplevels
+            .retain(|toplevel| &toplevel.id.0 != id);
+        if self.snapshot.toplevels.len() != before {
+            self.publish();
+        }
+    }
+
+    fn finish(&mut self) {
+        self.finished = true;
+        self.snapshot.dead.store(true, Ordering::Release);
+        self.wlr_manager = None;
+        if let Some(list) = self.ext_list.take() {
+            list.destroy();
+        }
+        Arc::make_mut(&mut self.published).toplevels.clear();
+        self.saw_finished = true;
+    }
+
+    fn publish(&mut self) {
+        self.published = Arc::new(self.snapshot.clone());
+        self.refresh_published_outputs();
+        self.saw_done = true;
+    }
+
+    /// Re-resolve the published toplevels' outputs from `output_state`.
+    fn refresh_published_outputs(&mut self) {
+        for toplevel in &mut Arc::make_mut(&mut self.published).toplevels {
+            toplevel.outputs = toplevel
+                .bound_outputs
+                .iter()
+                .filter_map(|output| self.output_state.info(output))
+                .collect();
+        }
+    }
+
+    /// Republish if `output` shows a published toplevel.
+    fn output_info_changed(&mut self, output: &WlOutput) {
+        if self
+            .published
+            .toplevels
+            .iter()
+            .any(|toplevel| toplevel.bound_outputs.contains(output))
+        {
+            self.refresh_published_outputs();
+            self.saw_done = true;
+        }
+    }
+}
+
+impl Worker for Toplevels {
+    type Event = ToplevelEvent;
+    type Config = ();
+
+    fn disposition(event: &ToplevelEvent) -> Disposition {
+        match event {
+            // Carries every toplevel, so only the newest describes reality.
+            ToplevelEvent::Updated(_) => Disposition::Supersedes,
+            ToplevelEvent::Finished | ToplevelEvent::Stop(_) => Disposition::Terminal,
+            // A global registered later is still picked up, an `Updated` follows if it is.
+            ToplevelEvent::Unsupported => Disposition::Once,
+        }
+    }
+
+    fn init(
+        conn: &Connection,
+        globals: &GlobalList,
+        qh: &QueueHandle<Self>,
+        _: &(),
+    ) -> Result<Self, Error> {
+        let wlr_manager = globals
+            .bind::<ZwlrForeignToplevelManagerV1, _, _>(qh, 1..=3, ())
+            .ok();
+        let ext_list = match wlr_manager {
+            Some(_) => None,
+            None => globals
+                .bind::<ExtForeignToplevelListV1, _, _>(qh, 1..=1, ())
+                .ok(),
+        };
+        let seat = globals.bind::<WlSeat, _, _>(qh, 1..=5, ()).ok();
+        let output_state = OutputState::new(globals, qh);
+        let unsupported = wlr_manager.is_none() && ext_list.is_none();
+        let snapshot = ToplevelSnapshot {
+            toplevels: Vec::new(),
+            seat,
+            conn: conn.clone(),
+            stopped: Arc::new(Mutex::new(false)),
+            dead: Arc::new(AtomicBool::new(false)),
+        };
+        Ok(Self {
+            registry_state: RegistryState::new(globals),
+            output_state,
+            wlr_manager,
+            ext_list,
+            published: Arc::new(snapshot.clone()),
+            snapshot,
+            pending: Vec::new(),
+            announce_unsupported: unsupported,
+            saw_done: false,
+            finished: false,
+            stopping: false,
+            saw_finished: false,
+        })
+    }
+
+    fn take_events(&mut self) -> Vec<ToplevelEvent> {
+        if std::mem::take(&mut self.announce_unsupported) {
+            return vec![ToplevelEvent::Unsupported];
+        }
+        if std::mem::take(&mut self.saw_finished) {
+            self.saw_done = false;
+            return vec![ToplevelEvent::Finished];
+        }
+        if std::mem::take(&mut self.saw_done) {
+            vec![ToplevelEvent::Updated(self.published.clone())]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn reset_events(&mut self) -> Vec<ToplevelEvent> {
+        if self.published.toplevels.is_empty() {
+            return Vec::new();
+        }
+        Arc::make_mut(&mut self.published).toplevels.clear();
+        vec![ToplevelEvent::Updated(self.published.clone())]
+    }
+
+    fn stop_event(error: Error) -> ToplevelEvent {
+        ToplevelEvent::Stop(error)
+    }
+
+    fn teardown(&mut self, queue: &mut EventQueue<Self>) {
+        crate::output::release_outputs(&self.output_state);
+        let mut stopped = self
+            .snapshot
+            .stopped
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        *stopped = true;
+        self.snapshot.dead.store(true, Ordering::Release);
+
+        for toplevel in self.snapshot.toplevels.iter().chain(&self.pending) {
+            toplevel.handle.destroy();
+        }
+        self.snapshot.toplevels.clear();
+        self.pending.clear();
+        if let Some(seat) = self.snapshot.seat.take()
+            && seat.version() >= 5
+        {
+            seat.release();
+        }
+
+        self.stopping = true;
+        let stop_sent = match (self.wlr_manager.take(), self.ext_list.as_ref()) {
+            (Some(manager), _) => {
+                manager.stop();
+                true
+            }
+            (None, Some(list)) => {
+                list.stop();
+                true
+            }
+            (None, None) => false,
+        };
+        drop(stopped);
+        if stop_sent && queue.roundtrip(self).is_ok() && !self.finished {
+            let _ = queue.roundtrip(self);
+        }
+        // the list is not destroyed by `finished`, unlike the wlr manager
+        if let Some(list) = self.ext_list.take() {
+            list.destroy();
+        }
+    }
+}
+
+impl OutputHandler for Toplevels {
+    fn output_state(&mut self) -> &mut OutputState {
+        &mut self.output_state
+    }
+    fn new_output(&mut self, _: &Connection, _: &QueueHandle<Self>, output: WlOutput) {
+        self.output_info_changed(&output);
+    }
+    fn update_output(&mut self, _: &Connection, _: &QueueHandle<Self>, output: WlOutput) {
+        self.output_info_changed(&output);
+    }
+    fn output_destroyed(&mut self, _: &Connection, _: &QueueHandle<Self>, output: WlOutput) {
+        // Fallback for the case the toplevel's output_leave never lands.
+        for toplevel in self.snapshot.toplevels.iter_mut().chain(&mut self.pending) {
+            toplevel
+                .bound_outputs
+                .retain(|existing| existing != &output);
+        }
+        let mut affected = false;
+        for toplevel in &mut Arc::make_mut(&mut self.published).toplevels {
+            let before = toplevel.bound_outputs.len();
+            toplevel
+                .bound_outputs
+                .retain(|existing| existing != &output);
+            affected |= toplevel.bound_outputs.len() != before;
+        }
+        if affected {
+            self.refresh_published_outputs();
+            self.saw_done = true;
+        }
+    }
+}
+
+impl sctk::registry::RegistryHandler<Toplevels> for Toplevels {
+    /// The compositor registered a toplevel global after we connected.
+    fn new_global(
+        state: &mut Toplevels,
+        _conn: &Connection,
+        qh: &QueueHandle<Toplevels>,
+        _name: u32,
+        interface: &str,
+        _version: u32,
+    ) {
+        if state.wlr_manager.is_some() || state.ext_list.is_some() || state.finished {
+            return;
+        }
+        if interface == ZwlrForeignToplevelManagerV1::interface().name {
+            state.wlr_manager = state.registry_state.bind_one(qh, 1..=3, ()).ok();
+        } else if interface == ExtForeignToplevelListV1::interface().name {
+            state.ext_list = state.registry_state.bind_one(qh, 1..=1, ()).ok();
+        }
+    }
+}
+
+impl ProvidesRegistryState for Toplevels {
+    fn registry(&mut self) -> &mut RegistryState {
+        &mut self.registry_state
+    }
+    registry_handlers![OutputState, Toplevels];
+}
+
+delegate_registry!(Toplevels);
+delegate_dispatch2!(Toplevels);
+delegate_noop!(Toplevels: ignore WlCallback);
+delegate_noop!(Toplevels: ignore WlSeat);
+
+/// Watch the open windows of every client, for a taskbar.
+pub fn listen(connection: Connection) -> iced_futures::Subscription<ToplevelEvent> {
+    worker::listen::<Toplevels>(connection, ())
+}
@@ -1,12 +1,12 @@
 use std::os::fd::AsFd;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures::channel::mpsc::{UnboundedSender, unbounded};
 use futures::{SinkExt, StreamExt};
 use iced_futures::Subscription;
-use rustix::event::{PollFd, PollFlags};
+use rustix::event::{PollFd, PollFlags, Timespec};
 use rustix::io::Errno;
 use wayland_client::{
     Connection, Dispatch, DispatchError, EventQueue, QueueHandle,
@@ -53,13 +53,20 @@ where
     /// What this worker hands to the application.
     type Event: Send + 'static;
 
+    /// What the application configures the worker with, part of the subscription identity.
+    type Config: Clone + std::hash::Hash + Send + 'static;
+
     fn disposition(_event: &Self::Event) -> Disposition {
         Disposition::Incremental
     }
 
     /// Build the initial state
-    fn init(conn: &Connection, globals: &GlobalList, qh: &QueueHandle<Self>)
-    -> Result<Self, Error>;
+    fn init(
+        conn: &Connection,
+        globals: &GlobalList,
+        qh: &QueueHandle<Self>,
+        config: &Self::Config,
+    ) -> Result<Self, Error>;
 
     /// Drain everything queued for the app since last call.
     fn take_events(&mut self) -> Vec<Self::Event>;
@@ -69,6 +76,14 @@ where
         Vec::new()
     }
 
+    /// When [`Worker::tick`] is due, `None` waits for protocol events only.
+    fn deadline(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Called once the deadline passed.
+    fn tick(&mut self) {}
+
     /// Build the event reporting a failure, so a protocol error does not look
     /// identical to the compositor simply going quiet.
     fn stop_event(error: Error) -> Self::Event;
@@ -83,11 +98,40 @@ pub(crate) enum Disposition {
     /// Carries a change; earlier events still matter.
     Incremental,
     /// Carries whole state, so anything queued before it is stale.
-    #[cfg_attr(not(feature = "workspace"), allow(dead_code))]
+    #[cfg_attr(
+        not(any(
+            feature = "workspace",
+            feature = "toplevel",
+            feature = "output_management"
+        )),
+        allow(dead_code)
+    )]
     Supersedes,
     /// The protocol is over, deliver it, then stop the worker.
-    #[cfg_attr(not(feature = "workspace"), allow(dead_code))]
+    #[cfg_attr(
+        not(any(
+            feature = "workspace",
+            feature = "toplevel",
+            feature = "output_management"
+        )),
+        allow(dead_code)
+    )]
     Terminal,
+    /// Reported once for the whole subscription, the repeats of a restarted worker are
+    /// dropped. At most one kind of event of a worker can be.
+    #[cfg_attr(
+        not(any(
+            feature = "workspace",
+            feature = "idle",
+            feature = "toplevel",
+            feature = "capture",
+            feature = "output_management",
+            feature = "power",
+            feature = "gamma"
+        )),
+        allow(dead_code)
+    )]
+    Once,
 }
 
 impl Disposition {
@@ -159,6 +203,7 @@ enum Ended {
 /// The worker thread: rebuild the state on failure, up to a point.
 fn run<S: Worker>(
     connection: Connection,
+    config: S::Config,
     tx: UnboundedSender<S::Event>,
     stop: Arc<AtomicBool>,
     handoff: Handoff<S>,
@@ -167,7 +212,7 @@ fn run<S: Worker>(
     // It shouldn't be a problem, but if related issue created, keep this as the default
     // and expose for manual management
     for attempt in 0..=MAX_RESTARTS {
-        match attempt_once::<S>(&connection, &tx, &stop, &handoff) {
+        match attempt_once::<S>(&connection, &config, &tx, &stop, &handoff) {
             Ended::Done => return,
             Ended::Failed(error) => {
                 if stop.load(Ordering::Acquire) || tx.is_closed() {
@@ -189,6 +234,7 @@ fn run<S: Worker>(
 /// One init/dispatch/teardown cycle.
 fn attempt_once<S: Worker>(
     connection: &Connection,
+    config: &S::Config,
     tx: &UnboundedSender<S::Event>,
     stop: &Arc<AtomicBool>,
     handoff: &Handoff<S>,
@@ -201,7 +247,7 @@ fn attempt_once<S: Worker>(
     let started = (|| {
         let (globals, queue) = registry_queue_init::<S>(connection)?;
         let qh = queue.handle();
-        let state = S::init(connection, &globals, &qh)?;
+        let state = S::init(connection, &globals, &qh, config)?;
         Ok::<_, Error>((queue, qh, state))
     })();
 
@@ -228,12 +274,20 @@ fn attempt_once<S: Worker>(
             if stop.load(Ordering::Acquire) {
                 return Ended::Done;
             }
-            if let Err(error) = queue.blocking_dispatch(&mut state) {
+            let deadline = state.deadline();
+            let dispatched = match deadline {
+                Some(deadline) => dispatch_until(&mut queue, &mut state, deadline),
+                None => queue.blocking_dispatch(&mut state),
+            };
+            if let Err(error) = dispatched {
                 for event in state.take_events() {
                     let _ = tx.unbounded_send(event);
                 }
                 return Ended::Failed(error.into());
             }
+            if deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+                state.tick();
+            }
             for event in state.take_events() {
                 let terminal = S::disposition(&event) == Disposition::Terminal;
                 if tx.unbounded_send(event).is_err() || terminal {
@@ -265,11 +319,52 @@ fn attempt_once<S: Worker>(
     ended
 }
 
+/// `blocking_dispatch`, giving up at `deadline`.
+fn dispatch_until<S: Worker>(
+    queue: &mut EventQueue<S>,
+    state: &mut S,
+    deadline: Instant,
+) -> Result<usize, DispatchError> {
+    let dispatched = queue.dispatch_pending(state)?;
+    if dispatched > 0 {
+        return Ok(dispatched);
+    }
+    match queue.flush() {
+        Err(WaylandError::Io(err)) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+        Err(err) => return Err(err.into()),
+        Ok(()) => {}
+    }
+    if let Some(guard) = queue.prepare_read() {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let timeout = Timespec {
+            tv_sec: timeout.as_secs().try_into().unwrap_or(i64::MAX),
+            tv_nsec: timeout.subsec_nanos().into(),
+        };
+        let fd = guard.connection_fd();
+        let mut fds = [PollFd::new(&fd, PollFlags::IN | PollFlags::ERR)];
+        match rustix::event::poll(&mut fds, Some(&timeout)) {
+            // Timed out or interrupted, dropping the guard cancels the read.
+            Ok(0) | Err(Errno::INTR) => {}
+            Ok(_) => match guard.read() {
+                Err(WaylandError::Io(err)) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(err) => return Err(err.into()),
+                Ok(_) => {}
+            },
+            Err(errno) => return Err(WaylandError::Io(errno.into()).into()),
+        }
+    }
+    queue.dispatch_pending(state)
+}
+
 /// Run `S` as a subscription on its own event queue of `connection`.
-pub(crate) fn listen<S: Worker>(connection: Connection) -> Subscription<S::Event> {
+pub(crate) fn listen<S: Worker>(
+    connection: Connection,
+    config: S::Config,
+) -> Subscription<S::Event> {
     let connection: crate::HashConnection = connection.into();
-    Subscription::run_with(connection, |conn| {
+    Subscription::run_with((connection, config), |(conn, config)| {
         let conn = conn.clone();
+        let config = config.clone();
         iced_futures::stream::channel(
             100,
             |mut output: futures::channel::mpsc::Sender<S::Event>| async move {
@@ -286,20 +381,27 @@ pub(crate) fn listen<S: Worker>(connection: Connection) -> Subscription<S::Event
                 // reported, rather than panicking on the executor thread.
                 if let Err(error) = std::thread::Builder::new()
                     .name("iced-wayland-subscriber".into())
-                    .spawn(move || run::<S>(connection, tx, stop, handoff))
+                    .spawn(move || run::<S>(connection, config, tx, stop, handoff))
                 {
                     let _ = output.send(S::stop_event(error.into())).await;
                     return;
                 }
+                let mut reported_once = false;
+                let mut first = |event: &S::Event| {
+                    S::disposition(event) != Disposition::Once
+                        || !std::mem::replace(&mut reported_once, true)
+                };
                 while let Some(mut event) = rx.next().await {
                     while let Ok(newer) = rx.try_recv() {
-                        if !S::disposition(&newer).supersedes() && output.send(event).await.is_err()
+                        if !S::disposition(&newer).supersedes()
+                            && first(&event)
+                            && output.send(event).await.is_err()
                         {
                             return;
                         }
                         event = newer;
                     }
-                    if output.send(event).await.is_err() {
+                    if first(&event) && output.send(event).await.is_err() {
                         break;
                     }
                 }
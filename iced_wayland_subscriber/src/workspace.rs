@@ -431,7 +431,8 @@ impl Dispatch<ExtWorkspaceHandleV1, ()> for Workspaces {
 pub enum WorkspaceEvent {
     /// The workspace tree, emitted once per manager `done` event.
     Updated(Arc<WorkspaceSnapshot>),
-    /// The compositor does not implement `ext-workspace-v1`. Emitted once, at startup.
+    /// The compositor does not implement `ext-workspace-v1`. Emitted once, at startup,
+    /// even if the worker restarts.
     Unsupported,
     /// The compositor ended the protocol.
     Finished,
@@ -504,6 +505,7 @@ impl Workspaces {
 
 impl Worker for Workspaces {
     type Event = WorkspaceEvent;
+    type Config = ();
 
     fn disposition(event: &WorkspaceEvent) -> Disposition {
         match event {
@@ -513,7 +515,7 @@ impl Worker for Workspaces {
             // the worker returning. `Unsupported` is not terminal: the global
             // may still be registered later, and an `Updated` follows if it is.
             WorkspaceEvent::Finished | WorkspaceEvent::Stop(_) => Disposition::Terminal,
-            WorkspaceEvent::Unsupported => Disposition::Incremental,
+            WorkspaceEvent::Unsupported => Disposition::Once,
         }
     }
 
@@ -521,6 +523,7 @@ impl Worker for Workspaces {
         conn: &Connection,
         globals: &GlobalList,
         qh: &QueueHandle<Self>,
+        _: &(),
     ) -> Result<Self, Error> {
         // Absent is not final, compositor may register the global after connect
         let manager = globals
@@ -669,5 +672,5 @@ delegate_noop!(Workspaces: ignore WlCallback);
 
 /// Watch the compositor's workspaces.
 pub fn listen(connection: Connection) -> iced_futures::Subscription<WorkspaceEvent> {
-    worker::listen::<Workspaces>(connection)
+    worker::listen::<Workspaces>(connection, ())
 }
@@ -0,0 +1,148 @@
+//! Compose and dead key sequences, from the compose tables of the user locale
+use std::env;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStringExt;
+use std::ptr::NonNull;
+
+use xkbcommon_dl::{
+    xkb_compose_compile_flags, xkb_compose_feed_result, xkb_compose_state, xkb_compose_state_flags,
+    xkb_compose_status, xkb_compose_table, xkb_keysym_t,
+};
+
+use crate::xkb_keyboard::{XKBCH, XkbContext};
+
+/// Where a compose sequence is after a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComposeState {
+    /// a sequence started or continued, the key produces no text
+    Composing,
+    /// the sequence completed, the key produces the composed text
+    Composed,
+    /// the key does not continue the sequence, which is dropped along with the key
+    Cancelled,
+}
+
+/// The locale compose tables are looked up for, like libX11 does
+fn locale() -> CString {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .find(|locale| !locale.is_empty())
+        .unwrap_or_else(|| "C".into());
+    CString::new(locale.into_vec()).unwrap_or_else(|_| c"C".to_owned())
+}
+
+/// The compose state of one keyboard
+#[derive(Debug)]
+pub struct Compose {
+    table: NonNull<xkb_compose_table>,
+    state: NonNull<xkb_compose_state>,
+}
+
+impl Compose {
+    /// Load the compose table of the user locale, honoring `XCOMPOSEFILE` and `~/.XCompose`.
+    /// `None` if the locale has none.
+    pub fn new(context: &XkbContext) -> Option<Self> {
+        let locale = locale();
+        let table = unsafe {
+            (XKBCH.xkb_compose_table_new_from_locale)(
+                context.as_ptr(),
+                locale.as_ptr(),
+                xkb_compose_compile_flags::XKB_COMPOSE_COMPILE_NO_FLAGS,
+            )
+        };
+        let table = NonNull::new(table)?;
+        let state = unsafe {
+            (XKBCH.xkb_compose_state_new)(
+                table.as_ptr(),
+                xkb_compose_state_flags::XKB_COMPOSE_STATE_NO_FLAGS,
+            )
+        };
+        let Some(state) = NonNull::new(state) else {
+            unsafe { (XKBCH.xkb_compose_table_unref)(table.as_ptr()) };
+            return None;
+        };
+        Some(Self { table, state })
+    }
+
+    /// Feed the keysym of a key press, `None` if it has nothing to do with composing.
+    pub fn feed(&mut self, keysym: xkb_keysym_t) -> Option<ComposeState> {
+        let result = unsafe { (XKBCH.xkb_compose_state_feed)(self.state.as_ptr(), keysym) };
+        if result == xkb_compose_feed_result::XKB_COMPOSE_FEED_IGNORED {
+            return None;
+        }
+        match unsafe { (XKBCH.xkb_compose_state_get_status)(self.state.as_ptr()) } {
+            xkb_compose_status::XKB_COMPOSE_COMPOSING => Some(ComposeState::Composing),
+            xkb_compose_status::XKB_COMPOSE_COMPOSED => Some(ComposeState::Composed),
+            xkb_compose_status::XKB_COMPOSE_CANCELLED => Some(ComposeState::Cancelled),
+            xkb_compose_status::XKB_COMPOSE_NOTHING => None,
+        }
+    }
+
+    /// The text of the sequence that was just composed
+    pub fn text(&mut self) -> Option<String> {
+        let state = self.state.as_ptr();
+        let size = unsafe { (XKBCH.xkb_compose_state_get_utf8)(state, std::ptr::null_mut(), 0) };
+        let size = usize::try_from(size).ok().filter(|size| *size > 0)?;
+        // the written text is nul terminated
+        let mut buffer = vec![0u8; size + 1];
+        unsafe { (XKBCH.xkb_compose_state_get_utf8)(state, buffer.as_mut_ptr().cast(), size + 1) };
+        buffer.truncate(size);
+        String::from_utf8(buffer).ok()
+    }
+
+    /// Whether a sequence is in progress
+    pub fn is_composing(&self) -> bool {
+        let status = unsafe { (XKBCH.xkb_compose_state_get_status)(self.state.as_ptr()) };
+        status == xkb_compose_status::XKB_COMPOSE_COMPOSING
+    }
+
+    /// Drop the sequence in progress
+    pub fn reset(&mut self) {
+        unsafe { (XKBCH.xkb_compose_state_reset)(self.state.as_ptr()) }
+    }
+}
+
+impl Drop for Compose {
+    fn drop(&mut self) {
+        unsafe {
+            (XKBCH.xkb_compose_state_unref)(self.state.as_ptr());
+            (XKBCH.xkb_compose_table_unref)(self.table.as_ptr());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xkbcommon_dl::keysyms;
+
+    #[test]
+    fn dead_acute_then_e_composes_e_acute() {
+        let file = env::temp_dir().join(format!("waycrate-compose-{}", std::process::id()));
+        std::fs::write(&file, "<dead_acute> <e> : \"é\" eacute\n").unwrap();
+        // SAFETY: no other test reads or writes the environment
+        unsafe { env::set_var("XCOMPOSEFILE", &file) };
+        let context = XkbContext::new().expect("libxkbcommon");
+        let compose = Compose::new(&context);
+        std::fs::remove_file(&file).unwrap();
+        let mut compose = compose.expect("compose table");
+
+        assert_eq!(
+            compose.feed(keysyms::dead_acute),
+            Some(ComposeState::Composing)
+        );
+        assert!(compose.is_composing());
+        assert_eq!(compose.feed(keysyms::e), Some(ComposeState::Composed));
+        assert!(!compose.is_composing());
+        assert_eq!(compose.text().as_deref(), Some("é"));
+
+        compose.reset();
+        assert_eq!(
+            compose.feed(keysyms::dead_acute),
+            Some(ComposeState::Composing)
+        );
+        assert_eq!(compose.feed(keysyms::x), Some(ComposeState::Cancelled));
+        assert_eq!(compose.text(), None);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod compose;
 pub mod keyboard;
 pub mod keymap;
 pub mod xkb_keyboard;
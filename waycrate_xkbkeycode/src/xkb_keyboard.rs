@@ -1,6 +1,8 @@
 use memmap2::MmapOptions;
+use std::collections::HashMap;
 use std::sync::LazyLock;
 use std::{
+    ffi::{CStr, c_char},
     ops::Deref,
     os::fd::OwnedFd,
     ptr::{self, NonNull},
@@ -10,7 +12,7 @@ use wayland_client::{Proxy, protocol::wl_keyboard::WlKeyboard};
 
 use xkbcommon_dl::{
     self as xkb, XkbCommon, XkbCommonCompose, xkb_keycode_t, xkb_keysym_t, xkb_layout_index_t,
-    xkbcommon_compose_handle, xkbcommon_handle,
+    xkb_level_index_t, xkb_mod_mask_t, xkbcommon_compose_handle, xkbcommon_handle,
 };
 
 use xkb::{xkb_keymap, xkb_keymap_compile_flags};
@@ -19,9 +21,32 @@ pub use winit_common::xkb::{Context, KeyContext, XkbContext, XkbState};
 pub use winit_core::event::{ElementState, KeyEvent};
 
 use calloop::RegistrationToken;
+use winit_core::keyboard::SmolStr;
+
+use crate::compose::{Compose, ComposeState};
 
 pub static XKBH: LazyLock<&'static XkbCommon> = LazyLock::new(xkbcommon_handle);
 pub static XKBCH: LazyLock<&'static XkbCommonCompose> = LazyLock::new(xkbcommon_compose_handle);
+/// `None` if libxkbcommon could not be loaded
+pub static XKBEH: LazyLock<Option<XkbCommonExtra>> = LazyLock::new(|| {
+    ["libxkbcommon.so.0", "libxkbcommon.so"]
+        .into_iter()
+        .find_map(|name| unsafe { XkbCommonExtra::open(name) }.ok())
+});
+
+// What `XkbCommon` of xkbcommon-dl leaves out, from the same library
+dlib::dlopen_external_library!(XkbCommonExtra,
+functions:
+    fn xkb_keymap_layout_get_name(*mut xkb_keymap, xkb_layout_index_t) -> *const c_char,
+    fn xkb_keymap_key_get_mods_for_level(
+        *mut xkb_keymap,
+        xkb_keycode_t,
+        xkb_layout_index_t,
+        xkb_level_index_t,
+        *mut xkb_mod_mask_t,
+        usize
+    ) -> usize,
+);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RepeatInfo {
@@ -55,6 +80,14 @@ pub struct KeyboardState {
     pub keyboard: WlKeyboard,
 
     pub xkb_context: Context,
+    /// `None` if the locale has no compose table
+    pub compose: Option<Compose>,
+    /// names of the layouts of the keymap, by index
+    pub layout_names: Vec<String>,
+    /// the effective layout, `None` until the first modifiers of a keymap
+    pub layout: Option<u32>,
+    /// xkb keycodes of the keys held down, released synthetically when the focus leaves
+    pub pressed_keys: Vec<u32>,
     pub repeat_info: RepeatInfo,
     pub repeat_token: Option<RegistrationToken>,
     pub current_repeat: Option<u32>,
@@ -65,11 +98,104 @@ impl KeyboardState {
         Self {
             keyboard,
             xkb_context: Context::new().unwrap(),
+            compose: XkbContext::new()
+                .ok()
+                .and_then(|context| Compose::new(&context)),
+            layout_names: Vec::new(),
+            layout: None,
+            pressed_keys: Vec::new(),
             repeat_info: RepeatInfo::default(),
             current_repeat: None,
             repeat_token: None,
         }
     }
+
+    /// Load the keymap sent by the compositor, along with the names of its layouts.
+    pub fn set_keymap_from_fd(&mut self, fd: OwnedFd, size: usize) {
+        self.xkb_context.set_keymap_from_fd(fd, size);
+        self.layout_names = self
+            .xkb_context
+            .keymap_mut()
+            .map(|keymap| layout_names(keymap.as_ptr()))
+            .unwrap_or_default();
+        self.layout = None;
+    }
+
+    /// Record the effective layout, returns it with its name when it changed.
+    pub fn update_layout(&mut self, layout: u32) -> Option<(u32, String)> {
+        if self.layout.replace(layout) == Some(layout) {
+            return None;
+        }
+        let name = self
+            .layout_names
+            .get(layout as usize)
+            .cloned()
+            .unwrap_or_default();
+        Some((layout, name))
+    }
+
+    /// Turn a key into a [`KeyEvent`] carrying the text of compose sequences, along with where
+    /// the sequence is if the key took part in one. Repeats never compose.
+    pub fn process_key_event(
+        &mut self,
+        keycode: u32,
+        state: ElementState,
+        repeat: bool,
+    ) -> Option<(KeyEvent, Option<ComposeState>)> {
+        if !repeat {
+            self.pressed_keys.retain(|pressed| *pressed != keycode);
+            if state == ElementState::Pressed {
+                self.pressed_keys.push(keycode);
+            }
+        }
+        let mut key_context = self.xkb_context.key_context()?;
+        let keysym = key_context.state.get_one_sym_raw(keycode);
+        let mut event = key_context.process_key_event(keycode, state, repeat);
+        let Some(compose) = self
+            .compose
+            .as_mut()
+            .filter(|_| state == ElementState::Pressed && !repeat)
+        else {
+            return Some((event, None));
+        };
+        let compose_state = compose.feed(keysym);
+        let text = match compose_state {
+            Some(ComposeState::Composed) => compose.text().map(SmolStr::from),
+            Some(ComposeState::Composing | ComposeState::Cancelled) => None,
+            None => return Some((event, None)),
+        };
+        event.text = text.clone();
+        event.text_with_all_modifiers = text;
+        Some((event, compose_state))
+    }
+
+    /// Press events for the keys already held when the focus entered, from the `keys` array of
+    /// `wl_keyboard::enter`. They do not compose.
+    pub fn enter_keys(&mut self, keys: &[u8]) -> Vec<KeyEvent> {
+        // the array holds evdev keycodes in native endianness, xkb ones are offset by 8
+        self.pressed_keys = keys
+            .chunks_exact(4)
+            .map(|key| u32::from_ne_bytes([key[0], key[1], key[2], key[3]]) + 8)
+            .collect();
+        self.pressed_keys
+            .clone()
+            .into_iter()
+            .filter_map(|keycode| self.synthetic_key_event(keycode, ElementState::Pressed))
+            .collect()
+    }
+
+    /// Release events for every key still held, when the focus leaves
+    pub fn release_all_keys(&mut self) -> Vec<KeyEvent> {
+        std::mem::take(&mut self.pressed_keys)
+            .into_iter()
+            .filter_map(|keycode| self.synthetic_key_event(keycode, ElementState::Released))
+            .collect()
+    }
+
+    fn synthetic_key_event(&mut self, keycode: u32, state: ElementState) -> Option<KeyEvent> {
+        let mut key_context = self.xkb_context.key_context()?;
+        Some(key_context.process_key_event(keycode, state, false))
+    }
 }
 
 impl Drop for KeyboardState {
@@ -80,12 +206,58 @@ impl Drop for KeyboardState {
     }
 }
 
+/// The names of the layouts of `keymap`, by index, empty for a layout without one
+fn layout_names(keymap: *mut xkb_keymap) -> Vec<String> {
+    let count = unsafe { (XKBH.xkb_keymap_num_layouts)(keymap) };
+    (0..count)
+        .map(|layout| {
+            XKBEH
+                .as_ref()
+                .map(|handle| unsafe { (handle.xkb_keymap_layout_get_name)(keymap, layout) })
+                .filter(|name| !name.is_null())
+                .map(|name| {
+                    unsafe { CStr::from_ptr(name) }
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
 #[derive(Debug)]
 pub enum Error {
     /// libxkbcommon is not available
     XKBNotFound,
 }
 
+/// A key and the shift level of it producing a keysym
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyLevel {
+    pub keycode: xkb_keycode_t,
+    pub level: xkb_level_index_t,
+    /// the modifiers selecting the level, empty for the first one
+    pub mods: xkb_mod_mask_t,
+}
+
+/// The first modifier mask selecting `level` of the key, `None` if none does
+fn level_mods(
+    keymap: *mut xkb_keymap,
+    keycode: xkb_keycode_t,
+    layout: xkb_layout_index_t,
+    level: xkb_level_index_t,
+) -> Option<xkb_mod_mask_t> {
+    if level == 0 {
+        return Some(0);
+    }
+    let handle = XKBEH.as_ref()?;
+    let mut mods = 0;
+    let count = unsafe {
+        (handle.xkb_keymap_key_get_mods_for_level)(keymap, keycode, layout, level, &mut mods, 1)
+    };
+    (count > 0).then_some(mods)
+}
+
 #[derive(Debug)]
 pub struct XkbKeymap {
     keymap: NonNull<xkb_keymap>,
@@ -126,6 +298,56 @@ impl XkbKeymap {
             if count == 1 { *keysyms } else { 0 }
         }
     }
+    /// Every keysym a single key of `layout` produces, with the key and the lowest level
+    /// producing it.
+    pub fn keysym_levels(&mut self, layout: xkb_layout_index_t) -> HashMap<xkb_keysym_t, KeyLevel> {
+        let keymap = self.keymap.as_ptr();
+        let (min, max) = unsafe {
+            (
+                (XKBH.xkb_keymap_min_keycode)(keymap),
+                (XKBH.xkb_keymap_max_keycode)(keymap),
+            )
+        };
+        let mut levels: HashMap<xkb_keysym_t, KeyLevel> = HashMap::new();
+        for keycode in min..=max {
+            let count = unsafe { (XKBH.xkb_keymap_num_levels_for_key)(keymap, keycode, layout) };
+            for level in 0..count {
+                let keysym = unsafe {
+                    let mut keysyms = ptr::null();
+                    let count = (XKBH.xkb_keymap_key_get_syms_by_level)(
+                        keymap,
+                        keycode,
+                        layout,
+                        level,
+                        &mut keysyms,
+                    );
+                    if count != 1 {
+                        continue;
+                    }
+                    *keysyms
+                };
+                if levels
+                    .get(&keysym)
+                    .is_some_and(|known| known.level <= level)
+                {
+                    continue;
+                }
+                let Some(mods) = level_mods(keymap, keycode, layout, level) else {
+                    continue;
+                };
+                levels.insert(
+                    keysym,
+                    KeyLevel {
+                        keycode,
+                        level,
+                        mods,
+                    },
+                );
+            }
+        }
+        levels
+    }
+
     /// Check whether the given key repeats.
     pub fn key_repeats(&mut self, keycode: xkb_keycode_t) -> bool {
         unsafe { (XKBH.xkb_keymap_key_repeats)(self.keymap.as_ptr(), keycode) == 1 }
@@ -144,3 +366,74 @@ impl Deref for XkbKeymap {
         &self.keymap
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_names_come_from_the_keymap() {
+        let context = XkbContext::new().expect("libxkbcommon");
+        let names = xkb::xkb_rule_names {
+            rules: ptr::null(),
+            model: ptr::null(),
+            layout: c"us,ru".as_ptr(),
+            variant: ptr::null(),
+            options: ptr::null(),
+        };
+        unsafe {
+            let keymap = (XKBH.xkb_keymap_new_from_names)(
+                (*context).as_ptr(),
+                &names,
+                xkb_keymap_compile_flags::XKB_KEYMAP_COMPILE_NO_FLAGS,
+            );
+            assert!(!keymap.is_null());
+            assert_eq!(layout_names(keymap), ["English (US)", "Russian"]);
+            (XKBH.xkb_keymap_unref)(keymap);
+        }
+    }
+
+    #[test]
+    fn shifted_keysyms_are_found_with_their_modifiers() {
+        let context = XkbContext::new().expect("libxkbcommon");
+        let names = xkb::xkb_rule_names {
+            rules: ptr::null(),
+            model: ptr::null(),
+            layout: c"us".as_ptr(),
+            variant: ptr::null(),
+            options: ptr::null(),
+        };
+        let keymap = unsafe {
+            (XKBH.xkb_keymap_new_from_names)(
+                (*context).as_ptr(),
+                &names,
+                xkb_keymap_compile_flags::XKB_KEYMAP_COMPILE_NO_FLAGS,
+            )
+        };
+        let mut keymap = XkbKeymap {
+            keymap: NonNull::new(keymap).expect("us keymap"),
+        };
+        let levels = keymap.keysym_levels(0);
+        let shift = 1;
+        // <AC01>, the key of `a`
+        let a = KeyLevel {
+            keycode: 38,
+            level: 0,
+            mods: 0,
+        };
+        assert_eq!(levels.get(&0x61), Some(&a));
+        assert_eq!(
+            levels.get(&0x41),
+            Some(&KeyLevel {
+                level: 1,
+                mods: shift,
+                ..a
+            })
+        );
+        // `!` is the shifted `1`
+        assert_eq!(
+            levels.get(&0x21).map(|key| (key.keycode, key.mods)),
+            Some((10, shift))
+        );
+    }
+}